@@ -1,33 +1,588 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
 export declare class AsyncPBox {
-  generatePubkey(sk: Buffer): Promise<Buffer>
+  /**
+   * Calculates public key from secret key. Secret key of wrong length gives
+   * configuration error. Result is taken from derivation cache, when it is
+   * enabled with `pbox_cache_entries` option.
+   */
+  generatePubkey(sk: Buffer): Promise<EncrResult>
+  /**
+   * Calculates DH shared key, taking it from derivation cache, when it is
+   * enabled with `pbox_cache_entries` option. Lengths of both keys are
+   * checked before cache lookup and dispatch, with error naming wrong key.
+   */
   calcDhsharedKey(pk: Buffer, sk: Buffer): Promise<Buffer>
+  /** Wipes and drops all entries of derivation cache. */
+  clearDerivationCache(): void
+  /**
+   * Debugging aid, that calculates shared key on both sides, i.e. with
+   * `pk_b` and `sk_a`, and with `pk_a` and `sk_b`, telling with constant-time
+   * comparison whether these keys are equal.
+   */
+  dhAgrees(pkA: Buffer, skA: Buffer, pkB: Buffer, skB: Buffer): Promise<boolean>
+  /**
+   * Packs message to recipient's public key with a fresh ephemeral keypair,
+   * doing key generation, DH and packing in one worker task. Ephemeral
+   * public key should be passed to recipient together with cipher. Ephemeral
+   * key is taken from seeded generator, when it is given.
+   */
+  packEphemeral(m: Buffer, recipientPk: Buffer, workLabel: number, rng?: SeededRandom | undefined | null): Promise<EphemeralPackResult>
+  /** Opens cipher, made by `pack_ephemeral`. */
+  openEphemeral(ephemeralPk: Buffer, c: Buffer, recipientSk: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Seals message to recipient with ephemeral key, like `pack_ephemeral`,
+   * together with sender's Ed25519 signature, that binds message, ephemeral
+   * key and recipient. Signature and sender's signing public key are inside
+   * of cipher, hence only recipient learns who the sender is. Result is
+   * ephemeral public key (32 bytes), followed by cipher.
+   */
+  sealSigned(m: Buffer, recipientPk: Buffer, senderSignSk: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Opens sealed message, made by `seal_signed`, returning it with sender's
+   * signing public key, when both cipher and signature are valid. Valid
+   * signature only proves that message comes from holder of returned key,
+   * hence caller should check that it is a known sender's key.
+   */
+  sealSignedOpen(sealed: Buffer, recipientSk: Buffer, workLabel: number): Promise<SignedSealOpenResult>
+  /**
+   * Packs message once with a fresh data key, and wraps data key to every
+   * member with one ephemeral key pair, like `pack_ephemeral` does, all in
+   * one task. Member opens data key with `open_ephemeral`, using returned
+   * ephemeral public key, and then opens cipher with secret box.
+   */
+  sealToGroup(m: Buffer, memberPks: Array<Buffer>, workLabel: number): Promise<GroupSealResult>
+  /**
+   * Raw X25519 multiplication of 32-byte point by 32-byte scalar. Scalar is
+   * clamped, as in RFC 7748. Small order points, giving all-zero result, are
+   * reported as configuration error.
+   */
+  scalarmult(scalar: Buffer, point: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Raw X25519 multiplication of base point by 32-byte scalar, clamped like
+   * in `scalarmult`.
+   */
+  scalarmultBase(scalar: Buffer, workLabel: number): Promise<EncrResult>
 }
 export type JsAsyncPBox = AsyncPBox
 
 export declare class AsyncSBoxCryptor {
+  /**
+   * Returns number of tasks, that can be started under given work label now,
+   * counting tasks in progress and reserved slots. Label without tasks in
+   * progress always gets at least one slot, even when pool is saturated, so
+   * that new work queues aren't starved by busy ones.
+   */
   canStartUnderWorkLabel(workLabel: number): number
-  open(c: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Tells whether one more task can be started under given work label now,
+   * like `can_start_under_work_label` giving non-zero.
+   */
+  canStartOne(workLabel: number): boolean
+  /**
+   * Tells whether labeled operations reject, when pool is saturated for
+   * their label, as set with cryptor option `reject_when_saturated`.
+   */
+  get rejectWhenSaturated(): boolean
+  /**
+   * Turns rejection of labeled operations on saturated pool on or off, for
+   * all parts of cryptor, that share its work labels.
+   */
+  setRejectWhenSaturated(reject: boolean): void
+  /**
+   * Returns how many secret box operations with inputs of given size can run
+   * at the same time, within number of threads and memory budget, set in
+   * options. Every operation is taken to hold its input and output, i.e.
+   * twice the input size. When input doesn't fit budget, zero is returned.
+   */
+  maxConcurrentFor(inputSize: number): number
+  /**
+   * Returns times, that tasks under given work label waited for a worker,
+   * or `null`, when there are no records for the label. Records are kept
+   * until they are reset with `reset_label_wait_stats`.
+   */
+  labelWaitStats(workLabel: number): JsLabelWaitStats | null
+  /**
+   * Drops wait records of given work label, or of all labels, when label
+   * isn't given.
+   */
+  resetLabelWaitStats(workLabel?: number | undefined | null): void
+  /**
+   * Reserves a slot under given work label, if one is available, returning
+   * `null` otherwise, and while `quiesce` rejects new work. Reserved slot
+   * counts as work in progress until either `release()` is called on
+   * reservation, or reservation object is garbage collected.
+   */
+  tryReserveUnderWorkLabel(workLabel: number): JsWorkReservation | null
+  /**
+   * Reserves a slot under given work label, like
+   * `try_reserve_under_work_label`, that also counts against given key id,
+   * returning `null`, when either label has no slot, or key already has
+   * `max_in_flight` reservations (1 by default), e.g. to serialize work on
+   * a stateful key, like a ratchet. Key ids are chosen by caller, and are
+   * independent from work labels.
+   */
+  tryReserveForKey(workLabel: number, keyId: number, maxInFlight?: number | undefined | null): JsWorkReservation | null
+  /** Number of reservations in progress for given key id. */
+  keyInFlight(keyId: number): number
+  /**
+   * Opens cipher. Optional `max_plaintext_len` bounds plaintext, rejecting
+   * longer ciphers with configuration error before decryption.
+   * When `empty_on_failure` is set, failed verification gives `Ok` with empty
+   * plaintext instead of `CipherVerificationErr`. Note that this masks
+   * tampering, and should only be used where empty plaintext can't be taken
+   * for a genuine message.
+   */
+  open(c: Buffer, n: Buffer, k: Buffer, workLabel: number, maxPlaintextLen?: number | undefined | null, emptyOnFailure?: boolean | undefined | null): Promise<EncrResult>
+  /**
+   * Checks authenticator of cipher, made by `pack`, without decrypting it,
+   * so that forged ciphers are dropped without allocating for plaintext.
+   */
+  verifyOnly(c: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<boolean>
+  /**
+   * Checks authenticator of cipher in with-nonce format, using nonce from
+   * it, without decrypting, like `verify_only` does.
+   */
+  verifyFormatWN(c: Buffer, k: Buffer, workLabel: number): Promise<boolean>
+  /**
+   * Decrypts `length` bytes of plaintext, starting at `offset`, from cipher,
+   * made by `pack`, seeking in keystream, so that the rest of cipher isn't
+   * processed. This does NOT verify authenticator of cipher, hence it must
+   * be used only when integrity of cipher is checked by other means, as
+   * bytes of a forged cipher are decrypted into garbage without any error.
+   * Range longer than cryptor's `max_message_bytes` is rejected.
+   */
+  openRange(c: Buffer, n: Buffer, k: Buffer, offset: number, length: number, workLabel: number): Promise<EncrResult>
+  /**
+   * Validates and opens envelope, which is one byte of version, 24-byte
+   * nonce, cipher, made by `pack`, and 64-byte Ed25519 signature of all
+   * bytes before it, i.e. of version, nonce and cipher. Signature is checked
+   * first, and cipher is opened only when it is valid, all in one worker
+   * task. Result tells, which step failed. Version isn't interpreted.
+   */
+  validateEnvelope(envelope: Buffer, boxK: Buffer, signPk: Buffer, workLabel: number): Promise<EnvelopeOpenResult>
+  /**
+   * Opens cipher, made by `pack`, passing plaintext to `on_chunk` in chunks
+   * of `chunk_len` bytes (64KB by default), so that processing can start
+   * before all of the plaintext is in memory. Authenticator is checked over
+   * the whole cipher before the first chunk is decrypted, hence chunks are
+   * never emitted from a forged cipher, and nothing is emitted, when result
+   * is `CipherVerificationErr`. Result resolves after the last chunk is
+   * passed to callback. Like with `open`, plaintext longer than cryptor's
+   * `max_message_bytes` is rejected before any work.
+   */
+  openStreaming(c: Buffer, n: Buffer, k: Buffer, onChunk: ((err: Error | null, arg: Buffer) => any), workLabel: number, chunkLen?: number | undefined | null): Promise<StreamOpenResult>
+  /** Opens like `open`, with key given by `SecretBuffer` handle. */
+  openWithSecret(c: Buffer, n: Buffer, k: SecretBuffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Opens cipher `body`, that has no nonce prefix, with nonce `n`, supplied
+   * separately. It is same as `open`, with lengths of arguments checked
+   * before passing work to a worker thread.
+   */
+  openBodyWithNonce(body: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Opens cipher of a message with `expected_len` bytes from the start of
+   * `c`, ignoring bytes that follow it. Result tells how many bytes of `c`
+   * are consumed by opened cipher.
+   */
+  openPrefix(c: Buffer, n: Buffer, k: Buffer, expectedLen: number, workLabel: number): Promise<PrefixOpenResult>
   pack(m: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<EncrResult>
-  openFormatWN(c: Buffer, k: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Packs like `pack`, also giving time that work waited for a worker, and
+   * time of work itself, for tuning of threads and work labels.
+   */
+  packTimed(m: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<TimedEncrResult>
+  /** Opens like `open`, with timings like in `pack_timed`. */
+  openTimed(c: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<TimedEncrResult>
+  /** Packs like `pack`, with key given by `SecretBuffer` handle. */
+  packWithSecret(m: Buffer, n: Buffer, k: SecretBuffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Opens with-nonce format. Optional `max_plaintext_len` works like in
+   * `open`.
+   */
+  openFormatWN(c: Buffer, k: Buffer, workLabel: number, maxPlaintextLen?: number | undefined | null): Promise<EncrResult>
+  /**
+   * Opens a batch of with-nonce format ciphers with the same key, doing
+   * whole batch in one worker task. Every item gets its own result, so that
+   * failure of some items doesn't fail the whole batch.
+   */
+  openMany(cs: Array<Buffer>, k: Buffer, workLabel: number): Promise<Array<EncrResult>>
+  /**
+   * Copies nonces from a batch of with-nonce format ciphers, like
+   * `copy_nonce_from_format_w_n`, doing whole batch in one worker task.
+   * Every item gets its own result, with configuration error for ciphers,
+   * that are too short.
+   */
+  copyNoncesFromMany(cs: Array<Buffer>, workLabel: number): Promise<Array<EncrResult>>
+  /**
+   * Opens all blocks in a buffer with concatenated blocks, each being nonce,
+   * followed by cipher length as big-endian u32, followed by cipher, made
+   * by `pack`. Every block gets own result, and truncated trailing block
+   * gets configuration error, that ends the array.
+   */
+  openConcatenated(c: Buffer, k: Buffer, workLabel: number): Promise<Array<EncrResult>>
+  /**
+   * Opens with-nonce format like `open_format_w_n`, also returning nonce
+   * from it, for receivers that check sequencing of nonces.
+   */
+  openFormatWNWithNonce(c: Buffer, k: Buffer, workLabel: number): Promise<NonceOpenResult>
+  /**
+   * Opens cipher, trying given keys in order, in one worker task, like when
+   * cipher may be under either old, or new key during key rotation. Result
+   * has plaintext with index of the key, that opened it.
+   */
+  openWithKeys(c: Buffer, n: Buffer, keys: Array<Buffer>, workLabel: number): Promise<KeyedOpenResult>
   packFormatWN(m: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Packs like `pack`, returning cipher after `reserve_bytes` of zeros, which
+   * caller can fill with own header, without copying cipher into another
+   * buffer. Reserve can be up to 64KB.
+   */
+  packWithReserve(m: Buffer, n: Buffer, k: Buffer, reserveBytes: number, workLabel: number): Promise<EncrResult>
+  /**
+   * Packs message in with-nonce format, like `pack_format_w_n`, with a fresh
+   * random nonce. Like it, large and small messages go to blocking pool or
+   * inline, as set by cryptor's options.
+   */
+  sealWn(m: Buffer, k: Buffer, workLabel: number): Promise<EncrResult>
+  /** Packs like `pack`, returning cipher as base64url string without padding. */
+  packBase64Url(m: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<EncodedEncrResult>
+  /**
+   * Opens like `open`, returning plaintext as string. Plaintext, that isn't
+   * valid UTF-8, gives configuration error.
+   */
+  openUtf8(c: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<EncodedEncrResult>
+  /**
+   * Opens like `open_format_w_n`, returning plaintext as string. Plaintext,
+   * that isn't valid UTF-8, gives configuration error.
+   */
+  openFormatWNUtf8(c: Buffer, k: Buffer, workLabel: number): Promise<EncodedEncrResult>
+  /**
+   * Packs message in layout of libsodium's `crypto_secretbox_easy`, i.e. as
+   * 16-byte poly1305 authenticator, followed by encrypted message, which is
+   * the same layout, as `pack` gives. Unlike `pack`, empty message is
+   * allowed, giving 16-byte cipher, as libsodium does.
+   */
+  packLibsodiumEasy(m: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Opens cipher from libsodium's `crypto_secretbox_easy`, or from `pack`.
+   * Cipher of 16 bytes, which is only an authenticator, opens to an empty
+   * message.
+   */
+  openLibsodiumEasy(c: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Packs like `pack_format_w_n`, returning cipher as base64url string
+   * without padding.
+   */
+  packFormatWNBase64Url(m: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<EncodedEncrResult>
+  /**
+   * Opens with-nonce cipher `c` with `old_k`, and packs its message with
+   * `new_k` and a fresh random nonce, without message leaving worker thread.
+   */
+  rewrapFormatWN(c: Buffer, oldK: Buffer, newK: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Packs message with a fresh random nonce, giving nonce, encrypted message
+   * and 16-byte authenticator (MAC) as separate buffers, for protocols,
+   * that carry them in distinct fields.
+   */
+  packDetachedWN(m: Buffer, k: Buffer, workLabel: number): Promise<DetachedPackResult>
+  /** Opens cipher, made by `pack_detached_w_n`. */
+  openDetachedWN(detached: DetachedCipher, k: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Opens cipher `c`, made by `pack` with nonce `old_n`, and packs its
+   * message with the same key and a fresh random nonce into with-nonce
+   * format, for remediation of nonce reuse. Message doesn't leave worker
+   * thread.
+   */
+  renonce(c: Buffer, oldN: Buffer, k: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Encrypts file at `src_path` into `dst_path` in segments of given size,
+   * reading and writing files on tokio's blocking pool, so that file I/O
+   * doesn't hold workers. Result has length of encrypted content. Progress
+   * is reported in percents. Destination, that is the source file, under
+   * the same or another path, is refused.
+   */
+  encryptFile(srcPath: string, dstPath: string, k: Buffer, segmentSize: number, workLabel: number, reportProgress: ((err: Error | null, arg: number) => any)): Promise<FileEncrResult>
+  /**
+   * Decrypts file, produced by `encrypt_file`, writing content into
+   * `dst_path`. Destination file is removed, if decryption fails. Like in
+   * `encrypt_file`, destination can't be the source file.
+   */
+  decryptFile(srcPath: string, dstPath: string, k: Buffer, workLabel: number, reportProgress: ((err: Error | null, arg: number) => any)): Promise<FileEncrResult>
+  /**
+   * Derives subkey by numeric id and 8-byte context from 32-byte master key,
+   * giving the same subkeys as libsodium's crypto_kdf_derive_from_key.
+   * Subkey length should be from 16 to 64 bytes. Id is a number, as JS can't
+   * pass u64, and it should be a non-negative integer.
+   */
+  deriveSubkey(masterK: Buffer, subkeyId: number, context: Buffer, subkeyLen: number, workLabel: number): Promise<EncrResult>
+  /**
+   * Packs message in with-nonce format with a fresh random nonce, and with
+   * one-time key, derived from shared secret and message counter by
+   * HKDF-SHA512, so that exposure of one message key doesn't expose others.
+   * Counter should be a non-negative integer, and is never to be reused with
+   * the same shared secret.
+   */
+  packWithDerivedKey(sharedSecret: Buffer, counter: number, m: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Opens cipher, made by `pack_with_derived_key` with the same shared secret
+   * and counter.
+   */
+  openWithDerivedKey(sharedSecret: Buffer, counter: number, c: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Makes symmetric ratchet from initial chain key. Sender and receiver
+   * should each make ratchet from the same chain key.
+   */
+  makeRatchet(chainKey: Buffer): JsRatchet
+  /**
+   * Makes ring of given reusable output buffers, into which its `pack` and
+   * `open` write results, instead of returning new buffers.
+   */
+  makeOutputRing(buffers: Array<Buffer>): JsOutputRing
+  /**
+   * Makes sealer, that packs many messages with given key, advancing nonce
+   * for every message. Initial nonce is random.
+   */
+  makeSealer(k: Buffer): JsSealer
+  /**
+   * Makes packer of stream, that takes content in chunks, as they arrive,
+   * giving back encrypted stream in segments of given size (64KB by
+   * default). Initial nonce is random.
+   */
+  makeStreamPacker(k: Buffer, segmentSize?: number | undefined | null): JsStreamPacker
+  /**
+   * Makes opener of stream, made by `StreamPacker`, that takes encrypted
+   * stream in chunks of any size.
+   */
+  makeStreamOpener(k: Buffer): JsStreamOpener
 }
 export type JsAsyncSBoxCryptor = AsyncSBoxCryptor
 
 export declare class AsyncSigning {
+  /**
+   * Signs message. Messages of at least `signing_blocking_pool_min_bytes`
+   * are signed on tokio's blocking pool, here and in `signature_under_label`,
+   * and are verified there in `verify`.
+   */
   signature(m: Buffer, sk: Buffer): Promise<Buffer>
   verify(sig: Buffer, m: Buffer, pk: Buffer): Promise<boolean>
+  /**
+   * Signs message like `signature`, but under work label, with malformed
+   * inputs reported in result instead of being thrown.
+   */
+  signatureUnderLabel(m: Buffer, sk: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Signs message, returning signed message in layout of NaCl's
+   * `crypto_sign`, i.e. 64-byte Ed25519 signature, followed by message
+   * bytes, so that `signed.subarray(0, 64)` is the signature, that `verify`
+   * takes with `signed.subarray(64)`.
+   */
+  signAttached(m: Buffer, sk: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Opens signed message, made by `sign_attached`, or by `crypto_sign`,
+   * returning message, when signature is valid, and
+   * `SignatureVerificationErr` otherwise.
+   */
+  openAttached(signed: Buffer, pk: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Signs JSON value, canonicalized by RFC 8785 (JCS), so that signature
+   * doesn't depend on serializer's key order and whitespace. Values, that
+   * JCS can't take, like non-finite numbers, give configuration error.
+   */
+  signJson(value: any, sk: Buffer, workLabel: number): Promise<EncrResult>
+  /**
+   * Verifies signature, made by `sign_json`, of JSON value, which is
+   * canonicalized by RFC 8785 (JCS) before verification.
+   */
+  verifyJson(sig: Buffer, value: any, pk: Buffer, workLabel: number): Promise<boolean>
+  /**
+   * Signs message, bound to context of up to 255 bytes, so that signature
+   * fails verification under other contexts, as well as plain verification.
+   * Signed data is ASCII string "napi-nacl context signature", context
+   * length as one byte, context, and message.
+   */
+  signWithContext(m: Buffer, context: Buffer, sk: Buffer, workLabel: number): Promise<EncrResult>
+  /** Verifies signature, made by `sign_with_context` with the same context. */
+  verifyWithContext(sig: Buffer, m: Buffer, context: Buffer, pk: Buffer, workLabel: number): Promise<boolean>
+  /**
+   * Signs message like `signature`, returning signature as base64url string
+   * without padding.
+   */
+  signatureBase64Url(m: Buffer, sk: Buffer): Promise<string>
+  /**
+   * Verifies signature of message, given as a sequence of chunks. Ed25519
+   * passes message through sha512 twice, hence chunks are joined, but it is
+   * done on worker thread, instead of `Buffer.concat` on JS thread.
+   */
+  verifyChunks(sig: Buffer, chunks: Array<Buffer>, pk: Buffer, workLabel: number): Promise<boolean>
+  /**
+   * Verifies detached signatures of all items in one worker task, giving
+   * validity of each item. With `stop_on_first_failure`, verification stops
+   * at the first invalid item, and its index is returned. Items with
+   * malformed signatures or keys are invalid.
+   */
+  verifyDetachedBatch(items: Array<DetachedSignedItem>, stopOnFirstFailure: boolean, workLabel: number): Promise<BatchVerificationResult>
+  /**
+   * Finds which of known public keys has made given signature of message,
+   * returning index of that key, or `null`, when signature is not valid for
+   * any of them. Keys are tried in given order in one worker task.
+   */
+  identifySigner(sig: Buffer, m: Buffer, knownPks: Array<Buffer>, workLabel: number): Promise<number | null>
+  /** Verifies signature, like `verify`, also returning sha512 of message `m`. */
+  verifyWithDigest(sig: Buffer, m: Buffer, pk: Buffer, workLabel: number): Promise<VerificationWithDigest>
+  /**
+   * Generates signing keypair together with its fingerprint, which is hex of
+   * the first 16 bytes of sha512 of public key.
+   */
+  generateIdentity(seed: Buffer, workLabel: number): Promise<Identity>
+  /**
+   * Runs known-answer test vectors through signing, returning pass/fail for
+   * each vector. When no vectors are given, Ed25519 vectors from RFC 8032
+   * are used. Note that nacl refuses to sign empty messages, hence signature
+   * of vector with empty message, like the first RFC one, is only verified.
+   */
+  runSignTestVectors(vectors?: Array<SignTestVector> | undefined | null): Promise<Array<boolean>>
   generateKeypair(seed: Buffer): Promise<Keypair>
 }
 export type JsAsyncSigning = AsyncSigning
 
+export declare class BenchmarkResult {
+  opsPerSec: number
+  p50Ms: number
+  p90Ms: number
+  p99Ms: number
+  maxMs: number
+}
+export type JsBenchmarkResult = BenchmarkResult
+
 export declare class Cryptor {
-  static makeTreaded(maxNumOfThreads: number, threadName: string): Cryptor
+  /**
+   * Returns cryptor, that uses one process-wide pool of threads, made on the
+   * first call with given parameters. Later calls return cryptors with the
+   * same pool, work label accounting and event callback, and should come
+   * with the same parameters, as calls with other parameters throw. Absent
+   * options are the same as empty ones.
+   *
+   * Process-wide means that Node's worker threads get this cryptor too,
+   * and that there is one `on_event` callback for all of them. It is called
+   * on JS thread, that has set it, with events of operations from all
+   * threads, and is replaced by `on_event` from any of them. Hence, set it
+   * from one thread only, or use `make_treaded` cryptors in workers, that
+   * need their own events.
+   */
+  static shared(maxNumOfThreads: number, threadName: string, options?: CryptorOptions | undefined | null): Cryptor
+  static makeTreaded(maxNumOfThreads: number, threadName: string, options?: CryptorOptions | undefined | null): Cryptor
+  /**
+   * Name, given to all worker threads of this cryptor. Names are set when
+   * threads are started, and can't be changed afterwards. There are no
+   * worker threads in builds with `no-thread-pool` feature.
+   */
+  get threadName(): string
+  /**
+   * Handles `sbox`, `pbox` and `signing` are all alike. Every getter call
+   * returns a new handle object, which shares thread pool, work label
+   * accounting and event callback with this cryptor, hence handles can be
+   * cached. Each handle keeps thread pool alive, until both cryptor and all
+   * its handles are garbage collected.
+   */
   get sbox(): AsyncSBoxCryptor
   get pbox(): AsyncPBox
   get signing(): AsyncSigning
-  scrypt(passwd: Buffer, salt: Buffer, logN: number, r: number, p: number, dkLen: number, reportProgress: ((err: Error | null, arg: number) => any)): Promise<Buffer>
+  /**
+   * Measures throughput and latency of given operation ("pack", "open",
+   * "sign" or "scrypt") on this cryptor's threads. Operations are submitted
+   * by `concurrency` parallel lanes, `iterations` in total, with messages of
+   * `input_size` bytes.
+   */
+  benchmark(op: string, inputSize: number, iterations: number, concurrency: number): Promise<BenchmarkResult>
+  /**
+   * Makes sure that all worker threads are up, so that first operations
+   * don't pay thread start latency. Resolves when every worker has run a
+   * warmup task, hence it waits, if workers are busy.
+   */
+  warmup(): Promise<void>
+  /**
+   * Number of tasks in progress under all work labels, counting reserved
+   * slots.
+   */
+  totalTasksInFlight(): number
+  /**
+   * Returns counts of tasks in progress under work labels, counting reserved
+   * slots, as an object with labels for keys.
+   */
+  exportLabelState(): Record<string, number>
+  /**
+   * Replaces counts of tasks under work labels with given ones, like those
+   * from `export_label_state`. Imported counts are taken as work in
+   * progress by admission methods and `quiesce`, until they are replaced by
+   * another import, e.g. of an empty object. Import is refused, while there
+   * is actual work in progress, or reserved slots.
+   */
+  importLabelState(state: Record<string, number>): void
+  /**
+   * Resolves when there are no tasks in progress under work labels, nor
+   * reserved slots. New work is still accepted while waiting, and it is
+   * waited for too, hence callers should stop sending work first, or set
+   * `reject_new_work`. With it, labeled operations, started while waiting,
+   * reject with "Cryptor is quiescing" error, and reservations give `null`,
+   * till this call resolves. Work, done without work labels, like scrypt,
+   * isn't waited for.
+   */
+  quiesce(rejectNewWork?: boolean | undefined | null): Promise<void>
+  /**
+   * Sets callback, that gets an event after every operation, done under
+   * work label, with operation's name, duration and kind of result.
+   * Callback doesn't keep Node's event loop alive. Cryptors from `shared`
+   * have one callback for all worker threads, see there.
+   */
+  onEvent(cb: ((err: Error | null, arg: OpEvent) => unknown)): void
+  /**
+   * Derives key with scrypt. Its working memory, 128 * r * 2^log_n bytes,
+   * is allocated on every call, unless it is reserved on a worker with
+   * `prealloc_scrypt` for these log_n and r.
+   */
+  scrypt(passwd: Buffer, salt: Buffer, logN: number, r: number, p: number, dkLen: number, reportProgress: ((err: Error | null, arg: number) => any), minProgressIntervalMs?: number | undefined | null): Promise<Buffer>
+  /**
+   * Derives several keys with one scrypt run, which derives material of all
+   * keys' total length, and splits it into keys of lengths, given in
+   * `key_lens`, in that order.
+   */
+  scryptDerive(passwd: Buffer, salt: Buffer, logN: number, r: number, p: number, keyLens: Array<number>, reportProgress: ((err: Error | null, arg: number) => any), minProgressIntervalMs?: number | undefined | null): Promise<Array<Buffer>>
+  /**
+   * Reserves working memory of scrypt with given log_n and r, i.e. 128 * r *
+   * 2^log_n bytes, on every worker, replacing memory, reserved before.
+   * Following scrypt calls with these log_n and r reuse it, one call at a
+   * time on each worker, and other calls allocate their own memory. Nothing
+   * is shared between workers, and memory is wiped after every call. This
+   * doesn't help scrypt on blocking pool, set with `scrypt_on_blocking_pool`.
+   * Returns error, when memory can't be allocated on some worker.
+   */
+  preallocScrypt(logN: number, r: number): Promise<void>
+  /**
+   * Derives signing keypair from passphrase, with scrypt giving 32-byte seed
+   * for `generate_keypair`, all in one worker task, under given work label.
+   * Seed is wiped, and never gets to JS. Same passphrase, salt and
+   * parameters always give the same keypair.
+   */
+  keypairFromPassphrase(passwd: Buffer, salt: Buffer, logN: number, r: number, p: number, workLabel: number): Promise<Keypair>
+  /**
+   * Does scrypt with a freshly generated random salt of `salt_len` bytes
+   * (16 by default), returning both salt and derived key.
+   */
+  scryptWithRandomSalt(passwd: Buffer, logN: number, r: number, p: number, dkLen: number, saltLen: number | undefined | null, reportProgress: ((err: Error | null, arg: number) => any), minProgressIntervalMs?: number | undefined | null): Promise<ScryptResult>
+  /**
+   * Derives key like `scrypt`, without progress reports, giving time that
+   * work waited for a worker, and time of work itself.
+   */
+  scryptTimed(passwd: Buffer, salt: Buffer, logN: number, r: number, p: number, dkLen: number): Promise<TimedBuffer>
+  /**
+   * Picks scrypt parameters, that take roughly given time per hash on this
+   * machine, using at most given memory, i.e. 128 * r * 2^log_n bytes. This
+   * runs a probe hash on a worker, and with r = 8 picks the largest log_n
+   * within both time and memory, raising p, when memory caps log_n first.
+   * Both target time and memory cap should be finite positive numbers.
+   */
+  calibrateScrypt(targetMs: number, maxMemoryBytes: number): Promise<ScryptParams>
 }
 export type JsCryptor = Cryptor
 
@@ -37,20 +592,474 @@ export declare class Keypair {
 }
 export type JsKeypair = Keypair
 
+/**
+ * Ring of reusable output buffers, registered by caller, who keeps own
+ * references to them. Every operation writes its result into the next free
+ * buffer, that is long enough, and returns its index with result length, so
+ * that result is `buffers[index].subarray(0, len)`. Buffer stays taken,
+ * until it is given back with `release(index)`. Operations still allocate
+ * result inside of nacl, copying it into ring buffer, but make no new JS
+ * buffers, and hence add no garbage on JS side.
+ */
+export declare class OutputRing {
+  /** Packs message like secret box `pack`, writing cipher into ring buffer. */
+  pack(m: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<RingResult>
+  /** Opens cipher like secret box `open`, writing plaintext into ring buffer. */
+  open(c: Buffer, n: Buffer, k: Buffer, workLabel: number): Promise<RingResult>
+  /** Gives buffer back to ring, for following operations to write into. */
+  release(index: number): void
+  /** Number of buffers, that aren't taken. */
+  get available(): number
+}
+export type JsOutputRing = OutputRing
+
+/**
+ * Symmetric ratchet, where every message is packed with its own key, derived
+ * from a chain key, which is advanced with every message.
+ */
+export declare class Ratchet {
+  /**
+   * Packs message with key of the next message in chain, returning cipher
+   * together with message's index, needed by receiver to open it.
+   */
+  packNext(m: Buffer, workLabel: number): Promise<RatchetCipher>
+  /**
+   * Opens cipher of message at given index. Keys of messages, skipped by
+   * this call, are kept for opening them later, when they come out of order.
+   */
+  openAt(c: Buffer, index: number, workLabel: number): Promise<EncrResult>
+}
+export type JsRatchet = Ratchet
+
+/**
+ * Packs messages with one key in with-nonce format, taking a new nonce for
+ * every message, so that nonces are never reused within sealer's lifetime.
+ * Opening needs no state, as with-nonce format carries nonce with cipher.
+ */
+export declare class Sealer {
+  /** Packs message in with-nonce format with the next nonce. */
+  next(m: Buffer, workLabel: number): Promise<EncrResult>
+  /** Number of messages, packed by this sealer. */
+  get count(): number
+}
+export type JsSealer = Sealer
+
+/**
+ * Secret, like a key, kept in Rust memory, and passed into crypto calls by
+ * handle, so that it doesn't have to be a JS buffer at call time. Bytes are
+ * wiped when handle is garbage collected and no calls use them.
+ */
+export declare class SecretBuffer {
+  /** Copies bytes of given buffer into secret storage, and wipes the buffer. */
+  static fromBuffer(src: Buffer): SecretBuffer
+  /**
+   * Makes secret with random bytes, which never exist in JS memory. When
+   * seeded generator is given, bytes are taken from it instead.
+   */
+  static random(len: number, rng?: JsSeededRandom | undefined | null): SecretBuffer
+  get length(): number
+}
+export type JsSecretBuffer = SecretBuffer
+
+/**
+ * Generator of reproducible "random" bytes from a high-entropy seed, for
+ * deterministic provisioning of many keys from one master secret, and for
+ * tests. Same seed gives the same sequence of outputs, hence seed should be
+ * kept as secret as all keys, made from it. It must not be used for nonces,
+ * which should never repeat.
+ */
+export declare class SeededRandom {
+  /** Makes generator from seed of at least 32 bytes. */
+  static fromSeed(seed: Buffer): SeededRandom
+  nextBytes(len: number): Buffer
+}
+export type JsSeededRandom = SeededRandom
+
+/**
+ * Opener of stream, made by `StreamPacker`, that is used like packer, taking
+ * chunks of encrypted stream, and resolving with content of segments, that
+ * are complete and verified. Segment, that may be the last one, is held till
+ * either more bytes come, or `finish` is called, so that truncated stream
+ * fails in `finish`, with segments before it already given out.
+ */
+export declare class StreamOpener {
+  /** Takes next chunk of encrypted stream. */
+  push(chunk: Buffer, workLabel: number): Promise<SegmentsResult>
+  /**
+   * Ends stream, resolving with content of its last segment, or failing,
+   * when stream is truncated.
+   */
+  finish(workLabel: number): Promise<SegmentsResult>
+}
+export type JsStreamOpener = StreamOpener
+
+/**
+ * Packer of stream, which content comes in chunks. Every call resolves with
+ * encrypted segments, that are complete with it, possibly none, and `finish`
+ * gives the rest. Calls should go one after another, like they do from
+ * `transform` and `flush` of Node's `Transform` stream, which calls back
+ * after pushing resolved chunks, and hence stream's backpressure holds work
+ * here too. Overlapping call gets configuration error. Packer can't be used
+ * after it fails or finishes.
+ */
+export declare class StreamPacker {
+  /** Takes next chunk of content. */
+  push(chunk: Buffer, workLabel: number): Promise<SegmentsResult>
+  /** Ends stream, resolving with its last segment. */
+  finish(workLabel: number): Promise<SegmentsResult>
+}
+export type JsStreamPacker = StreamPacker
+
+export declare class WorkReservation {
+  get workLabel(): number
+  /** Key id, when reservation is made with `try_reserve_for_key`. */
+  get keyId(): number | null
+  release(): void
+}
+export type JsWorkReservation = WorkReservation
+
+export interface Algorithms {
+  sbox: SBoxAlgorithm
+  pbox: PBoxAlgorithm
+  signing: SigningAlgorithm
+}
+
+/** Returns parameters of all algorithms, also given by individual constants. */
+export declare function algorithms(): Algorithms
+
+export type BatchVerificationResult =
+  | { type: 'Verified', valid: Array<boolean> }
+  | { type: 'FirstFailure', index: number }
+
+/**
+ * Serializes JSON value in canonical form of RFC 8785 (JCS), which is what
+ * `sign_json` signs.
+ */
+export declare function canonicalJson(value: any): string
+
+export interface Capabilities {
+  secretbox: boolean
+  box: boolean
+  sign: boolean
+  scrypt: boolean
+  x25519Scalarmult: boolean
+  secretstream: boolean
+  aead: boolean
+  /**
+   * Whether crypto work is done on cryptor's own worker threads. Builds
+   * with `no-thread-pool` feature start no threads of their own, yet work
+   * still runs off JS thread, on the single worker of napi's async runtime,
+   * one call at a time.
+   */
+  threadPool: boolean
+  scryptSaltReuseCheck: boolean
+  debugArgChecks: boolean
+}
+
+/**
+ * Returns which primitives and build features are available in this build,
+ * for feature detection at runtime.
+ */
+export declare function capabilities(): Capabilities
+
 export declare function copyNonceFromFormatWN(c: Buffer): Buffer
 
+/** Reads counter from nonce, made by `nonce_from_counter_be`. */
+export declare function counterFromNonceBe(n: Buffer, offset: number): bigint
+
+/** Reads counter from nonce, made by `nonce_from_counter_le`. */
+export declare function counterFromNonceLe(n: Buffer, offset: number): bigint
+
+export interface CryptorOptions {
+  /**
+   * Maximum length of message in secret box operations. Longer messages and
+   * ciphers are rejected with configuration error, without being passed to
+   * worker threads. There is no limit by default.
+   */
+  maxMessageBytes?: number
+  /**
+   * Memory, in bytes, that concurrent secret box operations may take, used
+   * by `max_concurrent_for`. There is no budget by default.
+   */
+  memoryBudgetBytes?: number
+  /**
+   * Expected number of work labels, with work in progress at the same time,
+   * for which label map is allocated upfront, so that it isn't resized under
+   * load.
+   */
+  expectedLabels?: number
+  /**
+   * Maximum length of key, derived by scrypt. Default is 64KB. Scrypt
+   * options are ignored in builds without `scrypt` feature.
+   */
+  maxScryptDkLen?: number
+  /**
+   * Secret box `pack`, `open` and their with-nonce format variants, with
+   * messages or ciphers of at least this many bytes, are done on tokio's
+   * blocking pool, so that worker threads stay responsive for small
+   * operations. Such work still counts under its work label, in
+   * `can_start_under_work_label`, though it doesn't take a worker.
+   * By default all work is done on workers.
+   */
+  blockingPoolMinBytes?: number
+  /**
+   * Size of LRU cache of results of pbox `generate_pubkey` and
+   * `calc_dhshared_key`, which are found by SHA-512 of inputs. Cache is off
+   * by default. Note that it keeps DH shared keys, which are secret, in
+   * memory for longer, until they are evicted or `clear_derivation_cache`
+   * is called, and that hits resolve faster than misses, telling whether
+   * given keys were used recently.
+   */
+  pboxCacheEntries?: number
+  /**
+   * Secret box `pack`, `open` and their with-nonce format variants, as well
+   * as signing `signature`, `verify` and `signature_under_label`, with
+   * messages or ciphers shorter than this many bytes, are done right on JS
+   * thread, inside of async call, as dispatch to worker costs more than
+   * crypto on small inputs. Such work still counts under its work label
+   * while it runs. By default all work is dispatched.
+   */
+  inlineBelowBytes?: number
+  /**
+   * Signing `signature`, `verify` and `signature_under_label`, with messages
+   * of at least this many bytes, are done on tokio's blocking pool, like
+   * above, so that occasional large signings don't hold up workers.
+   */
+  signingBlockingPoolMinBytes?: number
+  /** When set, scrypt is done on tokio's blocking pool, counting like above. */
+  scryptOnBlockingPool?: boolean
+  /**
+   * Maximum rate of intermediate progress reports of all scrypt operations
+   * of cryptor together, per second. Reports above it are dropped, while
+   * final 100% report of every operation is always delivered. There is no
+   * limit by default.
+   */
+  maxScryptProgressReportsPerSec?: number
+  /**
+   * When set, labeled operations reject with "Pool saturated" error right
+   * away, when `can_start_under_work_label` gives zero for their label,
+   * instead of waiting for a worker. Operations under reserved slots count
+   * as new tasks, and can be rejected too. Mode can be changed later with
+   * `set_reject_when_saturated`. It is off by default.
+   */
+  rejectWhenSaturated?: boolean
+  /**
+   * When set, operations reject with errors, named after variants
+   * `CipherVerificationErr` and `SignatureVerificationErr`, instead of
+   * resolving with these variants. Error messages end with work label of
+   * failed operation, like ", under work label 7". Batch operations, like
+   * `open_many`, still give per-item results.
+   */
+  throwOnVerificationFailure?: boolean
+}
+
+/** Secret box cipher with nonce and authenticator (MAC) kept apart. */
+export interface DetachedCipher {
+  nonce: Buffer
+  cipher: Buffer
+  mac: Buffer
+}
+
+export type DetachedPackResult =
+  | { type: 'Ok', nonce: Buffer, cipher: Buffer, mac: Buffer }
+  | { type: 'ConfigurationErr', field0: string }
+
+export interface DetachedSignedItem {
+  sig: Buffer
+  m: Buffer
+  pk: Buffer
+}
+
+export type EncodedEncrResult =
+  | { type: 'Ok', field0: string }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'SignatureVerificationErr' }
+  | { type: 'ConfigurationErr', field0: string }
+
 export type EncrResult =
   | { type: 'Ok', field0: Buffer }
   | { type: 'CipherVerificationErr' }
   | { type: 'SignatureVerificationErr' }
   | { type: 'ConfigurationErr', field0: string }
 
+/** Result of `validate_envelope`, with variant, telling which step failed. */
+export type EnvelopeOpenResult =
+  | { type: 'Ok', plaintext: Buffer, version: number }
+  | { type: 'ParseErr', field0: string }
+  | { type: 'SignatureVerificationErr' }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'ConfigurationErr', field0: string }
+
+export type EphemeralPackResult =
+  | { type: 'Ok', ephemeralPk: Buffer, cipher: Buffer }
+  | { type: 'ConfigurationErr', field0: string }
+
+/**
+ * Makes public identity bundle from Ed25519 signing public key, deriving
+ * X25519 box key from it, like libsodium's
+ * crypto_sign_ed25519_pk_to_curve25519.
+ */
+export declare function exportIdentityBundle(signingPk: Buffer): IdentityBundle
+
+export type FileEncrResult =
+  | { type: 'Ok', field0: number }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'ConfigurationErr', field0: string }
+
+/**
+ * Returns length of with-nonce cipher of message with given length. Layout of
+ * such cipher is nonce (`SBOX_NONCE_LENGTH` bytes), followed by poly MAC
+ * (`SBOX_POLY_LENGTH` bytes), followed by encrypted message, that has the
+ * same length as message. Empty messages aren't packed, giving error here.
+ */
+export declare function formatWNLenFor(plaintextLen: number): number
+
+/**
+ * Returns length of message in with-nonce cipher of given length, inverting
+ * `format_w_n_len_for`. Length, too short for nonce, poly MAC and non-empty
+ * message, gives error.
+ */
+export declare function formatWNPlaintextLenFor(cipherLen: number): number
+
+export interface GroupMemberKey {
+  pk: Buffer
+  wrappedKey: Buffer
+}
+
+export type GroupSealResult =
+  | { type: 'Ok', cipher: Buffer, nonce: Buffer, ephemeralPk: Buffer, members: Array<GroupMemberKey> }
+  | { type: 'ConfigurationErr', field0: string }
+
+export interface Identity {
+  skey: Buffer
+  pkey: Buffer
+  fingerprint: string
+}
+
+/**
+ * Public keys of identity, ready for publishing, with signing key, box key
+ * of the same secret, and fingerprint of signing key, like in `Identity`.
+ */
+export interface IdentityBundle {
+  signingPk: Buffer
+  boxPk: Buffer
+  fingerprint: string
+}
+
+/** Key, imported from JSON form, with its key id, if there is one. */
+export interface ImportedKey {
+  k: Buffer
+  kid?: string
+  isSecret: boolean
+}
+
+/**
+ * Key in JSON form, with `use` being either "secret", or "public", `alg`
+ * being JWK name of algorithm, and key bytes `k` in base64url without
+ * padding. Key id `kid` is optional.
+ */
+export interface JsonKey {
+  use: string
+  alg: string
+  kid?: string
+  k: string
+}
+
+export type KeyedOpenResult =
+  | { type: 'Ok', plaintext: Buffer, keyIndex: number }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'ConfigurationErr', field0: string }
+
+/**
+ * Takes key from JSON form, checking that it is for expected algorithm, and
+ * that it has proper length, keeping key id.
+ */
+export declare function keyFromJwk(jwk: JsonKey, expectedAlg: string): ImportedKey
+
+/**
+ * Compares keys in constant time, and is meant for secret key material.
+ * Both arrays should have one of key lengths, used in this module, i.e. 32
+ * or 64 bytes. Keys of different lengths are not equal.
+ */
+export declare function keysEqual(a: Buffer, b: Buffer): boolean
+
+export interface LabelWaitStats {
+  /** Number of tasks, which waits are recorded. */
+  count: number
+  avgMs: number
+  maxMs: number
+}
+
+/**
+ * Makes 24-byte nonce with counter written as 8-byte big-endian unsigned
+ * integer at bytes `offset..offset+8`. Other bytes are taken from `base`
+ * nonce, when it is given, or are zeros.
+ */
+export declare function nonceFromCounterBe(counter: bigint, offset: number, base?: Buffer | undefined | null): Buffer
+
+/**
+ * Makes 24-byte nonce with counter written as 8-byte little-endian unsigned
+ * integer at bytes `offset..offset+8`, like libsodium's `sodium_increment`
+ * counts, when offset is 0. Other bytes are taken from `base` nonce, when it
+ * is given, or are zeros. Nonces of `Sealer` and of file encryption have
+ * this layout with zero offset.
+ */
+export declare function nonceFromCounterLe(counter: bigint, offset: number, base?: Buffer | undefined | null): Buffer
+
+export type NonceOpenResult =
+  | { type: 'Ok', plaintext: Buffer, nonce: Buffer }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'ConfigurationErr', field0: string }
+
+export interface OpEvent {
+  op: string
+  workLabel: number
+  durationMs: number
+  /** `Ok`, or name of error, like `CipherVerificationErr`. */
+  resultKind: string
+}
+
 export const PBOX_JWK_ALG_NAME: string
 
 export const PBOX_KEY_LENGTH: number
 
+export interface PBoxAlgorithm {
+  jwkAlgName: string
+  keyLength: number
+  nonceLength: number
+  polyLength: number
+}
+
 export declare function plusFive(x: number): number
 
+export type PrefixOpenResult =
+  | { type: 'Ok', m: Buffer, consumed: number }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'ConfigurationErr', field0: string }
+
+export interface RatchetCipher {
+  cipher: Buffer
+  index: number
+}
+
+export type RingResult =
+  | { type: 'Ok', index: number, len: number }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'NoBufferAvailable' }
+  | { type: 'ConfigurationErr', field0: string }
+
+/**
+ * Tells, with constant-time comparison, whether two nonces, kept apart from
+ * their ciphers, are equal, for audits of nonce reuse under one key.
+ */
+export declare function sameNonce(n1: Buffer, n2: Buffer): boolean
+
+/**
+ * Tells, like `same_nonce`, whether two with-nonce ciphers have the same
+ * nonce in them.
+ */
+export declare function sameNonceWN(c1: Buffer, c2: Buffer): boolean
+
 export const SBOX_JWK_ALG_NAME: string
 
 export const SBOX_KEY_LENGTH: number
@@ -59,6 +1068,49 @@ export const SBOX_NONCE_LENGTH: number
 
 export const SBOX_POLY_LENGTH: number
 
+export interface SBoxAlgorithm {
+  jwkAlgName: string
+  keyLength: number
+  nonceLength: number
+  polyLength: number
+}
+
+/** Turns secret box key into JSON form with optional key id. */
+export declare function sboxKeyToJwk(k: Buffer, kid?: string | undefined | null): JsonKey
+
+/** Parameters of scrypt, picked by `calibrate_scrypt`. */
+export interface ScryptParams {
+  logN: number
+  r: number
+  p: number
+}
+
+export interface ScryptResult {
+  salt: Buffer
+  derivedKey: Buffer
+}
+
+export type SegmentsResult =
+  | { type: 'Ok', chunks: Array<Buffer> }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'ConfigurationErr', field0: string }
+
+/**
+ * Makes short authentication string of given number of decimal digits, up
+ * to 18, from two public keys, for comparison by people over another
+ * channel. Keys are put in lexicographic order, so that both parties get the
+ * same string, whichever key is theirs. Digits are the first 16 bytes of
+ * SHA-512("napi-nacl short auth string" || smaller pk || larger pk), as a
+ * big-endian number, modulo 10^digits, with leading zeros.
+ */
+export declare function shortAuthString(pkA: Buffer, pkB: Buffer, digits: number): string
+
+export type SignedSealOpenResult =
+  | { type: 'Ok', plaintext: Buffer, senderPk: Buffer }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'SignatureVerificationErr' }
+  | { type: 'ConfigurationErr', field0: string }
+
 export const SIGNING_JWK_ALG_NAME: string
 
 export const SIGNING_PUBLIC_KEY_LENGTH: number
@@ -66,3 +1118,89 @@ export const SIGNING_PUBLIC_KEY_LENGTH: number
 export const SIGNING_SECRET_KEY_LENGTH: number
 
 export const SIGNING_SEED_LENGTH: number
+
+export interface SigningAlgorithm {
+  jwkAlgName: string
+  seedLength: number
+  secretKeyLength: number
+  publicKeyLength: number
+}
+
+export interface SigningJsonKeys {
+  skey: JsonKey
+  pkey: JsonKey
+}
+
+/**
+ * Turns signing secret key into JSON forms of secret and public keys, both
+ * with the same optional key id. Public key is taken from the last 32 bytes
+ * of secret key.
+ */
+export declare function signingKeypairToJwk(sk: Buffer, kid?: string | undefined | null): SigningJsonKeys
+
+/**
+ * Signs message on calling thread, without cryptor, for occasional small
+ * signing work.
+ */
+export declare function signSync(m: Buffer, sk: Buffer): Buffer
+
+export interface SignTestVector {
+  seed: Buffer
+  pkey: Buffer
+  m: Buffer
+  sig: Buffer
+}
+
+/**
+ * Splits with-nonce cipher into nonce and body, i.e. cipher without nonce,
+ * that can go into `open`. Cipher, too short to have nonce and non-empty
+ * body, gives configuration error.
+ */
+export declare function splitFormatWN(c: Buffer): SplitFormatWNResult
+
+export type SplitFormatWNResult =
+  | { type: 'Ok', nonce: Buffer, body: Buffer }
+  | { type: 'ConfigurationErr', field0: string }
+
+export type StreamOpenResult =
+  | { type: 'Ok', plaintextLen: number }
+  | { type: 'CipherVerificationErr' }
+  | { type: 'ConfigurationErr', field0: string }
+
+export interface TimedBuffer {
+  result: Buffer
+  queuedMs: number
+  computeMs: number
+  immediate: boolean
+}
+
+/**
+ * Result of operation with time it waited for a worker, and time of work.
+ * Flag `immediate` tells whether there was an idle worker, when operation
+ * was submitted, i.e. whether it wasn't queued behind other work.
+ */
+export interface TimedEncrResult {
+  result: EncrResult
+  queuedMs: number
+  computeMs: number
+  immediate: boolean
+}
+
+export interface VerificationWithDigest {
+  valid: boolean
+  digest: Buffer
+}
+
+/**
+ * Verifies signature on calling thread, without cryptor, for occasional
+ * verification of small messages.
+ */
+export declare function verifySync(sig: Buffer, m: Buffer, pk: Buffer): boolean
+
+/**
+ * Zeroes bytes of given buffer in place, for clearing sensitive data, like
+ * decrypted secrets, without waiting for GC. Only this buffer's memory is
+ * wiped: copies, made in JS, e.g. strings decoded from it, or other buffers,
+ * sliced with copy, stay intact. Views into the same memory get zeroes.
+ */
+export declare function wipeBuffer(buf: Buffer): void
@@ -582,19 +582,58 @@ module.exports.AsyncSBoxCryptor = nativeBinding.AsyncSBoxCryptor
 module.exports.JsAsyncSBoxCryptor = nativeBinding.JsAsyncSBoxCryptor
 module.exports.AsyncSigning = nativeBinding.AsyncSigning
 module.exports.JsAsyncSigning = nativeBinding.JsAsyncSigning
+module.exports.BenchmarkResult = nativeBinding.BenchmarkResult
+module.exports.JsBenchmarkResult = nativeBinding.JsBenchmarkResult
 module.exports.Cryptor = nativeBinding.Cryptor
 module.exports.JsCryptor = nativeBinding.JsCryptor
 module.exports.Keypair = nativeBinding.Keypair
 module.exports.JsKeypair = nativeBinding.JsKeypair
+module.exports.OutputRing = nativeBinding.OutputRing
+module.exports.JsOutputRing = nativeBinding.JsOutputRing
+module.exports.Ratchet = nativeBinding.Ratchet
+module.exports.JsRatchet = nativeBinding.JsRatchet
+module.exports.Sealer = nativeBinding.Sealer
+module.exports.JsSealer = nativeBinding.JsSealer
+module.exports.SecretBuffer = nativeBinding.SecretBuffer
+module.exports.JsSecretBuffer = nativeBinding.JsSecretBuffer
+module.exports.SeededRandom = nativeBinding.SeededRandom
+module.exports.JsSeededRandom = nativeBinding.JsSeededRandom
+module.exports.StreamOpener = nativeBinding.StreamOpener
+module.exports.JsStreamOpener = nativeBinding.JsStreamOpener
+module.exports.StreamPacker = nativeBinding.StreamPacker
+module.exports.JsStreamPacker = nativeBinding.JsStreamPacker
+module.exports.WorkReservation = nativeBinding.WorkReservation
+module.exports.JsWorkReservation = nativeBinding.JsWorkReservation
+module.exports.algorithms = nativeBinding.algorithms
+module.exports.canonicalJson = nativeBinding.canonicalJson
+module.exports.capabilities = nativeBinding.capabilities
 module.exports.copyNonceFromFormatWN = nativeBinding.copyNonceFromFormatWN
+module.exports.counterFromNonceBe = nativeBinding.counterFromNonceBe
+module.exports.counterFromNonceLe = nativeBinding.counterFromNonceLe
+module.exports.exportIdentityBundle = nativeBinding.exportIdentityBundle
+module.exports.formatWNLenFor = nativeBinding.formatWNLenFor
+module.exports.formatWNPlaintextLenFor = nativeBinding.formatWNPlaintextLenFor
+module.exports.keyFromJwk = nativeBinding.keyFromJwk
+module.exports.keysEqual = nativeBinding.keysEqual
+module.exports.nonceFromCounterBe = nativeBinding.nonceFromCounterBe
+module.exports.nonceFromCounterLe = nativeBinding.nonceFromCounterLe
 module.exports.PBOX_JWK_ALG_NAME = nativeBinding.PBOX_JWK_ALG_NAME
 module.exports.PBOX_KEY_LENGTH = nativeBinding.PBOX_KEY_LENGTH
 module.exports.plusFive = nativeBinding.plusFive
+module.exports.sameNonce = nativeBinding.sameNonce
+module.exports.sameNonceWN = nativeBinding.sameNonceWN
 module.exports.SBOX_JWK_ALG_NAME = nativeBinding.SBOX_JWK_ALG_NAME
 module.exports.SBOX_KEY_LENGTH = nativeBinding.SBOX_KEY_LENGTH
 module.exports.SBOX_NONCE_LENGTH = nativeBinding.SBOX_NONCE_LENGTH
 module.exports.SBOX_POLY_LENGTH = nativeBinding.SBOX_POLY_LENGTH
+module.exports.sboxKeyToJwk = nativeBinding.sboxKeyToJwk
+module.exports.shortAuthString = nativeBinding.shortAuthString
 module.exports.SIGNING_JWK_ALG_NAME = nativeBinding.SIGNING_JWK_ALG_NAME
 module.exports.SIGNING_PUBLIC_KEY_LENGTH = nativeBinding.SIGNING_PUBLIC_KEY_LENGTH
 module.exports.SIGNING_SECRET_KEY_LENGTH = nativeBinding.SIGNING_SECRET_KEY_LENGTH
 module.exports.SIGNING_SEED_LENGTH = nativeBinding.SIGNING_SEED_LENGTH
+module.exports.signingKeypairToJwk = nativeBinding.signingKeypairToJwk
+module.exports.signSync = nativeBinding.signSync
+module.exports.splitFormatWN = nativeBinding.splitFormatWN
+module.exports.verifySync = nativeBinding.verifySync
+module.exports.wipeBuffer = nativeBinding.wipeBuffer
@@ -0,0 +1,178 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Self-profiling of cryptor's pool, used to empirically pick number of
+//! threads and work-label strategies on a target machine.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use crate::pool::CryptoPool;
+use std::sync::Arc;
+use std::time::{ Duration, Instant };
+
+/// Scrypt parameters used in benchmark. These are light, so that benchmark
+/// shows pool's behaviour, rather than stalls for seconds.
+#[cfg(feature = "scrypt")]
+const BENCH_SCRYPT_LOG_N: u8 = 10;
+#[cfg(feature = "scrypt")]
+const BENCH_SCRYPT_R: usize = 8;
+#[cfg(feature = "scrypt")]
+const BENCH_SCRYPT_P: usize = 1;
+
+#[derive(Clone, Copy)]
+enum BenchOp {
+  Pack,
+  Open,
+  Sign,
+  #[cfg(feature = "scrypt")]
+  Scrypt
+}
+
+impl BenchOp {
+
+  fn parse(op: &str) -> Result<Self> {
+    match op {
+      "pack" => Ok(BenchOp::Pack),
+      "open" => Ok(BenchOp::Open),
+      "sign" => Ok(BenchOp::Sign),
+      #[cfg(feature = "scrypt")]
+      "scrypt" => Ok(BenchOp::Scrypt),
+      _ => Err(Error::from_reason(format!(
+        "Unknown benchmark operation '{}', expected one of pack, open, sign, scrypt", op
+      )))
+    }
+  }
+
+}
+
+/// Inputs, prepared once and shared by all benchmark lanes.
+struct BenchInputs {
+  op: BenchOp,
+  m: Vec<u8>,
+  c: Vec<u8>,
+  n: [u8; nacl::secret_box::NONCE_LENGTH],
+  k: [u8; nacl::secret_box::KEY_LENGTH],
+  sk: Vec<u8>
+}
+
+impl BenchInputs {
+
+  fn prepare(op: BenchOp, input_size: usize) -> Result<Self> {
+    let m = vec![7u8; input_size];
+    let n = [3u8; nacl::secret_box::NONCE_LENGTH];
+    let k = [5u8; nacl::secret_box::KEY_LENGTH];
+    let c = match op {
+      BenchOp::Open => nacl::secret_box::pack(&m, &n, &k)
+        .map_err(|err| Error::from_reason(err.message))?,
+      _ => Vec::new()
+    };
+    let sk = match op {
+      BenchOp::Sign => Vec::from(
+        nacl::sign::generate_keypair(&[1u8; nacl::sign::SEED_LENGTH]).skey
+      ),
+      _ => Vec::new()
+    };
+    Ok(BenchInputs { op, m, c, n, k, sk })
+  }
+
+  fn run_once(&self) -> core::result::Result<(), nacl::Error> {
+    match self.op {
+      BenchOp::Pack => nacl::secret_box::pack(&self.m, &self.n, &self.k).map(|_| ()),
+      BenchOp::Open => nacl::secret_box::open(&self.c, &self.n, &self.k).map(|_| ()),
+      BenchOp::Sign => nacl::sign::signature(&self.m, &self.sk).map(|_| ()),
+      #[cfg(feature = "scrypt")]
+      BenchOp::Scrypt => crate::scrypt::scrypt(
+        &self.m, &self.n, BENCH_SCRYPT_LOG_N, BENCH_SCRYPT_R, BENCH_SCRYPT_P,
+        nacl::secret_box::KEY_LENGTH, &|_| {}
+      ).map(|_| ())
+    }
+  }
+
+}
+
+#[napi(js_name = "BenchmarkResult")]
+pub struct JsBenchmarkResult {
+  #[napi]
+  pub ops_per_sec: f64,
+  #[napi]
+  pub p50_ms: f64,
+  #[napi]
+  pub p90_ms: f64,
+  #[napi]
+  pub p99_ms: f64,
+  #[napi]
+  pub max_ms: f64
+}
+
+fn percentile_ms(sorted: &[Duration], p: f64) -> f64 {
+  let i = ((sorted.len() - 1) as f64 * p).round() as usize;
+  sorted[i].as_secs_f64() * 1000.0
+}
+
+/// Runs `iterations` of given operation, with `concurrency` lanes submitting
+/// operations into runtime `rt` one after another. Latency of every operation
+/// is measured from submission, i.e. it includes time spent waiting for a
+/// free worker thread.
+pub(crate) async fn run_benchmark(
+  rt: Arc<CryptoPool>, op: String, input_size: u32, iterations: u32, concurrency: u32
+) -> Result<JsBenchmarkResult> {
+  let op = BenchOp::parse(&op)?;
+  if input_size == 0 { return Err(Error::from_reason(
+    "Benchmark input size should be greater than zero")); }
+  if iterations == 0 { return Err(Error::from_reason(
+    "Benchmark should have at least one iteration")); }
+  if concurrency == 0 { return Err(Error::from_reason(
+    "Benchmark concurrency should be at least one")); }
+  let inputs = Arc::new(BenchInputs::prepare(op, input_size as usize)?);
+
+  let start = Instant::now();
+  let mut lanes = Vec::with_capacity(concurrency as usize);
+  for lane in 0..concurrency {
+    let lane_iterations = iterations / concurrency
+      + if lane < (iterations % concurrency) { 1 } else { 0 };
+    let lane_rt = rt.clone();
+    let inputs = inputs.clone();
+    lanes.push(rt.spawn(async move {
+      let mut latencies = Vec::with_capacity(lane_iterations as usize);
+      for _ in 0..lane_iterations {
+        let inputs = inputs.clone();
+        let submitted = Instant::now();
+        lane_rt.spawn(async move {
+          inputs.run_once()
+        }).await?;
+        latencies.push(submitted.elapsed());
+      }
+      Ok::<Vec<Duration>, nacl::Error>(latencies)
+    }));
+  }
+
+  let mut latencies = Vec::with_capacity(iterations as usize);
+  for lane in lanes {
+    match lane.await {
+      Ok(lane_latencies) => latencies.extend(lane_latencies),
+      Err(err) => return Err(Error::from_reason(err.message))
+    }
+  }
+  let elapsed = start.elapsed();
+
+  latencies.sort();
+  Ok(JsBenchmarkResult {
+    ops_per_sec: (iterations as f64) / elapsed.as_secs_f64(),
+    p50_ms: percentile_ms(&latencies, 0.5),
+    p90_ms: percentile_ms(&latencies, 0.9),
+    p99_ms: percentile_ms(&latencies, 0.99),
+    max_ms: percentile_ms(&latencies, 1.0)
+  })
+}
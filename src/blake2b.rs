@@ -0,0 +1,178 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! BLAKE2b of RFC 7693, with key, salt and personalization, as used by
+//! libsodium's key derivation. nacl has no BLAKE2b.
+
+use crate::wipe;
+
+pub(crate) const BLAKE2B_MAX_LENGTH: usize = 64;
+pub(crate) const BLAKE2B_MAX_KEY_LENGTH: usize = 64;
+pub(crate) const BLAKE2B_SALT_LENGTH: usize = 16;
+pub(crate) const BLAKE2B_PERSONAL_LENGTH: usize = 16;
+const BLOCK_LENGTH: usize = 128;
+
+const IV: [u64; 8] = [
+  0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+  0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+  [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+  [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+  [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+  [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+  [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+  [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+  [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+  [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+  [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+  [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0]
+];
+
+fn le64(b: &[u8]) -> u64 {
+  let mut word = [0u8; 8];
+  word.copy_from_slice(&b[0..8]);
+  u64::from_le_bytes(word)
+}
+
+#[allow(clippy::many_single_char_names)]
+fn g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+  v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+  v[d] = (v[d] ^ v[a]).rotate_right(32);
+  v[c] = v[c].wrapping_add(v[d]);
+  v[b] = (v[b] ^ v[c]).rotate_right(24);
+  v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+  v[d] = (v[d] ^ v[a]).rotate_right(16);
+  v[c] = v[c].wrapping_add(v[d]);
+  v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+fn compress(h: &mut [u64; 8], block: &[u8; BLOCK_LENGTH], t: u128, is_last: bool) {
+  let mut m = [0u64; 16];
+  for (i, word) in m.iter_mut().enumerate() {
+    *word = le64(&block[(8 * i)..]);
+  }
+  let mut v = [0u64; 16];
+  v[0..8].copy_from_slice(h);
+  v[8..16].copy_from_slice(&IV);
+  v[12] ^= t as u64;
+  v[13] ^= (t >> 64) as u64;
+  if is_last {
+    v[14] = !v[14];
+  }
+  for i in 0..12 {
+    let s = &SIGMA[i % 10];
+    g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+    g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+    g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+    g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+    g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+    g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+    g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+    g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+  }
+  for i in 0..8 {
+    h[i] ^= v[i] ^ v[i + 8];
+  }
+  for word in m.iter_mut().chain(v.iter_mut()) {
+    unsafe { std::ptr::write_volatile(word, 0); }
+  }
+}
+
+/// BLAKE2b hash of input with `out_len` bytes, from 1 to 64, and with key of
+/// at most 64 bytes, which may be empty. Salt and personalization are
+/// 16 bytes each, with zeros standing for none. Lengths are checked by
+/// caller.
+pub(crate) fn blake2b_salt_personal(
+  out_len: usize, key: &[u8], salt: &[u8; BLAKE2B_SALT_LENGTH],
+  personal: &[u8; BLAKE2B_PERSONAL_LENGTH], input: &[u8]
+) -> Vec<u8> {
+  debug_assert!((1..=BLAKE2B_MAX_LENGTH).contains(&out_len) && (key.len() <= BLAKE2B_MAX_KEY_LENGTH));
+  let mut h = IV;
+  h[0] ^= 0x01010000 ^ ((key.len() as u64) << 8) ^ (out_len as u64);
+  h[4] ^= le64(&salt[0..]);
+  h[5] ^= le64(&salt[8..]);
+  h[6] ^= le64(&personal[0..]);
+  h[7] ^= le64(&personal[8..]);
+
+  let mut block = [0u8; BLOCK_LENGTH];
+  let mut t: u128 = 0;
+  let mut chunks: Vec<&[u8]> = Vec::new();
+  if !key.is_empty() {
+    chunks.push(key);
+  }
+  chunks.extend(input.chunks(BLOCK_LENGTH));
+  if chunks.is_empty() {
+    compress(&mut h, &block, 0, true);
+  }
+  let num_of_chunks = chunks.len();
+  for (i, chunk) in chunks.into_iter().enumerate() {
+    block.fill(0);
+    block[0..chunk.len()].copy_from_slice(chunk);
+    // padded key takes a whole block
+    t += if (i == 0) && !key.is_empty() { BLOCK_LENGTH as u128 } else { chunk.len() as u128 };
+    compress(&mut h, &block, t, i == (num_of_chunks - 1));
+  }
+  wipe(&mut block);
+
+  let mut out = Vec::with_capacity(BLAKE2B_MAX_LENGTH);
+  for word in h.iter() {
+    out.extend_from_slice(&word.to_le_bytes());
+  }
+  wipe(&mut out[out_len..]);
+  out.truncate(out_len);
+  out
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::from_hex;
+
+  fn blake2b(out_len: usize, key: &[u8], input: &[u8]) -> Vec<u8> {
+    blake2b_salt_personal(out_len, key, &[0; 16], &[0; 16], input)
+  }
+
+  #[test]
+  fn rfc7693_vector() {
+    // appendix A of RFC 7693
+    assert_eq!(blake2b(64, &[], b"abc"), from_hex(concat!(
+      "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1",
+      "7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+    )));
+  }
+
+  #[test]
+  fn unkeyed_and_keyed_vectors() {
+    // as in BLAKE2 reference test vectors, with key 00..3f
+    let key: Vec<u8> = (0..64).collect();
+    let input: Vec<u8> = (0..255).collect();
+    assert_eq!(blake2b(64, &[], &[]), from_hex(concat!(
+      "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419",
+      "d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+    )));
+    assert_eq!(blake2b(64, &key, &[]), from_hex(concat!(
+      "10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786",
+      "b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568"
+    )));
+    assert_eq!(blake2b(64, &key, &input), from_hex(concat!(
+      "142709d62e28fcccd0af97fad0f8465b971e82201dc51070faa0372aa43e9248",
+      "4be1c1e73ba10906d5d1853db6a4106e0a7bf9800d373d6dee2d46d62ef2a461"
+    )));
+  }
+
+}
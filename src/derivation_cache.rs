@@ -0,0 +1,102 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bounded LRU cache of deterministic key derivations, like public keys from
+//! secret keys, and DH shared keys. Entries are found by SHA-512 of operation
+//! tag and inputs, so that inputs themselves aren't kept. Cached values are
+//! wiped, when they are evicted, cleared, or when cache is dropped.
+
+use std::collections::{ BTreeMap, HashMap };
+use crate::wipe;
+
+type InputsHash = [u8; 64];
+
+pub(crate) struct DerivationCache {
+  capacity: usize,
+  entries: HashMap<InputsHash, (Vec<u8>, u64)>,
+  by_use: BTreeMap<u64, InputsHash>,
+  next_use: u64
+}
+
+impl Drop for DerivationCache {
+  fn drop(&mut self) {
+    self.clear();
+  }
+}
+
+impl DerivationCache {
+
+  pub fn new(capacity: usize) -> Self {
+    DerivationCache {
+      capacity,
+      entries: HashMap::with_capacity(capacity),
+      by_use: BTreeMap::new(),
+      next_use: 0
+    }
+  }
+
+  /// Hashes operation tag with its inputs into a cache key.
+  pub fn key_of(tag: &[u8], inputs: &[&[u8]]) -> InputsHash {
+    let mut hasher = nacl::sha512::Sha512::new();
+    hasher.update(&(tag.len() as u64).to_be_bytes());
+    hasher.update(tag);
+    for input in inputs {
+      hasher.update(&(input.len() as u64).to_be_bytes());
+      hasher.update(input);
+    }
+    let mut key = [0u8; 64];
+    key.copy_from_slice(&hasher.digest());
+    key
+  }
+
+  /// Returns copy of cached value, marking it as the most recently used.
+  pub fn get(&mut self, key: &InputsHash) -> Option<Vec<u8>> {
+    let use_num = self.next_use;
+    let (value, last_use) = self.entries.get_mut(key)?;
+    self.by_use.remove(last_use);
+    *last_use = use_num;
+    self.by_use.insert(use_num, *key);
+    self.next_use += 1;
+    Some(value.clone())
+  }
+
+  /// Adds value, evicting the least recently used entry, when cache is full.
+  pub fn insert(&mut self, key: InputsHash, value: &[u8]) {
+    if self.capacity == 0 {
+      return;
+    }
+    if let Some((mut old, last_use)) = self.entries.remove(&key) {
+      self.by_use.remove(&last_use);
+      wipe(&mut old);
+    } else if self.entries.len() >= self.capacity
+    && let Some((_, oldest)) = self.by_use.pop_first()
+    && let Some((mut evicted, _)) = self.entries.remove(&oldest) {
+      wipe(&mut evicted);
+    }
+    let use_num = self.next_use;
+    self.next_use += 1;
+    self.entries.insert(key, (value.to_vec(), use_num));
+    self.by_use.insert(use_num, key);
+  }
+
+  pub fn clear(&mut self) {
+    for (value, _) in self.entries.values_mut() {
+      wipe(value);
+    }
+    self.entries.clear();
+    self.by_use.clear();
+  }
+
+}
@@ -0,0 +1,177 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! One-shot public key encryption with ephemeral sender keys. Every message
+//! gets new box keypair, public part of which goes to recipient along with
+//! cipher, and secret part is wiped right after packing. Since key pair is
+//! never reused, nonce is derived from both public keys, as the first 24 bytes
+//! of sha512(ephemeral_pk || recipient_pk).
+
+use nacl::public_box;
+use nacl::secret_box::NONCE_LENGTH;
+use crate::wipe;
+
+fn nonce_for(ephemeral_pk: &[u8], recipient_pk: &[u8]) -> [u8; NONCE_LENGTH] {
+  let mut hasher = nacl::sha512::Sha512::new();
+  hasher.update(ephemeral_pk);
+  hasher.update(recipient_pk);
+  let hash = hasher.digest();
+  let mut n = [0u8; NONCE_LENGTH];
+  n.copy_from_slice(&hash[0..NONCE_LENGTH]);
+  n
+}
+
+/// Packs message to recipient with given ephemeral secret key, which is
+/// wiped, returning ephemeral public key and cipher.
+pub(crate) fn pack(
+  m: &[u8], recipient_pk: &[u8], mut ephemeral_sk: Vec<u8>
+) -> Result<(Vec<u8>, Vec<u8>), nacl::Error> {
+  let result = public_box::generate_pubkey(&ephemeral_sk).and_then(|ephemeral_pk| {
+    let n = nonce_for(&ephemeral_pk, recipient_pk);
+    public_box::pack(m, &n, recipient_pk, &ephemeral_sk).map(|c| (ephemeral_pk, c))
+  });
+  wipe(&mut ephemeral_sk);
+  result
+}
+
+pub(crate) fn open(ephemeral_pk: &[u8], c: &[u8], recipient_sk: &[u8]) -> Result<Vec<u8>, nacl::Error> {
+  let recipient_pk = public_box::generate_pubkey(recipient_sk)?;
+  let n = nonce_for(ephemeral_pk, &recipient_pk);
+  public_box::open(c, &n, ephemeral_pk, recipient_sk)
+}
+
+/// Packs message to every recipient with the same ephemeral secret key, which
+/// is wiped, returning ephemeral public key and ciphers in recipients' order.
+/// Nonces differ between recipients, as they are derived from recipient keys.
+pub(crate) fn pack_to_many(
+  m: &[u8], recipient_pks: &[&[u8]], mut ephemeral_sk: Vec<u8>
+) -> Result<(Vec<u8>, Vec<Vec<u8>>), nacl::Error> {
+  let result = public_box::generate_pubkey(&ephemeral_sk).and_then(|ephemeral_pk| {
+    let ciphers = recipient_pks.iter().map(|recipient_pk| {
+      let n = nonce_for(&ephemeral_pk, recipient_pk);
+      public_box::pack(m, &n, recipient_pk, &ephemeral_sk)
+    }).collect::<Result<Vec<_>, _>>()?;
+    Ok((ephemeral_pk, ciphers))
+  });
+  wipe(&mut ephemeral_sk);
+  result
+}
+
+const SIGNED_SEAL_PREFIX: &[u8] = b"napi-nacl signed seal";
+const SIGNING_PK_LENGTH: usize = 32;
+const SIGNATURE_LENGTH: usize = 64;
+
+/// Data, signed by sender of signed seal, binding its message to ephemeral
+/// key and to recipient. It has a copy of message, and should be wiped after
+/// use.
+fn signed_seal_data(ephemeral_pk: &[u8], recipient_pk: &[u8], m: &[u8]) -> Vec<u8> {
+  let mut data = Vec::with_capacity(SIGNED_SEAL_PREFIX.len() + ephemeral_pk.len() + recipient_pk.len() + m.len());
+  data.extend_from_slice(SIGNED_SEAL_PREFIX);
+  data.extend_from_slice(ephemeral_pk);
+  data.extend_from_slice(recipient_pk);
+  data.extend_from_slice(m);
+  data
+}
+
+/// Packs message like `pack`, but with sender's proof inside of cipher, so
+/// that only recipient learns who sent it. Sender signs with Ed25519
+/// "napi-nacl signed seal" || ephemeral_pk || recipient_pk || m, and packs
+/// sender's signing pk (32 bytes) || signature (64 bytes) || m. Result is
+/// ephemeral_pk || cipher.
+pub(crate) fn pack_signed(
+  m: &[u8], recipient_pk: &[u8], sender_sign_sk: &[u8], mut ephemeral_sk: Vec<u8>
+) -> Result<Vec<u8>, nacl::Error> {
+  let result = public_box::generate_pubkey(&ephemeral_sk).and_then(|ephemeral_pk| {
+    let mut signed = signed_seal_data(&ephemeral_pk, recipient_pk, m);
+    let sig = nacl::sign::signature(&signed, sender_sign_sk);
+    wipe(&mut signed);
+    let sig = sig?;
+    let mut inner = Vec::with_capacity(SIGNING_PK_LENGTH + SIGNATURE_LENGTH + m.len());
+    inner.extend_from_slice(&sender_sign_sk[SIGNING_PK_LENGTH..]);
+    inner.extend_from_slice(&sig);
+    inner.extend_from_slice(m);
+    let n = nonce_for(&ephemeral_pk, recipient_pk);
+    let c = public_box::pack(&inner, &n, recipient_pk, &ephemeral_sk);
+    wipe(&mut inner);
+    let c = c?;
+    let mut bundle = ephemeral_pk;
+    bundle.extend_from_slice(&c);
+    Ok(bundle)
+  });
+  wipe(&mut ephemeral_sk);
+  result
+}
+
+/// Opens bundle, made by `pack_signed`, returning message and sender's
+/// signing public key, when both cipher and signature are valid.
+pub(crate) fn open_signed(bundle: &[u8], recipient_sk: &[u8]) -> Result<(Vec<u8>, Vec<u8>), nacl::Error> {
+  if bundle.len() < public_box::KEY_LENGTH {
+    return Err(nacl::Error {
+      condition: nacl::ErrorCondition::Configuration,
+      message: format!(
+        "Signed seal should have at least {} bytes, but it is only {} bytes long.", public_box::KEY_LENGTH, bundle.len()
+      )
+    });
+  }
+  let (ephemeral_pk, c) = bundle.split_at(public_box::KEY_LENGTH);
+  let recipient_pk = public_box::generate_pubkey(recipient_sk)?;
+  let n = nonce_for(ephemeral_pk, &recipient_pk);
+  let mut inner = public_box::open(c, &n, ephemeral_pk, recipient_sk)?;
+  if inner.len() <= SIGNING_PK_LENGTH + SIGNATURE_LENGTH {
+    wipe(&mut inner);
+    return Err(nacl::Error {
+      condition: nacl::ErrorCondition::Configuration,
+      message: "Signed seal has no message in it".to_string()
+    });
+  }
+  let (sender_pk, rest) = inner.split_at(SIGNING_PK_LENGTH);
+  let (sig, m) = rest.split_at(SIGNATURE_LENGTH);
+  let mut signed = signed_seal_data(ephemeral_pk, &recipient_pk, m);
+  let verified = nacl::sign::verify(sig, &signed, sender_pk);
+  wipe(&mut signed);
+  let result = match verified {
+    Ok(true) => Ok((m.to_vec(), sender_pk.to_vec())),
+    Ok(false) => Err(nacl::Error {
+      condition: nacl::ErrorCondition::SignatureVerification,
+      message: "Signature of sender fails verification.".to_string()
+    }),
+    Err(err) => Err(err)
+  };
+  wipe(&mut inner);
+  result
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  #[test]
+  fn signed_seal_roundtrip() {
+    let recipient_sk = [1u8; public_box::KEY_LENGTH];
+    let recipient_pk = public_box::generate_pubkey(&recipient_sk).unwrap();
+    let sender = nacl::sign::generate_keypair(&[2u8; 32]);
+    let m = b"message from sender";
+    let sealed = pack_signed(m, &recipient_pk, &sender.skey, vec![3u8; public_box::KEY_LENGTH]).unwrap();
+    let (opened, sender_pk) = open_signed(&sealed, &recipient_sk).unwrap();
+    assert_eq!(opened, m);
+    assert_eq!(sender_pk, sender.pkey);
+    let mut tampered = sealed.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(open_signed(&tampered, &recipient_sk).is_err());
+    assert!(open_signed(&sealed, &[4u8; public_box::KEY_LENGTH]).is_err());
+  }
+
+}
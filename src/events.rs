@@ -0,0 +1,122 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Events about operations, done under work labels, that are passed to JS
+//! callback, set with `Cryptor.on_event`, for tracing and metrics.
+
+use std::sync::{ Arc, RwLock };
+use std::time::Instant;
+use napi::threadsafe_function::ThreadsafeFunctionCallMode;
+use napi_derive::napi;
+use crate::files::FileCryptErr;
+use crate::WeakThreadsafeFunction;
+
+#[napi(object, js_name = "OpEvent")]
+pub struct JsOpEvent {
+  pub op: String,
+  pub work_label: u32,
+  pub duration_ms: f64,
+  /// `Ok`, or name of error, like `CipherVerificationErr`.
+  pub result_kind: String
+}
+
+/// Kind of operation's result, as reported in events.
+pub(crate) trait OpOutcome {
+  fn result_kind(&self) -> &'static str;
+}
+
+impl<T> OpOutcome for core::result::Result<T, nacl::Error> {
+  fn result_kind(&self) -> &'static str {
+    match self {
+      Ok(_) => "Ok",
+      Err(err) => match err.condition {
+        nacl::ErrorCondition::CipherVerification => "CipherVerificationErr",
+        nacl::ErrorCondition::SignatureVerification => "SignatureVerificationErr",
+        nacl::ErrorCondition::Configuration => "ConfigurationErr"
+      }
+    }
+  }
+}
+
+impl<T> OpOutcome for napi::Result<T> {
+  fn result_kind(&self) -> &'static str {
+    match self {
+      Ok(_) => "Ok",
+      Err(_) => "Error"
+    }
+  }
+}
+
+impl<T> OpOutcome for core::result::Result<T, FileCryptErr> {
+  fn result_kind(&self) -> &'static str {
+    match self {
+      Ok(_) => "Ok",
+      Err(FileCryptErr::Io(_)) => "Error",
+      Err(FileCryptErr::Crypto(err)) => match err.condition {
+        nacl::ErrorCondition::CipherVerification => "CipherVerificationErr",
+        _ => "ConfigurationErr"
+      }
+    }
+  }
+}
+
+/// Batch is reported as a whole, while its items have own results.
+impl<T> OpOutcome for Vec<T> {
+  fn result_kind(&self) -> &'static str {
+    "Ok"
+  }
+}
+
+/// `None` is given by lookups, that have found nothing.
+impl<T> OpOutcome for Option<T> {
+  fn result_kind(&self) -> &'static str {
+    match self {
+      Some(_) => "Ok",
+      None => "NotFound"
+    }
+  }
+}
+
+impl<R: OpOutcome, T> OpOutcome for (R, T) {
+  fn result_kind(&self) -> &'static str {
+    self.0.result_kind()
+  }
+}
+
+/// Holder of event callback, shared by cryptor and all its handles.
+#[derive(Default)]
+pub(crate) struct OpEvents {
+  cb: RwLock<Option<Arc<WeakThreadsafeFunction<JsOpEvent>>>>
+}
+
+impl OpEvents {
+
+  pub fn set(&self, cb: WeakThreadsafeFunction<JsOpEvent>) {
+    *self.cb.write().unwrap() = Some(Arc::new(cb));
+  }
+
+  pub fn emit(&self, op: &str, work_label: u32, start: Instant, outcome: &impl OpOutcome) {
+    let cb = self.cb.read().unwrap().clone();
+    if let Some(cb) = cb {
+      cb.call(Ok(JsOpEvent {
+        op: op.to_string(),
+        work_label,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+        result_kind: outcome.result_kind().to_string()
+      }), ThreadsafeFunctionCallMode::NonBlocking);
+    }
+  }
+
+}
@@ -0,0 +1,408 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encryption of files, done entirely on worker threads, without moving file
+//! bytes through JS.
+//!
+//! Encrypted file is a sequence of with-nonce secret boxes:
+//! - header box, with 12 bytes of content: segment size as big-endian u32,
+//!   followed by length of file content as big-endian u64;
+//! - segment boxes, each with `segment size` bytes of file content, except
+//!   the last one, which may be shorter.
+//!
+//! Header box uses a random nonce, and i-th box uses this nonce with i added
+//! to its first 8 bytes, read as little-endian u64. Opening checks that every
+//! box has expected nonce, so that reordered, replaced or dropped segments
+//! are detected.
+
+use std::fs::{ self, File };
+use std::io::{ BufReader, BufWriter, Read, Write };
+use nacl::secret_box::{ NONCE_LENGTH, POLY_LENGTH, format_wn };
+
+const HEADER_CONTENT_LENGTH: usize = 12;
+const BOX_OVERHEAD: usize = NONCE_LENGTH + POLY_LENGTH;
+
+pub(crate) enum FileCryptErr {
+  Io(String),
+  Crypto(nacl::Error)
+}
+
+impl From<std::io::Error> for FileCryptErr {
+  fn from(err: std::io::Error) -> Self {
+    FileCryptErr::Io(err.to_string())
+  }
+}
+
+impl From<nacl::Error> for FileCryptErr {
+  fn from(err: nacl::Error) -> Self {
+    FileCryptErr::Crypto(err)
+  }
+}
+
+fn conf_err(message: String) -> FileCryptErr {
+  FileCryptErr::Crypto(nacl::Error {
+    condition: nacl::ErrorCondition::Configuration,
+    message
+  })
+}
+
+pub(crate) fn nonce_for_box(base: &[u8], i: u64) -> [u8; NONCE_LENGTH] {
+  let mut n = [0u8; NONCE_LENGTH];
+  n.copy_from_slice(base);
+  let mut counter = [0u8; 8];
+  counter.copy_from_slice(&n[0..8]);
+  let counter = u64::from_le_bytes(counter).wrapping_add(i);
+  n[0..8].copy_from_slice(&counter.to_le_bytes());
+  n
+}
+
+/// Reads into given buffer till it is full or till the end of source,
+/// returning number of read bytes.
+fn read_full(src: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+  let mut filled = 0;
+  while filled < buf.len() {
+    let read = src.read(&mut buf[filled..])?;
+    if read == 0 { break; }
+    filled += read;
+  }
+  Ok(filled)
+}
+
+struct Progress<'a> {
+  total: u64,
+  last_reported: u32,
+  cb: &'a dyn Fn(u32)
+}
+
+impl Progress<'_> {
+
+  fn update(&mut self, done: u64) {
+    let percent = (done * 100).checked_div(self.total).unwrap_or(100) as u32;
+    if percent > self.last_reported {
+      self.last_reported = percent;
+      (self.cb)(percent);
+    }
+  }
+
+}
+
+fn encrypt_into(
+  src_path: &str, dst: &mut impl Write, k: &[u8], segment_size: usize, base_nonce: &[u8],
+  progress_cb: &dyn Fn(u32)
+) -> Result<u64, FileCryptErr> {
+  let src_file = File::open(src_path)?;
+  let content_len = src_file.metadata()?.len();
+  let mut src = BufReader::new(src_file);
+
+  let mut header = [0u8; HEADER_CONTENT_LENGTH];
+  header[0..4].copy_from_slice(&(segment_size as u32).to_be_bytes());
+  header[4..].copy_from_slice(&content_len.to_be_bytes());
+  dst.write_all(&format_wn::pack(&header, &nonce_for_box(base_nonce, 0), k)?)?;
+
+  let mut progress = Progress { total: content_len, last_reported: 0, cb: progress_cb };
+  let mut segment = vec![0u8; segment_size];
+  let mut done: u64 = 0;
+  let mut i: u64 = 1;
+  while done < content_len {
+    let read = read_full(&mut src, &mut segment)?;
+    if read == 0 { break; }
+    dst.write_all(&format_wn::pack(&segment[0..read], &nonce_for_box(base_nonce, i), k)?)?;
+    done += read as u64;
+    i += 1;
+    progress.update(done);
+  }
+  if done != content_len {
+    return Err(FileCryptErr::Io(format!(
+      "File {} changed while being encrypted", src_path
+    )));
+  }
+  progress.update(done);
+  dst.flush()?;
+  Ok(content_len)
+}
+
+fn decrypt_into(
+  src_path: &str, dst: &mut impl Write, k: &[u8], progress_cb: &dyn Fn(u32)
+) -> Result<u64, FileCryptErr> {
+  let mut src = BufReader::new(File::open(src_path)?);
+
+  let mut header_box = [0u8; BOX_OVERHEAD + HEADER_CONTENT_LENGTH];
+  if read_full(&mut src, &mut header_box)? < header_box.len() {
+    return Err(conf_err(format!("File {} is too short to be encrypted file", src_path)));
+  }
+  let header = format_wn::open(&header_box, k)?;
+  let base_nonce = format_wn::copy_nonce_from(&header_box)?.to_vec();
+  let mut segment_size = [0u8; 4];
+  segment_size.copy_from_slice(&header[0..4]);
+  let segment_size = u32::from_be_bytes(segment_size) as usize;
+  if segment_size == 0 {
+    return Err(conf_err(format!("File {} has zero segment size in its header", src_path)));
+  }
+  let mut content_len = [0u8; 8];
+  content_len.copy_from_slice(&header[4..]);
+  let content_len = u64::from_be_bytes(content_len);
+
+  let mut progress = Progress { total: content_len, last_reported: 0, cb: progress_cb };
+  // header's segment size isn't trusted for allocation beyond content length
+  let mut segment_box = vec![0u8; BOX_OVERHEAD + (segment_size as u64).min(content_len) as usize];
+  let mut done: u64 = 0;
+  let mut i: u64 = 1;
+  while done < content_len {
+    let expected_len = (BOX_OVERHEAD as u64 + (content_len - done).min(segment_size as u64)) as usize;
+    let read = read_full(&mut src, &mut segment_box[0..expected_len])?;
+    if read < expected_len {
+      return Err(conf_err(format!("File {} is truncated", src_path)));
+    }
+    let segment_box = &segment_box[0..expected_len];
+    if !nacl::compare(format_wn::copy_nonce_from(segment_box)?, &nonce_for_box(&base_nonce, i)) {
+      return Err(FileCryptErr::Crypto(nacl::Error {
+        condition: nacl::ErrorCondition::CipherVerification,
+        message: format!("Segment {} of file {} is out of place", i, src_path)
+      }));
+    }
+    let content = format_wn::open(segment_box, k)?;
+    dst.write_all(&content)?;
+    done += content.len() as u64;
+    i += 1;
+    progress.update(done);
+  }
+  if read_full(&mut src, &mut [0u8; 1])? != 0 {
+    return Err(conf_err(format!("File {} has unexpected bytes after last segment", src_path)));
+  }
+  progress.update(done);
+  dst.flush()?;
+  Ok(content_len)
+}
+
+/// Writes result of encryption into `dst_path`, removing it on failure.
+/// Destination, that is the source file, is refused, as creating it would
+/// truncate source.
+fn with_dst_file(
+  src_path: &str, dst_path: &str,
+  write: impl FnOnce(&mut BufWriter<File>) -> Result<u64, FileCryptErr>
+) -> Result<u64, FileCryptErr> {
+  if (src_path == dst_path) || is_same_existing_file(src_path, dst_path) {
+    return Err(conf_err(format!(
+      "Destination {} is the same file as source {}", dst_path, src_path
+    )));
+  }
+  let mut dst = BufWriter::new(File::create(dst_path)?);
+  let result = write(&mut dst);
+  drop(dst);
+  if result.is_err() {
+    let _ = fs::remove_file(dst_path);
+  }
+  result
+}
+
+fn is_same_existing_file(src_path: &str, dst_path: &str) -> bool {
+  match (fs::canonicalize(src_path), fs::canonicalize(dst_path)) {
+    (Ok(src), Ok(dst)) => src == dst,
+    _ => false
+  }
+}
+
+pub(crate) fn encrypt_file(
+  src_path: &str, dst_path: &str, k: &[u8], segment_size: usize, base_nonce: &[u8],
+  progress_cb: &dyn Fn(u32)
+) -> Result<u64, FileCryptErr> {
+  if (segment_size == 0) || (segment_size > (u32::MAX as usize)) {
+    return Err(conf_err(format!(
+      "Segment size should be from 1 to {} bytes, but it is {}", u32::MAX, segment_size
+    )));
+  }
+  with_dst_file(src_path, dst_path, |dst| encrypt_into(src_path, dst, k, segment_size, base_nonce, progress_cb))
+}
+
+pub(crate) fn decrypt_file(
+  src_path: &str, dst_path: &str, k: &[u8], progress_cb: &dyn Fn(u32)
+) -> Result<u64, FileCryptErr> {
+  with_dst_file(src_path, dst_path, |dst| decrypt_into(src_path, dst, k, progress_cb))
+}
+
+#[cfg(test)]
+mod tests {
+
+  use std::cell::Cell;
+  use std::path::PathBuf;
+  use super::*;
+
+  const K: [u8; 32] = [3; 32];
+  const BASE_NONCE: [u8; NONCE_LENGTH] = [5; NONCE_LENGTH];
+
+  struct TestDir(PathBuf);
+
+  impl TestDir {
+    fn new(name: &str) -> Self {
+      let dir = std::env::temp_dir().join(format!("napi-nacl-{}-{}", name, std::process::id()));
+      let _ = fs::remove_dir_all(&dir);
+      fs::create_dir_all(&dir).unwrap();
+      TestDir(dir)
+    }
+    fn path(&self, file: &str) -> String {
+      self.0.join(file).to_str().unwrap().to_string()
+    }
+  }
+
+  impl Drop for TestDir {
+    fn drop(&mut self) {
+      let _ = fs::remove_dir_all(&self.0);
+    }
+  }
+
+  fn ok(result: Result<u64, FileCryptErr>) -> u64 {
+    match result {
+      Ok(len) => len,
+      Err(FileCryptErr::Io(message)) => panic!("io error: {}", message),
+      Err(FileCryptErr::Crypto(err)) => panic!("crypto error: {}", err.message)
+    }
+  }
+
+  fn err_message(result: Result<u64, FileCryptErr>) -> String {
+    match result {
+      Ok(_) => panic!("expected an error"),
+      Err(FileCryptErr::Io(message)) => message,
+      Err(FileCryptErr::Crypto(err)) => err.message
+    }
+  }
+
+  fn encrypt(dir: &TestDir, content: &[u8], segment_size: usize) -> String {
+    let src = dir.path("content");
+    let dst = dir.path("encrypted");
+    fs::write(&src, content).unwrap();
+    assert_eq!(ok(encrypt_file(&src, &dst, &K, segment_size, &BASE_NONCE, &|_| {})), content.len() as u64);
+    dst
+  }
+
+  fn decrypt_err(dir: &TestDir, encrypted: &[u8]) -> String {
+    let src = dir.path("broken");
+    let dst = dir.path("decrypted");
+    fs::write(&src, encrypted).unwrap();
+    let message = err_message(decrypt_file(&src, &dst, &K, &|_| {}));
+    assert!(!fs::exists(&dst).unwrap(), "destination is removed on failure");
+    message
+  }
+
+  #[test]
+  fn files_roundtrip() {
+    let dir = TestDir::new("files-roundtrip");
+    let segment_size = 100;
+    for len in [0usize, 1, 99, 100, 101, 305] {
+      let content: Vec<u8> = (0..len).map(|i| i as u8).collect();
+      let encrypted = encrypt(&dir, &content, segment_size);
+      let num_of_segments = len.div_ceil(segment_size);
+      assert_eq!(
+        fs::metadata(&encrypted).unwrap().len() as usize,
+        BOX_OVERHEAD*(1 + num_of_segments) + HEADER_CONTENT_LENGTH + len
+      );
+      let decrypted = dir.path("decrypted");
+      let last_progress = Cell::new(0);
+      let cb = |p: u32| {
+        assert!(p > last_progress.get());
+        last_progress.set(p);
+      };
+      assert_eq!(ok(decrypt_file(&encrypted, &decrypted, &K, &cb)), len as u64);
+      assert_eq!(last_progress.get(), 100);
+      assert_eq!(fs::read(&decrypted).unwrap(), content);
+    }
+  }
+
+  #[test]
+  fn broken_files_fail_to_decrypt() {
+    let dir = TestDir::new("files-broken");
+    let segment_size = 10;
+    let box_len = BOX_OVERHEAD + segment_size;
+    let header_len = BOX_OVERHEAD + HEADER_CONTENT_LENGTH;
+    let encrypted = fs::read(encrypt(&dir, &[7; 35], segment_size)).unwrap();
+
+    let mut tampered = encrypted.clone();
+    tampered[header_len + box_len + NONCE_LENGTH] ^= 1;
+    decrypt_err(&dir, &tampered);
+
+    let mut swapped = encrypted[0..header_len].to_vec();
+    swapped.extend_from_slice(&encrypted[(header_len + box_len)..(header_len + 2*box_len)]);
+    swapped.extend_from_slice(&encrypted[header_len..(header_len + box_len)]);
+    swapped.extend_from_slice(&encrypted[(header_len + 2*box_len)..]);
+    assert!(decrypt_err(&dir, &swapped).contains("out of place"));
+
+    assert!(decrypt_err(&dir, &encrypted[0..(encrypted.len() - 1)]).contains("truncated"));
+    assert!(decrypt_err(&dir, &encrypted[0..(header_len - 1)]).contains("too short"));
+
+    let mut extended = encrypted.clone();
+    extended.push(0);
+    assert!(decrypt_err(&dir, &extended).contains("unexpected bytes"));
+
+    let src = dir.path("broken");
+    fs::write(&src, &encrypted).unwrap();
+    assert!(decrypt_file(&src, &dir.path("decrypted"), &[4; 32], &|_| {}).is_err());
+  }
+
+  #[test]
+  fn zero_segment_size_in_header_is_rejected() {
+    let dir = TestDir::new("files-zero-segment");
+    let mut header = [0u8; HEADER_CONTENT_LENGTH];
+    header[4..].copy_from_slice(&5u64.to_be_bytes());
+    let encrypted = format_wn::pack(&header, &BASE_NONCE, &K).unwrap();
+    assert!(decrypt_err(&dir, &encrypted).contains("zero segment size"));
+  }
+
+  #[test]
+  fn segment_size_in_header_above_content_length_decrypts() {
+    let dir = TestDir::new("files-huge-segment");
+    let mut header = [0u8; HEADER_CONTENT_LENGTH];
+    header[0..4].copy_from_slice(&u32::MAX.to_be_bytes());
+    header[4..].copy_from_slice(&5u64.to_be_bytes());
+    let mut encrypted = format_wn::pack(&header, &nonce_for_box(&BASE_NONCE, 0), &K).unwrap();
+    encrypted.extend(format_wn::pack(b"short", &nonce_for_box(&BASE_NONCE, 1), &K).unwrap());
+    let src = dir.path("encrypted");
+    let dst = dir.path("decrypted");
+    fs::write(&src, &encrypted).unwrap();
+    assert_eq!(ok(decrypt_file(&src, &dst, &K, &|_| {})), 5);
+    assert_eq!(fs::read(&dst).unwrap(), b"short");
+  }
+
+  #[test]
+  fn zero_segment_size_is_rejected() {
+    let dir = TestDir::new("files-zero-segment-size");
+    let src = dir.path("content");
+    fs::write(&src, b"content").unwrap();
+    assert!(encrypt_file(&src, &dir.path("encrypted"), &K, 0, &BASE_NONCE, &|_| {}).is_err());
+  }
+
+  #[test]
+  fn source_is_not_overwritten() {
+    let dir = TestDir::new("files-same-path");
+    fs::create_dir(dir.0.join("sub")).unwrap();
+    let content = b"content, that should stay";
+    let src = dir.path("content");
+    fs::write(&src, content).unwrap();
+    let same_files = [src.clone(), dir.path("sub/../content"), dir.path("./content")];
+    for dst in &same_files {
+      let message = err_message(encrypt_file(&src, dst, &K, 10, &BASE_NONCE, &|_| {}));
+      assert!(message.contains("same file"));
+      assert_eq!(fs::read(&src).unwrap(), content);
+    }
+
+    let encrypted = encrypt(&dir, content, 10);
+    let encrypted_bytes = fs::read(&encrypted).unwrap();
+    let same_files = [encrypted.clone(), dir.path("sub/../encrypted")];
+    for dst in &same_files {
+      let message = err_message(decrypt_file(&encrypted, dst, &K, &|_| {}));
+      assert!(message.contains("same file"));
+      assert_eq!(fs::read(&encrypted).unwrap(), encrypted_bytes);
+    }
+  }
+
+}
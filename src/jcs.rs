@@ -0,0 +1,210 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! JSON canonicalization, as defined in RFC 8785 (JCS), for signing of
+//! structured data. Object members are sorted by UTF-16 code units of their
+//! names, strings are minimally escaped, and numbers are written like
+//! ECMAScript's `Number.prototype.toString` does.
+
+use serde_json::Value;
+
+pub(crate) fn canonicalize(value: &Value) -> Result<String, String> {
+  let mut out = String::new();
+  write_value(value, &mut out)?;
+  Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut String) -> Result<(), String> {
+  match value {
+    Value::Null => out.push_str("null"),
+    Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+    Value::Number(num) => match num.as_f64() {
+      Some(x) if x.is_finite() => write_number(x, out),
+      _ => return Err(format!("Number {} can't be canonicalized", num))
+    },
+    Value::String(s) => write_string(s, out),
+    Value::Array(items) => {
+      out.push('[');
+      for (i, item) in items.iter().enumerate() {
+        if i > 0 { out.push(','); }
+        write_value(item, out)?;
+      }
+      out.push(']');
+    },
+    Value::Object(members) => {
+      let mut members = members.iter().collect::<Vec<_>>();
+      members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+      out.push('{');
+      for (i, (name, member)) in members.into_iter().enumerate() {
+        if i > 0 { out.push(','); }
+        write_string(name, out);
+        out.push(':');
+        write_value(member, out)?;
+      }
+      out.push('}');
+    }
+  }
+  Ok(())
+}
+
+fn write_string(s: &str, out: &mut String) {
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\u{08}' => out.push_str("\\b"),
+      '\t' => out.push_str("\\t"),
+      '\n' => out.push_str("\\n"),
+      '\u{0c}' => out.push_str("\\f"),
+      '\r' => out.push_str("\\r"),
+      c if c < '\u{20}' => out.push_str(&format!("\\u{:04x}", c as u32)),
+      c => out.push(c)
+    }
+  }
+  out.push('"');
+}
+
+/// Writes finite number in ECMAScript's shortest round-trip form.
+fn write_number(x: f64, out: &mut String) {
+  if x == 0.0 {
+    // covers negative zero, which is written as 0
+    out.push('0');
+    return;
+  }
+  if x < 0.0 {
+    out.push('-');
+  }
+  // Rust's exponent form gives the shortest round-trip digits, but, when two
+  // such digit strings are equally close to the number, ECMAScript takes the
+  // even one, as does Rust's exact rounding to the same number of digits.
+  let shortest = format!("{:e}", x.abs());
+  let num_of_digits = shortest.split_once('e').unwrap().0.replace('.', "").len();
+  let rounded = format!("{:.*e}", num_of_digits - 1, x.abs());
+  let sci = if rounded.parse::<f64>() == Ok(x.abs()) { rounded } else { shortest };
+  let (mantissa, exp) = sci.split_once('e').unwrap();
+  let digits = mantissa.replace('.', "");
+  let k = digits.len() as i32;
+  // value is 0.digits * 10^n
+  let n = exp.parse::<i32>().unwrap() + 1;
+  if (k <= n) && (n <= 21) {
+    out.push_str(&digits);
+    out.extend(std::iter::repeat_n('0', (n - k) as usize));
+  } else if (0 < n) && (n <= 21) {
+    out.push_str(&digits[0..(n as usize)]);
+    out.push('.');
+    out.push_str(&digits[(n as usize)..]);
+  } else if (-6 < n) && (n <= 0) {
+    out.push_str("0.");
+    out.extend(std::iter::repeat_n('0', (-n) as usize));
+    out.push_str(&digits);
+  } else {
+    out.push_str(&digits[0..1]);
+    if k > 1 {
+      out.push('.');
+      out.push_str(&digits[1..]);
+    }
+    out.push('e');
+    out.push(if n > 0 { '+' } else { '-' });
+    out.push_str(&(n - 1).abs().to_string());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  fn number_str(x: f64) -> String {
+    let mut out = String::new();
+    write_number(x, &mut out);
+    out
+  }
+
+  #[test]
+  fn rfc8785_number_vectors() {
+    // appendix B of RFC 8785, IEEE 754 bits and expected serialization
+    let vectors: [(u64, &str); 24] = [
+      (0x0000000000000000, "0"),
+      (0x8000000000000000, "0"),
+      (0x0000000000000001, "5e-324"),
+      (0x8000000000000001, "-5e-324"),
+      (0x7fefffffffffffff, "1.7976931348623157e+308"),
+      (0xffefffffffffffff, "-1.7976931348623157e+308"),
+      (0x4340000000000000, "9007199254740992"),
+      (0xc340000000000000, "-9007199254740992"),
+      (0x4430000000000000, "295147905179352830000"),
+      (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+      (0x44b52d02c7e14af6, "1e+23"),
+      (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+      (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+      (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+      (0x444b1ae4d6e2ef50, "1e+21"),
+      (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+      (0x3eb0c6f7a0b5ed8d, "0.000001"),
+      (0x41b3de4355555553, "333333333.3333332"),
+      (0x41b3de4355555554, "333333333.33333325"),
+      (0x41b3de4355555555, "333333333.3333333"),
+      (0x41b3de4355555556, "333333333.3333334"),
+      (0x41b3de4355555557, "333333333.33333343"),
+      (0xbecbf647612f3696, "-0.0000033333333333333333"),
+      (0x43143ff3c1cb0959, "1424953923781206.2")
+    ];
+    for (bits, expected) in vectors {
+      assert_eq!(number_str(f64::from_bits(bits)), expected, "bits {:016x}", bits);
+    }
+  }
+
+  #[test]
+  fn rfc8785_key_sorting() {
+    // section 3.2.3 of RFC 8785
+    let value: Value = serde_json::from_str(r#"{
+      "\u20ac": "Euro Sign",
+      "\r": "Carriage Return",
+      "\ufb33": "Hebrew Letter Dalet With Dagesh",
+      "1": "One",
+      "\ud83d\ude00": "Emoji: Grinning Face",
+      "\u0080": "Control",
+      "\u00f6": "Latin Small Letter O With Diaeresis"
+    }"#).unwrap();
+    let expected = concat!(
+      "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",",
+      "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",",
+      "\"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+    );
+    assert_eq!(canonicalize(&value).unwrap(), expected);
+  }
+
+  #[test]
+  fn rfc8785_sample() {
+    // section 3.2.2 of RFC 8785; numbers come from JS already parsed, while
+    // serde_json's default parsing isn't correctly rounded, hence exact bits
+    let mut value: Value = serde_json::from_str(r#"{
+      "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+      "literals": [null, true, false]
+    }"#).unwrap();
+    // 333333333.33333329 is 0x41b3de4355555555
+    value["numbers"] = serde_json::json!([
+      f64::from_bits(0x41b3de4355555555), 1E30f64, 4.50f64, 2e-3f64, 1e-27f64
+    ]);
+    let expected = concat!(
+      r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"#,
+      "\"string\":\"\u{20ac}$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\"}"
+    );
+    assert_eq!(canonicalize(&value).unwrap(), expected);
+  }
+
+}
@@ -0,0 +1,289 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Key derivation, built on sha512 from nacl, and on BLAKE2b for subkeys,
+//! compatible with libsodium.
+
+use nacl::sha512::{ Sha512, hash_sha512 };
+use crate::blake2b::{
+  BLAKE2B_MAX_LENGTH, BLAKE2B_PERSONAL_LENGTH, BLAKE2B_SALT_LENGTH, blake2b_salt_personal
+};
+use crate::wipe;
+
+const SHA512_BLOCK_LENGTH: usize = 128;
+pub(crate) const HMAC_SHA512_LENGTH: usize = 64;
+
+/// HMAC-SHA512, as defined in RFC 2104.
+pub(crate) fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; HMAC_SHA512_LENGTH] {
+  let mut block_key = [0u8; SHA512_BLOCK_LENGTH];
+  if key.len() > SHA512_BLOCK_LENGTH {
+    hash_sha512(&mut block_key[0..64], key);
+  } else {
+    block_key[0..key.len()].copy_from_slice(key);
+  }
+
+  let mut pad = [0u8; SHA512_BLOCK_LENGTH];
+  for i in 0..SHA512_BLOCK_LENGTH {
+    pad[i] = block_key[i] ^ 0x36;
+  }
+  let mut hasher = Sha512::new();
+  hasher.update(&pad);
+  hasher.update(data);
+  let mut inner = hasher.digest();
+
+  for i in 0..SHA512_BLOCK_LENGTH {
+    pad[i] = block_key[i] ^ 0x5c;
+  }
+  hasher.update(&pad);
+  hasher.update(&inner);
+  let outer = hasher.digest();
+
+  let mut mac = [0u8; HMAC_SHA512_LENGTH];
+  mac.copy_from_slice(&outer);
+  wipe(&mut block_key);
+  wipe(&mut pad);
+  wipe(&mut inner);
+  mac
+}
+
+const SUBKEY_CONTEXT_LENGTH: usize = 8;
+const SUBKEY_MIN_LENGTH: usize = 16;
+const SUBKEY_MAX_LENGTH: usize = BLAKE2B_MAX_LENGTH;
+const MASTER_KEY_LENGTH: usize = 32;
+
+fn conf_err(message: String) -> nacl::Error {
+  nacl::Error { condition: nacl::ErrorCondition::Configuration, message }
+}
+
+/// Derives subkey with numeric id and 8-byte context from 32-byte master key,
+/// like libsodium's crypto_kdf_derive_from_key, i.e. subkey is BLAKE2b of
+/// empty input with `subkey_len` bytes, keyed with master key, with salt,
+/// that is subkey id as little-endian u64 followed by zeros, and with
+/// personalization, that is context followed by zeros.
+pub(crate) fn derive_subkey(
+  master_k: &[u8], subkey_id: u64, context: &[u8], subkey_len: usize
+) -> Result<Vec<u8>, nacl::Error> {
+  if master_k.len() != MASTER_KEY_LENGTH {
+    return Err(conf_err(format!(
+      "Master key should have {} bytes, but it is {} bytes long.", MASTER_KEY_LENGTH, master_k.len()
+    )));
+  }
+  if context.len() != SUBKEY_CONTEXT_LENGTH {
+    return Err(conf_err(format!(
+      "Context should have {} bytes, but it is {} bytes long.", SUBKEY_CONTEXT_LENGTH, context.len()
+    )));
+  }
+  if !(SUBKEY_MIN_LENGTH..=SUBKEY_MAX_LENGTH).contains(&subkey_len) {
+    return Err(conf_err(format!(
+      "Subkey length should be from {} to {} bytes, but {} was requested",
+      SUBKEY_MIN_LENGTH, SUBKEY_MAX_LENGTH, subkey_len
+    )));
+  }
+  let mut salt = [0u8; BLAKE2B_SALT_LENGTH];
+  salt[0..8].copy_from_slice(&subkey_id.to_le_bytes());
+  let mut personal = [0u8; BLAKE2B_PERSONAL_LENGTH];
+  personal[0..SUBKEY_CONTEXT_LENGTH].copy_from_slice(context);
+  Ok(blake2b_salt_personal(subkey_len, master_k, &salt, &personal, &[]))
+}
+
+const HKDF_MAX_LENGTH: usize = 255 * HMAC_SHA512_LENGTH;
+
+/// HKDF with HMAC-SHA512, as defined in RFC 5869. Empty salt stands for a
+/// salt of zeros, as in RFC.
+pub(crate) fn hkdf_sha512(
+  ikm: &[u8], salt: &[u8], info: &[u8], okm_len: usize
+) -> Result<Vec<u8>, nacl::Error> {
+  if (okm_len == 0) || (okm_len > HKDF_MAX_LENGTH) {
+    return Err(conf_err(format!(
+      "HKDF output length should be from 1 to {} bytes, but {} was requested", HKDF_MAX_LENGTH, okm_len
+    )));
+  }
+  let mut prk = hmac_sha512(salt, ikm);
+  let mut okm = Vec::with_capacity(okm_len + HMAC_SHA512_LENGTH);
+  let mut block_input = Vec::with_capacity(HMAC_SHA512_LENGTH + info.len() + 1);
+  let mut i = 1u8;
+  while okm.len() < okm_len {
+    block_input.extend_from_slice(info);
+    block_input.push(i);
+    let mut block = hmac_sha512(&prk, &block_input);
+    okm.extend_from_slice(&block);
+    wipe(&mut block_input);
+    block_input.clear();
+    block_input.extend_from_slice(&block);
+    wipe(&mut block);
+    i = i.wrapping_add(1);
+  }
+  wipe(&mut okm[okm_len..]);
+  okm.truncate(okm_len);
+  wipe(&mut prk);
+  wipe(&mut block_input);
+  Ok(okm)
+}
+
+const MESSAGE_KEY_INFO: &[u8] = b"napi-nacl message key";
+const SHARED_SECRET_LENGTH: usize = 32;
+
+/// Derives secret box key of message with given counter from shared secret,
+/// as HKDF-SHA512 with empty salt, and with info, that is ASCII string
+/// "napi-nacl message key", followed by counter as big-endian u64.
+pub(crate) fn message_key(shared_secret: &[u8], counter: u64) -> Result<Vec<u8>, nacl::Error> {
+  if shared_secret.len() != SHARED_SECRET_LENGTH {
+    return Err(conf_err(format!(
+      "Shared secret should have {} bytes, but it is {} bytes long.", SHARED_SECRET_LENGTH, shared_secret.len()
+    )));
+  }
+  let mut info = [0u8; MESSAGE_KEY_INFO.len() + 8];
+  info[0..MESSAGE_KEY_INFO.len()].copy_from_slice(MESSAGE_KEY_INFO);
+  info[MESSAGE_KEY_INFO.len()..].copy_from_slice(&counter.to_be_bytes());
+  hkdf_sha512(shared_secret, &[], &info, nacl::secret_box::KEY_LENGTH)
+}
+
+const SEEDED_RNG_INFO: &[u8] = b"napi-nacl seeded random";
+const SEEDED_RNG_MIN_SEED_LENGTH: usize = 32;
+
+/// Deterministic generator of bytes from a high-entropy seed. Block i of
+/// output is HMAC-SHA512(seed, "napi-nacl seeded random" || i as big-endian
+/// u64), and every call takes fresh blocks, dropping unused bytes of the last
+/// one, so that output of a call depends only on seed and calls before it.
+pub(crate) struct SeededRng {
+  seed: Vec<u8>,
+  next_block: u64
+}
+
+impl Drop for SeededRng {
+  fn drop(&mut self) {
+    wipe(&mut self.seed);
+  }
+}
+
+impl SeededRng {
+
+  pub fn new(seed: &[u8]) -> Result<Self, nacl::Error> {
+    if seed.len() < SEEDED_RNG_MIN_SEED_LENGTH {
+      return Err(conf_err(format!(
+        "Seed should have at least {} bytes, but it is {} bytes long.", SEEDED_RNG_MIN_SEED_LENGTH, seed.len()
+      )));
+    }
+    Ok(SeededRng { seed: seed.to_vec(), next_block: 0 })
+  }
+
+  pub fn next_bytes(&mut self, len: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(len + HMAC_SHA512_LENGTH);
+    let mut input = [0u8; SEEDED_RNG_INFO.len() + 8];
+    input[0..SEEDED_RNG_INFO.len()].copy_from_slice(SEEDED_RNG_INFO);
+    while bytes.len() < len {
+      input[SEEDED_RNG_INFO.len()..].copy_from_slice(&self.next_block.to_be_bytes());
+      let mut block = hmac_sha512(&self.seed, &input);
+      bytes.extend_from_slice(&block);
+      wipe(&mut block);
+      self.next_block += 1;
+    }
+    wipe(&mut bytes[len..]);
+    bytes.truncate(len);
+    bytes
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::from_hex;
+
+  #[test]
+  fn subkeys_match_libsodium_kdf() {
+    // crypto_kdf_derive_from_key with master key 00..1f and context "KDF test"
+    let master_k: Vec<u8> = (0..32).collect();
+    let vectors: [(u64, usize, &str); 4] = [
+      (0, 32, "c13fcc2e6cd0cd0f82d93b163a5696c5105378f8c629d36baf3ae0239de9c280"),
+      (1, 16, "3c387fab802aae447033073e76ee002c"),
+      (2, 64, concat!(
+        "1944da61ff18dc2028c3578ac85be904931b83860896598f62468f1cb5471c6a",
+        "344c945dbc62c9aaf70feb62472d17775ea5db6ed5494c68b7a9a59761f39614"
+      )),
+      (u64::MAX, 32, "500c3043b2b9177ec843ecbe9f98f92d8c11fbbd10a225ab844548de89c21d55")
+    ];
+    for (subkey_id, subkey_len, subkey) in vectors {
+      assert_eq!(derive_subkey(&master_k, subkey_id, b"KDF test", subkey_len).unwrap(), from_hex(subkey));
+    }
+    assert!(derive_subkey(&master_k, 0, b"KDF test", 15).is_err());
+    assert!(derive_subkey(&master_k, 0, b"KDF test", 65).is_err());
+    assert!(derive_subkey(&master_k, 0, b"KDF", 32).is_err());
+    assert!(derive_subkey(&master_k[0..31], 0, b"KDF test", 32).is_err());
+  }
+
+  #[test]
+  fn hmac_sha512_rfc4231_vectors() {
+    // test cases 1, 2 and 6 of RFC 4231
+    let vectors: [(Vec<u8>, &[u8], &str); 3] = [
+      (vec![0x0b; 20], b"Hi There", concat!(
+        "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde",
+        "daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"
+      )),
+      (b"Jefe".to_vec(), b"what do ya want for nothing?", concat!(
+        "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554",
+        "9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+      )),
+      (vec![0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First", concat!(
+        "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352",
+        "6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"
+      ))
+    ];
+    for (key, data, mac) in vectors {
+      assert_eq!(hmac_sha512(&key, data).to_vec(), from_hex(mac));
+    }
+  }
+
+  #[test]
+  fn hkdf_sha512_vectors() {
+    // inputs of test cases 1, 2 and 3 of RFC 5869, with SHA-512
+    let ikm = from_hex(&"0b".repeat(22));
+    let salt = from_hex("000102030405060708090a0b0c");
+    let info = from_hex("f0f1f2f3f4f5f6f7f8f9");
+    assert_eq!(hkdf_sha512(&ikm, &salt, &info, 42).unwrap(), from_hex(concat!(
+      "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c14815793",
+      "38da362cb8d9f925d7cb"
+    )));
+    let long_ikm: Vec<u8> = (0x00..0x50).collect();
+    let long_salt: Vec<u8> = (0x60..0xb0).collect();
+    let long_info: Vec<u8> = (0xb0..=0xff).collect();
+    assert_eq!(hkdf_sha512(&long_ikm, &long_salt, &long_info, 82).unwrap(), from_hex(concat!(
+      "ce6c97192805b346e6161e821ed165673b84f400a2b514b2fe23d84cd189ddf1",
+      "b695b48cbd1c8388441137b3ce28f16aa64ba33ba466b24df6cfcb021ecff235",
+      "f6a2056ce3af1de44d572097a8505d9e7a93"
+    )));
+    assert_eq!(hkdf_sha512(&ikm, &[], &[], 42).unwrap(), from_hex(concat!(
+      "f5fa02b18298a72a8c23898a8703472c6eb179dc204c03425c970e3b164bf90f",
+      "ff22d04836d0e2343bac"
+    )));
+    let longest = hkdf_sha512(&ikm, &salt, &info, HKDF_MAX_LENGTH).unwrap();
+    assert_eq!(&longest[(HKDF_MAX_LENGTH - 32)..], from_hex(
+      "931623de9936c096438e9f180736960acc54a28763012fec34d40b7c12ee8560"
+    ));
+    assert!(hkdf_sha512(&ikm, &salt, &info, HKDF_MAX_LENGTH + 1).is_err());
+    assert!(hkdf_sha512(&ikm, &salt, &info, 0).is_err());
+  }
+
+  #[test]
+  fn message_key_is_hkdf_with_counter_info() {
+    let shared_secret = [9u8; SHARED_SECRET_LENGTH];
+    let mut info = MESSAGE_KEY_INFO.to_vec();
+    info.extend_from_slice(&7u64.to_be_bytes());
+    assert_eq!(message_key(&shared_secret, 7).unwrap(), hkdf_sha512(&shared_secret, &[], &info, 32).unwrap());
+    assert_ne!(message_key(&shared_secret, 7).unwrap(), message_key(&shared_secret, 8).unwrap());
+  }
+
+}
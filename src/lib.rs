@@ -20,10 +20,42 @@ use napi::{
   threadsafe_function::{ThreadsafeFunctionCallMode, ThreadsafeFunction}
 };
 use napi_derive::napi;
-use tokio::runtime::{ Runtime, Builder };
+#[cfg(not(feature = "no-thread-pool"))]
+use tokio::runtime::Handle;
 use dashmap::DashMap;
-use std::sync::Arc;
-use nacl;
+use base64::{ Engine, engine::general_purpose::URL_SAFE_NO_PAD };
+use pool::CryptoPool;
+use derivation_cache::DerivationCache;
+use events::{ OpEvents, OpOutcome };
+use std::collections::HashMap;
+use std::sync::{ Arc, Mutex, RwLock };
+use std::sync::atomic::{ AtomicBool, AtomicU32, Ordering };
+use tokio::sync::Notify;
+#[cfg(feature = "scrypt")]
+use std::cell::Cell;
+use std::time::{ Duration, Instant };
+
+mod bench;
+mod blake2b;
+mod derivation_cache;
+mod ephemeral;
+mod events;
+mod files;
+mod jcs;
+mod kdf;
+mod pool;
+mod ratchet;
+mod segments;
+mod sign_vectors;
+mod x25519;
+mod xsalsa20poly1305;
+#[cfg(feature = "scrypt-salt-reuse-check")]
+mod salt_reuse;
+#[cfg(feature = "scrypt")]
+mod scrypt;
+
+/// Threadsafe function, that doesn't keep Node's event loop alive.
+type WeakThreadsafeFunction<T> = ThreadsafeFunction<T, Unknown<'static>, T, Status, true, true>;
 
 #[napi]
 pub enum EncrResult {
@@ -42,243 +74,3887 @@ fn into_napi_ok(nacl_result: core::result::Result<Vec<u8>, nacl::Error>) -> Resu
       nacl::ErrorCondition::Configuration => Ok(EncrResult::ConfigurationErr(err.message)),
     }
   }
-}
+}
+
+#[napi]
+pub enum EncodedEncrResult {
+  Ok(String),
+	CipherVerificationErr,
+	SignatureVerificationErr,
+	ConfigurationErr(String)
+}
+
+fn into_napi_encoded_ok(nacl_result: core::result::Result<String, nacl::Error>) -> Result<EncodedEncrResult> {
+  match nacl_result {
+    Ok(s) => Ok(EncodedEncrResult::Ok(s)),
+    Err(err) => match err.condition {
+      nacl::ErrorCondition::CipherVerification => Ok(EncodedEncrResult::CipherVerificationErr),
+      nacl::ErrorCondition::SignatureVerification => Ok(EncodedEncrResult::SignatureVerificationErr),
+      nacl::ErrorCondition::Configuration => Ok(EncodedEncrResult::ConfigurationErr(err.message)),
+    }
+  }
+}
+
+/// Turns plaintext into string, when it is valid UTF-8, wiping bytes otherwise.
+fn utf8_plaintext(m: Vec<u8>) -> core::result::Result<String, nacl::Error> {
+  String::from_utf8(m).map_err(|err| {
+    let message = format!("Plaintext is not valid UTF-8: {}", err.utf8_error());
+    wipe(&mut err.into_bytes());
+    nacl::Error { condition: nacl::ErrorCondition::Configuration, message }
+  })
+}
+
+#[napi]
+pub enum PrefixOpenResult {
+  Ok { m: Buffer, consumed: u32 },
+	CipherVerificationErr,
+	ConfigurationErr(String)
+}
+
+#[napi]
+pub enum NonceOpenResult {
+  Ok { plaintext: Buffer, nonce: Buffer },
+	CipherVerificationErr,
+	ConfigurationErr(String)
+}
+
+#[napi]
+pub enum SignedSealOpenResult {
+  Ok { plaintext: Buffer, sender_pk: Buffer },
+	CipherVerificationErr,
+	SignatureVerificationErr,
+	ConfigurationErr(String)
+}
+
+#[napi]
+pub enum DetachedPackResult {
+  Ok { nonce: Buffer, cipher: Buffer, mac: Buffer },
+	ConfigurationErr(String)
+}
+
+/// Secret box cipher with nonce and authenticator (MAC) kept apart.
+#[napi(object, js_name = "DetachedCipher")]
+pub struct JsDetachedCipher {
+  pub nonce: Buffer,
+  pub cipher: Buffer,
+  pub mac: Buffer
+}
+
+#[napi]
+pub enum KeyedOpenResult {
+  Ok { plaintext: Buffer, key_index: u32 },
+	CipherVerificationErr,
+	ConfigurationErr(String)
+}
+
+#[napi]
+pub enum EphemeralPackResult {
+  Ok { ephemeral_pk: Buffer, cipher: Buffer },
+	ConfigurationErr(String)
+}
+
+#[napi(object, js_name = "GroupMemberKey")]
+pub struct JsGroupMemberKey {
+  pub pk: Buffer,
+  pub wrapped_key: Buffer
+}
+
+#[napi]
+pub enum GroupSealResult {
+  Ok { cipher: Buffer, nonce: Buffer, ephemeral_pk: Buffer, members: Vec<JsGroupMemberKey> },
+	ConfigurationErr(String)
+}
+
+#[napi]
+pub enum SplitFormatWNResult {
+  Ok { nonce: Buffer, body: Buffer },
+	ConfigurationErr(String)
+}
+
+#[napi]
+pub enum FileEncrResult {
+  Ok(f64),
+  CipherVerificationErr,
+  ConfigurationErr(String)
+}
+
+fn into_napi_file_ok(result: core::result::Result<u64, files::FileCryptErr>) -> Result<FileEncrResult> {
+  match result {
+    Ok(content_len) => Ok(FileEncrResult::Ok(content_len as f64)),
+    Err(files::FileCryptErr::Io(message)) => Err(Error::from_reason(message)),
+    Err(files::FileCryptErr::Crypto(err)) => match err.condition {
+      nacl::ErrorCondition::CipherVerification => Ok(FileEncrResult::CipherVerificationErr),
+      _ => Ok(FileEncrResult::ConfigurationErr(err.message)),
+    }
+  }
+}
+
+#[napi]
+pub enum StreamOpenResult {
+  Ok { plaintext_len: f64 },
+  CipherVerificationErr,
+  ConfigurationErr(String)
+}
+
+#[napi]
+pub enum SegmentsResult {
+  Ok { chunks: Vec<Buffer> },
+	CipherVerificationErr,
+	ConfigurationErr(String)
+}
+
+fn into_segments_result(result: core::result::Result<Vec<Vec<u8>>, nacl::Error>) -> SegmentsResult {
+  match result {
+    Ok(chunks) => SegmentsResult::Ok { chunks: chunks.into_iter().map(|chunk| chunk.into()).collect() },
+    Err(err) => match err.condition {
+      nacl::ErrorCondition::CipherVerification => SegmentsResult::CipherVerificationErr,
+      _ => SegmentsResult::ConfigurationErr(err.message)
+    }
+  }
+}
+
+/// Result of `validate_envelope`, with variant, telling which step failed.
+#[napi]
+pub enum EnvelopeOpenResult {
+  Ok { plaintext: Buffer, version: u32 },
+	ParseErr(String),
+	SignatureVerificationErr,
+	CipherVerificationErr,
+	ConfigurationErr(String)
+}
+
+/// Results with variants for failed verification, which can be turned into
+/// rejections, when cryptor is made with `throw_on_verification_failure`.
+trait VerificationFailure {
+  fn verification_failure(&self) -> Option<&'static str>;
+}
+
+const CIPHER_VERIFICATION_FAILURE: &str = "CipherVerificationErr: cipher verification failed";
+const SIGNATURE_VERIFICATION_FAILURE: &str = "SignatureVerificationErr: signature verification failed";
+
+impl VerificationFailure for EncrResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      EncrResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      EncrResult::SignatureVerificationErr => Some(SIGNATURE_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl VerificationFailure for EncodedEncrResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      EncodedEncrResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      EncodedEncrResult::SignatureVerificationErr => Some(SIGNATURE_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl VerificationFailure for PrefixOpenResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      PrefixOpenResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl VerificationFailure for KeyedOpenResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      KeyedOpenResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl VerificationFailure for NonceOpenResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      NonceOpenResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl VerificationFailure for FileEncrResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      FileEncrResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl VerificationFailure for StreamOpenResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      StreamOpenResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl VerificationFailure for SegmentsResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      SegmentsResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl VerificationFailure for SignedSealOpenResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      SignedSealOpenResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      SignedSealOpenResult::SignatureVerificationErr => Some(SIGNATURE_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl VerificationFailure for EnvelopeOpenResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      EnvelopeOpenResult::SignatureVerificationErr => Some(SIGNATURE_VERIFICATION_FAILURE),
+      EnvelopeOpenResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+impl OpOutcome for EnvelopeOpenResult {
+  fn result_kind(&self) -> &'static str {
+    match self {
+      EnvelopeOpenResult::Ok { .. } => "Ok",
+      EnvelopeOpenResult::ParseErr(_) => "ParseErr",
+      EnvelopeOpenResult::SignatureVerificationErr => "SignatureVerificationErr",
+      EnvelopeOpenResult::CipherVerificationErr => "CipherVerificationErr",
+      EnvelopeOpenResult::ConfigurationErr(_) => "ConfigurationErr"
+    }
+  }
+}
+
+impl OpOutcome for StreamOpenResult {
+  fn result_kind(&self) -> &'static str {
+    match self {
+      StreamOpenResult::Ok { .. } => "Ok",
+      StreamOpenResult::CipherVerificationErr => "CipherVerificationErr",
+      StreamOpenResult::ConfigurationErr(_) => "ConfigurationErr"
+    }
+  }
+}
+
+/// Turns verification failure into rejection, when cryptor is set to throw.
+/// Error message is failure's name and description, followed by work label of
+/// operation, like "CipherVerificationErr: cipher verification failed, under
+/// work label 7".
+fn rejecting_failures<R: VerificationFailure>(throw: bool, work_label: u32, result: Result<R>) -> Result<R> {
+  match result {
+    Ok(r) if throw => match r.verification_failure() {
+      Some(failure) => Err(Error::from_reason(format!("{}, under work label {}", failure, work_label))),
+      None => Ok(r)
+    },
+    result => result
+  }
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>> {
+  let mut bytes = vec![0u8; len];
+  match getrandom::fill(&mut bytes) {
+    Ok(()) => Ok(bytes),
+    Err(err) => Err(Error::from_reason(format!("Failed to get random bytes: {}", err)))
+  }
+}
+
+/// Zeroes given bytes with volatile writes, so that wiping isn't optimized
+/// away, when bytes are dropped right after it.
+fn wipe(bytes: &mut [u8]) {
+  for b in bytes.iter_mut() {
+    unsafe { std::ptr::write_volatile(b, 0); }
+  }
+}
+
+/// Numbers of tasks in progress, including reserved slots, under each work
+/// label, and in total.
+struct WorkLabels {
+  /// Counts of tasks per label. Map has default number of shards, as
+  /// admission reads `counts.len()`, which visits every shard, so that more
+  /// shards make it slower, as `label_map_shards_benchmark` shows.
+  counts: DashMap<u32, u32>,
+  in_flight: AtomicU32,
+  /// Part of `in_flight`, that came with `import_label_state`.
+  imported: AtomicU32,
+  all_done: Notify,
+  waits: DashMap<u32, LabelWaits>,
+  /// Reservations in progress per key id, with absent ids having none.
+  key_counts: DashMap<u32, u32>,
+  max_num_of_threads: u32,
+  /// When set, labeled operations reject, instead of queueing, when no more
+  /// tasks can be started under their label.
+  reject_when_saturated: AtomicBool,
+  /// Held while checking and taking a slot in `try_admit`, so that callers
+  /// on different threads can't take the same last slot.
+  admission: Mutex<()>,
+  /// Number of `quiesce` calls in progress, that reject new work.
+  quiescing: AtomicU32,
+  /// Taken for reading, while counting tasks, and for writing by import of
+  /// label state, so that no task is counted or uncounted between import's
+  /// check for work in progress and its store of imported counts.
+  import: RwLock<()>
+}
+
+impl WorkLabels {
+
+  fn new(counts: DashMap<u32, u32>, max_num_of_threads: u32, reject_when_saturated: bool) -> Self {
+    WorkLabels {
+      counts, in_flight: AtomicU32::new(0), imported: AtomicU32::new(0), all_done: Notify::new(),
+      waits: DashMap::new(), key_counts: DashMap::new(), max_num_of_threads,
+      reject_when_saturated: AtomicBool::new(reject_when_saturated),
+      admission: Mutex::new(()),
+      quiescing: AtomicU32::new(0),
+      import: RwLock::new(())
+    }
+  }
+
+}
+
+/// Number of tasks, that can be started under given work label now. Label
+/// without tasks in progress always gets one slot, even when all threads are
+/// taken, so that a new work queue is never starved by busy ones, and pool
+/// is oversubscribed by at most one task per such label.
+fn slots_under_label(labels: &WorkLabels, work_label: u32) -> u32 {
+  let num_of_work_queues = labels.counts.len() as u32;
+  let idle = labels.max_num_of_threads.saturating_sub(num_of_work_queues);
+  let under_label_already = labels.counts.get(&work_label).map(|count| *count);
+  if idle == 0 {
+    // there are more work queues in progress then there are threads,
+    // but if given work queue isn't in progress, we allow one task to be added
+    return if under_label_already.is_some() { 0 } else { 1 }
+  }
+  // threads, not taken by tasks in progress, counting reserved slots
+  let free = labels.max_num_of_threads.saturating_sub(labels.in_flight.load(Ordering::SeqCst));
+  match under_label_already {
+    Some(under_label_already) => idle.saturating_sub(under_label_already).min(free),
+    None => idle.min(free).max(1)
+  }
+}
+
+/// Takes a slot under given work label, when there is one, like counting a
+/// task in `increment_label_count`, returning false, when there is none.
+fn try_admit(labels: &WorkLabels, work_label: u32) -> bool {
+  let _admission = labels.admission.lock().unwrap();
+  if slots_under_label(labels, work_label) == 0 {
+    return false;
+  }
+  increment_label_count(labels, work_label);
+  true
+}
+
+/// Times that tasks under a work label waited for a worker.
+#[derive(Default)]
+struct LabelWaits {
+  count: u64,
+  total: Duration,
+  max: Duration
+}
+
+fn record_label_wait(labels: &WorkLabels, work_label: u32, waited: Duration) {
+  let mut waits = labels.waits.entry(work_label).or_default();
+  waits.count += 1;
+  waits.total += waited;
+  waits.max = waits.max.max(waited);
+}
+
+/// Uncounts task, that has just been counted under given work label, when
+/// `quiesce`, that rejects new work, is in progress, returning true in this
+/// case. Check comes after counting, so that quiesce, that sets its flag
+/// before looking at counts, either waits for task, or task sees the flag.
+fn uncount_if_quiescing(labels: &WorkLabels, work_label: u32) -> bool {
+  if labels.quiescing.load(Ordering::SeqCst) == 0 {
+    return false;
+  }
+  decrement_label_count(labels, work_label);
+  true
+}
+
+fn quiescing_err(work_label: u32) -> Error {
+  Error::from_reason(format!("Cryptor is quiescing, under work label {}", work_label))
+}
+
+/// Counts new task under given work label, rejecting it, when cryptor is set
+/// to reject on saturation and label has no slot, or when `quiesce` rejects
+/// new work.
+fn admit_under_label(labels: &WorkLabels, work_label: u32) -> Result<()> {
+  if !labels.reject_when_saturated.load(Ordering::Relaxed) {
+    increment_label_count(labels, work_label);
+  } else if !try_admit(labels, work_label) {
+    return Err(Error::from_reason(format!("Pool saturated, under work label {}", work_label)));
+  }
+  if uncount_if_quiescing(labels, work_label) {
+    return Err(quiescing_err(work_label));
+  }
+  Ok(())
+}
+
+/// Makes new labeled work rejected, while it is alive.
+struct RejectingNewWork<'a>(&'a WorkLabels);
+
+impl<'a> RejectingNewWork<'a> {
+  fn new(labels: &'a WorkLabels) -> Self {
+    labels.quiescing.fetch_add(1, Ordering::SeqCst);
+    RejectingNewWork(labels)
+  }
+}
+
+impl Drop for RejectingNewWork<'_> {
+  fn drop(&mut self) {
+    self.0.quiescing.fetch_sub(1, Ordering::SeqCst);
+  }
+}
+
+fn increment_label_count(labels: &WorkLabels, work_label: u32) {
+  let _counting = labels.import.read().unwrap();
+  *labels.counts.entry(work_label).or_insert(0) += 1;
+  labels.in_flight.fetch_add(1, Ordering::SeqCst);
+}
+
+fn decrement_label_count(labels: &WorkLabels, work_label: u32) {
+  let _counting = labels.import.read().unwrap();
+  match labels.counts.get_mut(&work_label) {
+    Some(mut label_count) => {
+      *label_count -= 1;
+    },
+    None => {
+      return;
+    }
+  }
+  labels.counts.remove_if(&work_label, |_, &label_count| { label_count == 0 });
+  if labels.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+    labels.all_done.notify_waiters();
+  }
+}
+
+/// Replaces counts of tasks with given imported ones, with their total,
+/// returning false, when there is work in progress, other than imported.
+fn import_label_counts(labels: &WorkLabels, counts: Vec<(u32, u32)>, total: u32) -> bool {
+  let _importing = labels.import.write().unwrap();
+  if labels.in_flight.load(Ordering::SeqCst) != labels.imported.load(Ordering::SeqCst) {
+    return false;
+  }
+  labels.counts.clear();
+  for (work_label, count) in counts {
+    labels.counts.insert(work_label, count);
+  }
+  labels.imported.store(total, Ordering::SeqCst);
+  labels.in_flight.store(total, Ordering::SeqCst);
+  if total == 0 {
+    labels.all_done.notify_waiters();
+  }
+  true
+}
+
+#[cfg(feature = "debug-arg-checks")]
+fn check_arg_len(arg: &str, bytes: &[u8], len_name: &str, len: u32) -> Result<()> {
+  if bytes.len() != len as usize {
+    return Err(Error::from_reason(format!(
+      "Argument {} should have {} = {} bytes, but it is {} bytes long. Is it a wrong argument?",
+      arg, len_name, len, bytes.len()
+    )));
+  }
+  Ok(())
+}
+
+/// Checks lengths of given arguments against given constants, throwing error
+/// with names of argument and constant, in builds with `debug-arg-checks`
+/// feature. Other builds have no checks.
+macro_rules! debug_check_args {
+  ($( $arg:ident : $len:ident ),+) => {
+    #[cfg(feature = "debug-arg-checks")]
+    {
+      $( check_arg_len(stringify!($arg), &$arg, stringify!($len), $len)?; )+
+    }
+  }
+}
+
+/// Counts one more reservation for given key id, unless key already has
+/// given maximum of them.
+fn try_increment_key_count(labels: &WorkLabels, key_id: u32, max_in_flight: u32) -> bool {
+  let mut key_count = labels.key_counts.entry(key_id).or_insert(0);
+  if *key_count >= max_in_flight {
+    drop(key_count);
+    labels.key_counts.remove_if(&key_id, |_, &key_count| { key_count == 0 });
+    return false;
+  }
+  *key_count += 1;
+  true
+}
+
+fn decrement_key_count(labels: &WorkLabels, key_id: u32) {
+  if let Some(mut key_count) = labels.key_counts.get_mut(&key_id) {
+    *key_count -= 1;
+  }
+  labels.key_counts.remove_if(&key_id, |_, &key_count| { key_count == 0 });
+}
+
+macro_rules! compute_in {
+  ($self:ident, $code:expr) => {
+    {
+      let result = $self.rt.spawn(async move {
+        $code
+      }).await;
+      match result {
+        Ok(r) => Ok(r.into()),
+        Err(err) => Err(Error::from_reason(err.message))
+      }
+    }
+  }
+}
+
+/// Does work with data of given size right on calling thread, when it is
+/// smaller than cryptor's `inline_below_bytes`, skipping dispatch to worker,
+/// which costs more than crypto on small inputs. Otherwise work is started
+/// on a worker, or on blocking pool, when cryptor is set to do so.
+macro_rules! spawn_sized {
+  ($self:ident, $size:expr, $code:expr) => {
+    {
+      let size = $size;
+      if $self.inline_below_bytes.is_some_and(|max| size < max) {
+        let result = $code;
+        pool::set_no_queue_wait();
+        result
+      } else {
+        let on_blocking_pool = $self.blocking_pool_min_bytes.is_some_and(|min| size >= min);
+        $self.rt.spawn_work(on_blocking_pool, move || {
+          $code
+        }).await
+      }
+    }
+  }
+}
+
+/// Like `compute_in`, but sends work with data of given size to blocking
+/// pool, or does it inline, when cryptor is set to do so.
+macro_rules! compute_sized_in {
+  ($self:ident, $size:expr, $code:expr) => {
+    {
+      let result = spawn_sized!($self, $size, $code);
+      match result {
+        Ok(r) => Ok(r.into()),
+        Err(err) => Err(Error::from_reason(err.message))
+      }
+    }
+  }
+}
+
+macro_rules! under_label {
+  ($self:ident, $op:literal, $work_label:ident, $computation:expr) => {
+    {
+      admit_under_label(&$self.labels, $work_label)?;
+      under_admitted_label!($self, $op, $work_label, $computation)
+    }
+  }
+}
+
+/// Like `under_label`, for task, that is already counted under its label by
+/// `admit_under_label`, e.g. when admission should come before taking state,
+/// which rejection must not consume.
+macro_rules! under_admitted_label {
+  ($self:ident, $op:literal, $work_label:ident, $computation:expr) => {
+    {
+      let start = Instant::now();
+      let result = $computation;
+      if let Some(waited) = pool::take_queue_wait() {
+        record_label_wait(&$self.labels, $work_label, waited);
+      }
+      decrement_label_count(&$self.labels, $work_label);
+      $self.events.emit($op, $work_label, start, &result);
+      result
+    }
+  }
+}
+
+/// Like `compute_under_label_in`, but sends work with data of given size to
+/// blocking pool, or does it inline, when cryptor is set to do so.
+macro_rules! compute_sized_under_label_in {
+  ($self:ident, $op:literal, $work_label:ident, $size:expr, $code:expr) => {
+    {
+      let result = under_label!($self, $op, $work_label, spawn_sized!($self, $size, $code));
+      rejecting_failures($self.throw_on_verification_failure, $work_label, into_napi_ok(result))
+    }
+  }
+}
+
+macro_rules! compute_under_label_in {
+  ($self:ident, $op:literal, $work_label:ident, $code:expr) => {
+    compute_under_label_in!($self, $op, $work_label, $code, into_napi_ok)
+  };
+  ($self:ident, $op:literal, $work_label:ident, $code:expr, $into_result:ident) => {
+    {
+      let result = under_label!($self, $op, $work_label, $self.rt.spawn(async move {
+        $code
+      }).await);
+      rejecting_failures($self.throw_on_verification_failure, $work_label, $into_result(result))
+    }
+  }
+}
+
+
+const ED25519_SIGNATURE_LENGTH: usize = 64;
+const DEFAULT_STREAM_CHUNK_LENGTH: u32 = 64 * 1024;
+const MAX_PACK_RESERVE_LENGTH: u32 = 64 * 1024;
+
+/// Bytes, wiped when dropped.
+struct SecretBytes(Vec<u8>);
+
+impl Drop for SecretBytes {
+  fn drop(&mut self) {
+    wipe(&mut self.0);
+  }
+}
+
+/// Secret, like a key, kept in Rust memory, and passed into crypto calls by
+/// handle, so that it doesn't have to be a JS buffer at call time. Bytes are
+/// wiped when handle is garbage collected and no calls use them.
+#[napi(js_name = "SecretBuffer")]
+pub struct JsSecretBuffer {
+  bytes: Arc<SecretBytes>
+}
+
+#[napi]
+impl JsSecretBuffer {
+
+  /// Copies bytes of given buffer into secret storage, and wipes the buffer.
+  #[napi(factory)]
+  pub fn from_buffer(mut src: Buffer) -> Self {
+    let bytes = src.to_vec();
+    wipe(&mut src);
+    JsSecretBuffer { bytes: Arc::new(SecretBytes(bytes)) }
+  }
+
+  /// Makes secret with random bytes, which never exist in JS memory. When
+  /// seeded generator is given, bytes are taken from it instead.
+  #[napi(factory)]
+  pub fn random(len: u32, rng: Option<&JsSeededRandom>) -> Result<Self> {
+    Ok(JsSecretBuffer { bytes: Arc::new(SecretBytes(random_bytes_from(rng, len as usize)?)) })
+  }
+
+  #[napi(getter)]
+  pub fn length(&self) -> u32 {
+    self.bytes.0.len() as u32
+  }
+
+}
+
+/// Generator of reproducible "random" bytes from a high-entropy seed, for
+/// deterministic provisioning of many keys from one master secret, and for
+/// tests. Same seed gives the same sequence of outputs, hence seed should be
+/// kept as secret as all keys, made from it. It must not be used for nonces,
+/// which should never repeat.
+#[napi(js_name = "SeededRandom")]
+pub struct JsSeededRandom {
+  rng: Mutex<kdf::SeededRng>
+}
+
+#[napi]
+impl JsSeededRandom {
+
+  /// Makes generator from seed of at least 32 bytes.
+  #[napi(factory)]
+  pub fn from_seed(seed: Buffer) -> Result<Self> {
+    let rng = kdf::SeededRng::new(&seed).map_err(|err| Error::from_reason(err.message))?;
+    Ok(JsSeededRandom { rng: Mutex::new(rng) })
+  }
+
+  #[napi]
+  pub fn next_bytes(&self, len: u32) -> Buffer {
+    self.rng.lock().unwrap().next_bytes(len as usize).into()
+  }
+
+}
+
+fn random_bytes_from(rng: Option<&JsSeededRandom>, len: usize) -> Result<Vec<u8>> {
+  match rng {
+    Some(rng) => Ok(rng.rng.lock().unwrap().next_bytes(len)),
+    None => random_bytes(len)
+  }
+}
+
+fn message_len_err(max_message_bytes: Option<usize>, msg_len: usize) -> Option<String> {
+  match max_message_bytes {
+    Some(max) if msg_len > max => Some(format!(
+      "Message is {} bytes long, exceeding maximum of {} bytes", msg_len, max
+    )),
+    _ => None
+  }
+}
+
+
+/// Result of operation with time it waited for a worker, and time of work.
+/// Flag `immediate` tells whether there was an idle worker, when operation
+/// was submitted, i.e. whether it wasn't queued behind other work.
+#[napi(object, js_name = "TimedEncrResult")]
+pub struct JsTimedEncrResult {
+  pub result: EncrResult,
+  pub queued_ms: f64,
+  pub compute_ms: f64,
+  pub immediate: bool
+}
+
+#[napi(object, js_name = "TimedBuffer")]
+pub struct JsTimedBuffer {
+  pub result: Buffer,
+  pub queued_ms: f64,
+  pub compute_ms: f64,
+  pub immediate: bool
+}
+
+fn as_ms(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
+
+
+#[napi(js_name = "AsyncSBoxCryptor")]
+pub struct JsAsyncSBoxCryptor {
+  labels: Arc<WorkLabels>,
+  events: Arc<OpEvents>,
+  throw_on_verification_failure: bool,
+  max_num_of_threads: u32,
+  max_message_bytes: Option<usize>,
+  blocking_pool_min_bytes: Option<usize>,
+  inline_below_bytes: Option<usize>,
+  memory_budget_bytes: Option<f64>,
+  rt: Arc<CryptoPool>
+}
+
+#[napi]
+impl JsAsyncSBoxCryptor {
+
+  /// Returns number of tasks, that can be started under given work label now,
+  /// counting tasks in progress and reserved slots. Label without tasks in
+  /// progress always gets at least one slot, even when pool is saturated, so
+  /// that new work queues aren't starved by busy ones.
+  #[napi]
+  pub fn can_start_under_work_label(&self, work_label: u32) -> u32 {
+    slots_under_label(&self.labels, work_label)
+  }
+
+  /// Tells whether one more task can be started under given work label now,
+  /// like `can_start_under_work_label` giving non-zero.
+  #[napi]
+  pub fn can_start_one(&self, work_label: u32) -> bool {
+    self.can_start_under_work_label(work_label) > 0
+  }
+
+  /// Tells whether labeled operations reject, when pool is saturated for
+  /// their label, as set with cryptor option `reject_when_saturated`.
+  #[napi(getter)]
+  pub fn reject_when_saturated(&self) -> bool {
+    self.labels.reject_when_saturated.load(Ordering::Relaxed)
+  }
+
+  /// Turns rejection of labeled operations on saturated pool on or off, for
+  /// all parts of cryptor, that share its work labels.
+  #[napi]
+  pub fn set_reject_when_saturated(&self, reject: bool) {
+    self.labels.reject_when_saturated.store(reject, Ordering::Relaxed);
+  }
+
+  /// Returns how many secret box operations with inputs of given size can run
+  /// at the same time, within number of threads and memory budget, set in
+  /// options. Every operation is taken to hold its input and output, i.e.
+  /// twice the input size. When input doesn't fit budget, zero is returned.
+  #[napi]
+  pub fn max_concurrent_for(&self, input_size: f64) -> u32 {
+    match self.memory_budget_bytes {
+      Some(budget) if input_size > 0.0 => {
+        let fit = (budget / (2.0 * (input_size + (SBOX_POLY_LENGTH + SBOX_NONCE_LENGTH) as f64))).floor();
+        (fit.min(self.max_num_of_threads as f64)) as u32
+      },
+      _ => self.max_num_of_threads
+    }
+  }
+
+  /// Returns times, that tasks under given work label waited for a worker,
+  /// or `null`, when there are no records for the label. Records are kept
+  /// until they are reset with `reset_label_wait_stats`.
+  #[napi]
+  pub fn label_wait_stats(&self, work_label: u32) -> Option<JsLabelWaitStats> {
+    self.labels.waits.get(&work_label).map(|waits| JsLabelWaitStats {
+      count: waits.count as f64,
+      avg_ms: as_ms(waits.total) / (waits.count as f64),
+      max_ms: as_ms(waits.max)
+    })
+  }
+
+  /// Drops wait records of given work label, or of all labels, when label
+  /// isn't given.
+  #[napi]
+  pub fn reset_label_wait_stats(&self, work_label: Option<u32>) {
+    match work_label {
+      Some(work_label) => { self.labels.waits.remove(&work_label); },
+      None => self.labels.waits.clear()
+    }
+  }
+
+  /// Reserves a slot under given work label, if one is available, returning
+  /// `null` otherwise, and while `quiesce` rejects new work. Reserved slot
+  /// counts as work in progress until either `release()` is called on
+  /// reservation, or reservation object is garbage collected.
+  #[napi]
+  pub fn try_reserve_under_work_label(&self, work_label: u32) -> Option<JsWorkReservation> {
+    if !try_admit(&self.labels, work_label) || uncount_if_quiescing(&self.labels, work_label) {
+      return None;
+    }
+    Some(JsWorkReservation {
+      labels: self.labels.clone(),
+      work_label,
+      key_id: None,
+      released: false
+    })
+  }
+
+  /// Reserves a slot under given work label, like
+  /// `try_reserve_under_work_label`, that also counts against given key id,
+  /// returning `null`, when either label has no slot, or key already has
+  /// `max_in_flight` reservations (1 by default), e.g. to serialize work on
+  /// a stateful key, like a ratchet. Key ids are chosen by caller, and are
+  /// independent from work labels.
+  #[napi]
+  pub fn try_reserve_for_key(
+    &self, work_label: u32, key_id: u32, max_in_flight: Option<u32>
+  ) -> Option<JsWorkReservation> {
+    if !try_increment_key_count(&self.labels, key_id, max_in_flight.unwrap_or(1)) {
+      return None;
+    }
+    match self.try_reserve_under_work_label(work_label) {
+      Some(mut reservation) => {
+        reservation.key_id = Some(key_id);
+        Some(reservation)
+      },
+      None => {
+        decrement_key_count(&self.labels, key_id);
+        None
+      }
+    }
+  }
+
+  /// Number of reservations in progress for given key id.
+  #[napi]
+  pub fn key_in_flight(&self, key_id: u32) -> u32 {
+    self.labels.key_counts.get(&key_id).map(|count| *count).unwrap_or(0)
+  }
+
+  fn check_message_len(&self, msg_len: usize) -> Option<EncrResult> {
+    message_len_err(self.max_message_bytes, msg_len).map(EncrResult::ConfigurationErr)
+  }
+
+  fn check_open_len(&self, msg_len: usize, max_plaintext_len: Option<u32>) -> Option<EncrResult> {
+    match max_plaintext_len {
+      Some(max) if msg_len > (max as usize) => Some(EncrResult::ConfigurationErr(format!(
+        "Plaintext would be {} bytes long, exceeding given maximum of {} bytes", msg_len, max
+      ))),
+      _ => self.check_message_len(msg_len)
+    }
+  }
+
+  /// Opens cipher. Optional `max_plaintext_len` bounds plaintext, rejecting
+  /// longer ciphers with configuration error before decryption.
+  /// When `empty_on_failure` is set, failed verification gives `Ok` with empty
+  /// plaintext instead of `CipherVerificationErr`. Note that this masks
+  /// tampering, and should only be used where empty plaintext can't be taken
+  /// for a genuine message.
+  #[napi]
+  pub async fn open(
+    &self, c: Buffer, n: Buffer, k: Buffer, work_label: u32, max_plaintext_len: Option<u32>,
+    empty_on_failure: Option<bool>
+  ) -> Result<EncrResult> {
+    debug_check_args!(n: SBOX_NONCE_LENGTH, k: SBOX_KEY_LENGTH);
+    if let Some(err) = self.check_open_len(
+      c.len().saturating_sub(SBOX_POLY_LENGTH as usize), max_plaintext_len
+    ) {
+      return Ok(err);
+    }
+    let result = compute_sized_under_label_in!(self, "open", work_label, c.len(), nacl::secret_box::open(&c, &n, &k))?;
+    match result {
+      EncrResult::CipherVerificationErr if empty_on_failure == Some(true) => Ok(EncrResult::Ok(Vec::new().into())),
+      result => Ok(result)
+    }
+  }
+
+  /// Checks authenticator of cipher, made by `pack`, without decrypting it,
+  /// so that forged ciphers are dropped without allocating for plaintext.
+  #[napi]
+  pub async fn verify_only(&self, c: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<bool> {
+    under_label!(self, "verify_only", work_label, compute_in!(self, xsalsa20poly1305::verify_tag(&c, &n, &k)))
+  }
+
+  /// Checks authenticator of cipher in with-nonce format, using nonce from
+  /// it, without decrypting, like `verify_only` does.
+  #[napi]
+  pub async fn verify_format_w_n(&self, c: Buffer, k: Buffer, work_label: u32) -> Result<bool> {
+    under_label!(self, "verify_format_w_n", work_label, compute_in!(self, {
+      if c.len() < (SBOX_NONCE_LENGTH as usize) {
+        Err(nacl::Error { condition: nacl::ErrorCondition::Configuration, message: format!(
+          "Array c should have at least {} bytes in it, but it is only {} bytes long.", SBOX_NONCE_LENGTH, c.len()
+        ) })
+      } else {
+        let (n, cipher) = c.split_at(SBOX_NONCE_LENGTH as usize);
+        xsalsa20poly1305::verify_tag(cipher, n, &k)
+      }
+    }))
+  }
+
+  /// Decrypts `length` bytes of plaintext, starting at `offset`, from cipher,
+  /// made by `pack`, seeking in keystream, so that the rest of cipher isn't
+  /// processed. This does NOT verify authenticator of cipher, hence it must
+  /// be used only when integrity of cipher is checked by other means, as
+  /// bytes of a forged cipher are decrypted into garbage without any error.
+  /// Range longer than cryptor's `max_message_bytes` is rejected.
+  #[napi]
+  pub async fn open_range(
+    &self, c: Buffer, n: Buffer, k: Buffer, offset: u32, length: u32, work_label: u32
+  ) -> Result<EncrResult> {
+    if let Some(err) = self.check_message_len(length as usize) {
+      return Ok(err);
+    }
+    compute_under_label_in!(self, "open_range", work_label, {
+      xsalsa20poly1305::open_range(&c, &n, &k, offset as usize, length as usize)
+    })
+  }
+
+  /// Validates and opens envelope, which is one byte of version, 24-byte
+  /// nonce, cipher, made by `pack`, and 64-byte Ed25519 signature of all
+  /// bytes before it, i.e. of version, nonce and cipher. Signature is checked
+  /// first, and cipher is opened only when it is valid, all in one worker
+  /// task. Result tells, which step failed. Version isn't interpreted.
+  #[napi]
+  pub async fn validate_envelope(
+    &self, envelope: Buffer, box_k: Buffer, sign_pk: Buffer, work_label: u32
+  ) -> Result<EnvelopeOpenResult> {
+    let head_len = 1 + (SBOX_NONCE_LENGTH as usize);
+    let sig_len = ED25519_SIGNATURE_LENGTH;
+    let min_len = head_len + (SBOX_POLY_LENGTH as usize) + 1 + sig_len;
+    if envelope.len() < min_len {
+      return Ok(EnvelopeOpenResult::ParseErr(format!(
+        "Envelope should have at least {} bytes, but it is only {} bytes long.", min_len, envelope.len()
+      )));
+    }
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(envelope.len() - min_len + 1) {
+      return Ok(EnvelopeOpenResult::ConfigurationErr(msg));
+    }
+    let result = under_label!(self, "validate_envelope", work_label, self.rt.spawn(async move {
+      let (signed, sig) = envelope.split_at(envelope.len() - sig_len);
+      match nacl::sign::verify(sig, signed, &sign_pk) {
+        Ok(true) => {},
+        Ok(false) => return EnvelopeOpenResult::SignatureVerificationErr,
+        Err(err) => return EnvelopeOpenResult::ConfigurationErr(err.message)
+      }
+      let (n, c) = signed[1..].split_at(head_len - 1);
+      match nacl::secret_box::open(c, n, &box_k) {
+        Ok(plaintext) => EnvelopeOpenResult::Ok { plaintext: plaintext.into(), version: signed[0] as u32 },
+        Err(err) => match err.condition {
+          nacl::ErrorCondition::CipherVerification => EnvelopeOpenResult::CipherVerificationErr,
+          _ => EnvelopeOpenResult::ConfigurationErr(err.message)
+        }
+      }
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, Ok(result))
+  }
+
+  /// Opens cipher, made by `pack`, passing plaintext to `on_chunk` in chunks
+  /// of `chunk_len` bytes (64KB by default), so that processing can start
+  /// before all of the plaintext is in memory. Authenticator is checked over
+  /// the whole cipher before the first chunk is decrypted, hence chunks are
+  /// never emitted from a forged cipher, and nothing is emitted, when result
+  /// is `CipherVerificationErr`. Result resolves after the last chunk is
+  /// passed to callback. Like with `open`, plaintext longer than cryptor's
+  /// `max_message_bytes` is rejected before any work.
+  #[napi]
+  pub async fn open_streaming(
+    &self, c: Buffer, n: Buffer, k: Buffer, on_chunk: ThreadsafeFunction<Buffer>, work_label: u32,
+    chunk_len: Option<u32>
+  ) -> Result<StreamOpenResult> {
+    let chunk_len = chunk_len.unwrap_or(DEFAULT_STREAM_CHUNK_LENGTH) as usize;
+    if chunk_len == 0 {
+      return Ok(StreamOpenResult::ConfigurationErr("Chunk length should be greater than zero".to_string()));
+    }
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(
+      c.len().saturating_sub(SBOX_POLY_LENGTH as usize)
+    ) {
+      return Ok(StreamOpenResult::ConfigurationErr(msg));
+    }
+    let result = under_label!(self, "open_streaming", work_label, self.rt.spawn(async move {
+      match xsalsa20poly1305::verify_tag(&c, &n, &k) {
+        Ok(true) => {},
+        Ok(false) => return StreamOpenResult::CipherVerificationErr,
+        Err(err) => return StreamOpenResult::ConfigurationErr(err.message)
+      }
+      let m_len = c.len() - (SBOX_POLY_LENGTH as usize);
+      let mut start = 0;
+      while start < m_len {
+        let end = (start + chunk_len).min(m_len);
+        let chunk = xsalsa20poly1305::decrypt_part(&c, &n, &k, start, end);
+        on_chunk.call(Ok(chunk.into()), ThreadsafeFunctionCallMode::Blocking);
+        start = end;
+      }
+      StreamOpenResult::Ok { plaintext_len: m_len as f64 }
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, Ok(result))
+  }
+
+
+  /// Opens like `open`, with key given by `SecretBuffer` handle.
+  #[napi]
+  pub async fn open_with_secret(
+    &self, c: Buffer, n: Buffer, k: &JsSecretBuffer, work_label: u32
+  ) -> Result<EncrResult> {
+    if let Some(err) = self.check_message_len(c.len().saturating_sub(SBOX_POLY_LENGTH as usize)) {
+      return Ok(err);
+    }
+    let k = k.bytes.clone();
+    compute_under_label_in!(self, "open_with_secret", work_label, nacl::secret_box::open(&c, &n, &k.0))
+  }
+
+  /// Opens cipher `body`, that has no nonce prefix, with nonce `n`, supplied
+  /// separately. It is same as `open`, with lengths of arguments checked
+  /// before passing work to a worker thread.
+  #[napi]
+  pub async fn open_body_with_nonce(
+    &self, body: Buffer, n: Buffer, k: Buffer, work_label: u32
+  ) -> Result<EncrResult> {
+    if body.len() <= SBOX_POLY_LENGTH as usize {
+      return Ok(EncrResult::ConfigurationErr(format!(
+        "Cipher body should be longer than {} bytes, but it is {} bytes long.", SBOX_POLY_LENGTH, body.len()
+      )));
+    }
+    if n.len() != SBOX_NONCE_LENGTH as usize {
+      return Ok(EncrResult::ConfigurationErr(format!(
+        "Nonce n should have {} bytes, but it is {} bytes long.", SBOX_NONCE_LENGTH, n.len()
+      )));
+    }
+    if k.len() != SBOX_KEY_LENGTH as usize {
+      return Ok(EncrResult::ConfigurationErr(format!(
+        "Key k should have {} bytes, but it is {} bytes long.", SBOX_KEY_LENGTH, k.len()
+      )));
+    }
+    self.open(body, n, k, work_label, None, None).await
+  }
+
+  /// Opens cipher of a message with `expected_len` bytes from the start of
+  /// `c`, ignoring bytes that follow it. Result tells how many bytes of `c`
+  /// are consumed by opened cipher.
+  #[napi]
+  pub async fn open_prefix(
+    &self, c: Buffer, n: Buffer, k: Buffer, expected_len: u32, work_label: u32
+  ) -> Result<PrefixOpenResult> {
+    let consumed = (expected_len as usize) + (SBOX_POLY_LENGTH as usize);
+    if expected_len == 0 {
+      return Ok(PrefixOpenResult::ConfigurationErr("Expected message length should be greater than zero".to_string()));
+    }
+    if c.len() < consumed {
+      return Ok(PrefixOpenResult::ConfigurationErr(format!(
+        "Cipher should have at least {} bytes, but it is {} bytes long.", consumed, c.len()
+      )));
+    }
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(expected_len as usize) {
+      return Ok(PrefixOpenResult::ConfigurationErr(msg));
+    }
+    let result = under_label!(self, "open_prefix", work_label, self.rt.spawn(async move {
+      nacl::secret_box::open(&c[0..consumed], &n, &k)
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, match result {
+      Ok(m) => Ok(PrefixOpenResult::Ok { m: m.into(), consumed: consumed as u32 }),
+      Err(err) => match err.condition {
+        nacl::ErrorCondition::CipherVerification => Ok(PrefixOpenResult::CipherVerificationErr),
+        _ => Ok(PrefixOpenResult::ConfigurationErr(err.message))
+      }
+    })
+  }
+
+  #[napi]
+  pub async fn pack(&self, m: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<EncrResult> {
+    debug_check_args!(n: SBOX_NONCE_LENGTH, k: SBOX_KEY_LENGTH);
+    if let Some(err) = self.check_message_len(m.len()) {
+      return Ok(err);
+    }
+    compute_sized_under_label_in!(self, "pack", work_label, m.len(), nacl::secret_box::pack(&m, &n, &k))
+  }
+
+  /// Packs like `pack`, also giving time that work waited for a worker, and
+  /// time of work itself, for tuning of threads and work labels.
+  #[napi]
+  pub async fn pack_timed(&self, m: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<JsTimedEncrResult> {
+    if let Some(err) = self.check_message_len(m.len()) {
+      return Ok(JsTimedEncrResult { result: err, queued_ms: 0.0, compute_ms: 0.0, immediate: true });
+    }
+    let (result, timing) = under_label!(self, "pack_timed", work_label, self.rt.spawn_timed(move || {
+      nacl::secret_box::pack(&m, &n, &k)
+    }).await);
+    Ok(JsTimedEncrResult {
+      result: rejecting_failures(self.throw_on_verification_failure, work_label, into_napi_ok(result))?,
+      queued_ms: as_ms(timing.queued),
+      compute_ms: as_ms(timing.computed),
+      immediate: timing.immediate
+    })
+  }
+
+  /// Opens like `open`, with timings like in `pack_timed`.
+  #[napi]
+  pub async fn open_timed(&self, c: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<JsTimedEncrResult> {
+    if let Some(err) = self.check_message_len(c.len().saturating_sub(SBOX_POLY_LENGTH as usize)) {
+      return Ok(JsTimedEncrResult { result: err, queued_ms: 0.0, compute_ms: 0.0, immediate: true });
+    }
+    let (result, timing) = under_label!(self, "open_timed", work_label, self.rt.spawn_timed(move || {
+      nacl::secret_box::open(&c, &n, &k)
+    }).await);
+    Ok(JsTimedEncrResult {
+      result: rejecting_failures(self.throw_on_verification_failure, work_label, into_napi_ok(result))?,
+      queued_ms: as_ms(timing.queued),
+      compute_ms: as_ms(timing.computed),
+      immediate: timing.immediate
+    })
+  }
+
+  /// Packs like `pack`, with key given by `SecretBuffer` handle.
+  #[napi]
+  pub async fn pack_with_secret(
+    &self, m: Buffer, n: Buffer, k: &JsSecretBuffer, work_label: u32
+  ) -> Result<EncrResult> {
+    if let Some(err) = self.check_message_len(m.len()) {
+      return Ok(err);
+    }
+    let k = k.bytes.clone();
+    compute_under_label_in!(self, "pack_with_secret", work_label, nacl::secret_box::pack(&m, &n, &k.0))
+  }
+
+  /// Opens with-nonce format. Optional `max_plaintext_len` works like in
+  /// `open`.
+  #[napi]
+  pub async fn open_format_w_n(
+    &self, c: Buffer, k: Buffer, work_label: u32, max_plaintext_len: Option<u32>
+  ) -> Result<EncrResult> {
+    debug_check_args!(k: SBOX_KEY_LENGTH);
+    if let Some(err) = self.check_open_len(
+      c.len().saturating_sub((SBOX_NONCE_LENGTH + SBOX_POLY_LENGTH) as usize), max_plaintext_len
+    ) {
+      return Ok(err);
+    }
+    compute_sized_under_label_in!(self, "open_format_w_n", work_label, c.len(), {
+      nacl::secret_box::format_wn::open(&c, &k)
+    })
+  }
+
+  /// Opens a batch of with-nonce format ciphers with the same key, doing
+  /// whole batch in one worker task. Every item gets its own result, so that
+  /// failure of some items doesn't fail the whole batch.
+  #[napi]
+  pub async fn open_many(&self, cs: Vec<Buffer>, k: Buffer, work_label: u32) -> Result<Vec<EncrResult>> {
+    let max_message_bytes = self.max_message_bytes;
+    let results = under_label!(self, "open_many", work_label, self.rt.spawn(async move {
+      cs.iter().map(|c| {
+        let msg_len = c.len().saturating_sub((SBOX_NONCE_LENGTH + SBOX_POLY_LENGTH) as usize);
+        match message_len_err(max_message_bytes, msg_len) {
+          Some(message) => Err(nacl::Error { condition: nacl::ErrorCondition::Configuration, message }),
+          None => nacl::secret_box::format_wn::open(c, &k)
+        }
+      }).collect::<Vec<_>>()
+    }).await);
+    results.into_iter().map(into_napi_ok).collect()
+  }
+
+  /// Copies nonces from a batch of with-nonce format ciphers, like
+  /// `copy_nonce_from_format_w_n`, doing whole batch in one worker task.
+  /// Every item gets its own result, with configuration error for ciphers,
+  /// that are too short.
+  #[napi]
+  pub async fn copy_nonces_from_many(&self, cs: Vec<Buffer>, work_label: u32) -> Result<Vec<EncrResult>> {
+    let results = under_label!(self, "copy_nonces_from_many", work_label, self.rt.spawn(async move {
+      cs.iter().map(|c| {
+        nacl::secret_box::format_wn::copy_nonce_from(c).map(|n| n.to_vec())
+      }).collect::<Vec<_>>()
+    }).await);
+    results.into_iter().map(into_napi_ok).collect()
+  }
+
+  /// Opens all blocks in a buffer with concatenated blocks, each being nonce,
+  /// followed by cipher length as big-endian u32, followed by cipher, made
+  /// by `pack`. Every block gets own result, and truncated trailing block
+  /// gets configuration error, that ends the array.
+  #[napi]
+  pub async fn open_concatenated(&self, c: Buffer, k: Buffer, work_label: u32) -> Result<Vec<EncrResult>> {
+    let max_message_bytes = self.max_message_bytes;
+    let results = under_label!(self, "open_concatenated", work_label, self.rt.spawn(async move {
+      let conf_err = |message: String| Err(nacl::Error { condition: nacl::ErrorCondition::Configuration, message });
+      let block_head_len = (SBOX_NONCE_LENGTH as usize) + 4;
+      let mut results = Vec::new();
+      let mut rest = &c[..];
+      while !rest.is_empty() {
+        if rest.len() < block_head_len {
+          results.push(conf_err(format!("Block {} is truncated in its nonce and length", results.len())));
+          break;
+        }
+        let (n, len_and_rest) = rest.split_at(SBOX_NONCE_LENGTH as usize);
+        let cipher_len = u32::from_be_bytes([len_and_rest[0], len_and_rest[1], len_and_rest[2], len_and_rest[3]]) as usize;
+        let after_head = &len_and_rest[4..];
+        if after_head.len() < cipher_len {
+          results.push(conf_err(format!(
+            "Block {} is truncated, having {} of {} cipher bytes", results.len(), after_head.len(), cipher_len
+          )));
+          break;
+        }
+        let (cipher, after_block) = after_head.split_at(cipher_len);
+        let msg_len = cipher_len.saturating_sub(SBOX_POLY_LENGTH as usize);
+        results.push(match message_len_err(max_message_bytes, msg_len) {
+          Some(message) => conf_err(message),
+          None => nacl::secret_box::open(cipher, n, &k)
+        });
+        rest = after_block;
+      }
+      results
+    }).await);
+    results.into_iter().map(into_napi_ok).collect()
+  }
+
+  /// Opens with-nonce format like `open_format_w_n`, also returning nonce
+  /// from it, for receivers that check sequencing of nonces.
+  #[napi]
+  pub async fn open_format_w_n_with_nonce(
+    &self, c: Buffer, k: Buffer, work_label: u32
+  ) -> Result<NonceOpenResult> {
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(
+      c.len().saturating_sub((SBOX_NONCE_LENGTH + SBOX_POLY_LENGTH) as usize)
+    ) {
+      return Ok(NonceOpenResult::ConfigurationErr(msg));
+    }
+    let result = under_label!(self, "open_format_w_n_with_nonce", work_label, self.rt.spawn(async move {
+      let plaintext = nacl::secret_box::format_wn::open(&c, &k)?;
+      let nonce = nacl::secret_box::format_wn::copy_nonce_from(&c)?.to_vec();
+      Ok::<(Vec<u8>, Vec<u8>), nacl::Error>((plaintext, nonce))
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, match result {
+      Ok((plaintext, nonce)) => Ok(NonceOpenResult::Ok { plaintext: plaintext.into(), nonce: nonce.into() }),
+      Err(err) => match err.condition {
+        nacl::ErrorCondition::CipherVerification => Ok(NonceOpenResult::CipherVerificationErr),
+        _ => Ok(NonceOpenResult::ConfigurationErr(err.message))
+      }
+    })
+  }
+
+  /// Opens cipher, trying given keys in order, in one worker task, like when
+  /// cipher may be under either old, or new key during key rotation. Result
+  /// has plaintext with index of the key, that opened it.
+  #[napi]
+  pub async fn open_with_keys(
+    &self, c: Buffer, n: Buffer, keys: Vec<Buffer>, work_label: u32
+  ) -> Result<KeyedOpenResult> {
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(
+      c.len().saturating_sub(SBOX_POLY_LENGTH as usize)
+    ) {
+      return Ok(KeyedOpenResult::ConfigurationErr(msg));
+    }
+    if keys.is_empty() {
+      return Ok(KeyedOpenResult::ConfigurationErr("There should be at least one key to try".to_string()));
+    }
+    let result = under_label!(self, "open_with_keys", work_label, self.rt.spawn(async move {
+      let mut last_err = None;
+      for (i, k) in keys.iter().enumerate() {
+        match nacl::secret_box::open(&c, &n, k) {
+          Ok(plaintext) => return Ok((plaintext, i as u32)),
+          Err(err) => match err.condition {
+            nacl::ErrorCondition::CipherVerification => { last_err = Some(err); },
+            _ => return Err(err)
+          }
+        }
+      }
+      Err(last_err.unwrap())
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, match result {
+      Ok((plaintext, key_index)) => Ok(KeyedOpenResult::Ok { plaintext: plaintext.into(), key_index }),
+      Err(err) => match err.condition {
+        nacl::ErrorCondition::CipherVerification => Ok(KeyedOpenResult::CipherVerificationErr),
+        _ => Ok(KeyedOpenResult::ConfigurationErr(err.message))
+      }
+    })
+  }
+
+  #[napi]
+  pub async fn pack_format_w_n(&self, m: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<EncrResult> {
+    debug_check_args!(n: SBOX_NONCE_LENGTH, k: SBOX_KEY_LENGTH);
+    if let Some(err) = self.check_message_len(m.len()) {
+      return Ok(err);
+    }
+    compute_sized_under_label_in!(self, "pack_format_w_n", work_label, m.len(), {
+      nacl::secret_box::format_wn::pack(&m, &n, &k)
+    })
+  }
+
+  /// Packs like `pack`, returning cipher after `reserve_bytes` of zeros, which
+  /// caller can fill with own header, without copying cipher into another
+  /// buffer. Reserve can be up to 64KB.
+  #[napi]
+  pub async fn pack_with_reserve(
+    &self, m: Buffer, n: Buffer, k: Buffer, reserve_bytes: u32, work_label: u32
+  ) -> Result<EncrResult> {
+    if reserve_bytes > MAX_PACK_RESERVE_LENGTH {
+      return Ok(EncrResult::ConfigurationErr(format!(
+        "Reserve should be at most {} bytes, but {} bytes are requested", MAX_PACK_RESERVE_LENGTH, reserve_bytes
+      )));
+    }
+    if let Some(err) = self.check_message_len(m.len()) {
+      return Ok(err);
+    }
+    compute_sized_under_label_in!(self, "pack_with_reserve", work_label, m.len(), {
+      nacl::secret_box::pack(&m, &n, &k).map(|c| {
+        let mut out = vec![0u8; (reserve_bytes as usize) + c.len()];
+        out[(reserve_bytes as usize)..].copy_from_slice(&c);
+        out
+      })
+    })
+  }
+
+  /// Packs message in with-nonce format, like `pack_format_w_n`, with a fresh
+  /// random nonce. Like it, large and small messages go to blocking pool or
+  /// inline, as set by cryptor's options.
+  #[napi]
+  pub async fn seal_wn(&self, m: Buffer, k: Buffer, work_label: u32) -> Result<EncrResult> {
+    if let Some(err) = self.check_message_len(m.len()) {
+      return Ok(err);
+    }
+    let n = random_bytes(SBOX_NONCE_LENGTH as usize)?;
+    compute_sized_under_label_in!(self, "seal_wn", work_label, m.len(), {
+      nacl::secret_box::format_wn::pack(&m, &n, &k)
+    })
+  }
+
+  /// Packs like `pack`, returning cipher as base64url string without padding.
+  #[napi]
+  pub async fn pack_base64url(&self, m: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<EncodedEncrResult> {
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(m.len()) {
+      return Ok(EncodedEncrResult::ConfigurationErr(msg));
+    }
+    compute_under_label_in!(self, "pack_base64url", work_label, {
+      nacl::secret_box::pack(&m, &n, &k).map(|c| URL_SAFE_NO_PAD.encode(c))
+    }, into_napi_encoded_ok)
+  }
+
+  /// Opens like `open`, returning plaintext as string. Plaintext, that isn't
+  /// valid UTF-8, gives configuration error.
+  #[napi]
+  pub async fn open_utf8(&self, c: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<EncodedEncrResult> {
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(
+      c.len().saturating_sub(SBOX_POLY_LENGTH as usize)
+    ) {
+      return Ok(EncodedEncrResult::ConfigurationErr(msg));
+    }
+    compute_under_label_in!(self, "open_utf8", work_label, {
+      nacl::secret_box::open(&c, &n, &k).and_then(utf8_plaintext)
+    }, into_napi_encoded_ok)
+  }
+
+  /// Opens like `open_format_w_n`, returning plaintext as string. Plaintext,
+  /// that isn't valid UTF-8, gives configuration error.
+  #[napi]
+  pub async fn open_format_w_n_utf8(&self, c: Buffer, k: Buffer, work_label: u32) -> Result<EncodedEncrResult> {
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(
+      c.len().saturating_sub((SBOX_NONCE_LENGTH + SBOX_POLY_LENGTH) as usize)
+    ) {
+      return Ok(EncodedEncrResult::ConfigurationErr(msg));
+    }
+    compute_under_label_in!(self, "open_format_w_n_utf8", work_label, {
+      nacl::secret_box::format_wn::open(&c, &k).and_then(utf8_plaintext)
+    }, into_napi_encoded_ok)
+  }
+
+  /// Packs message in layout of libsodium's `crypto_secretbox_easy`, i.e. as
+  /// 16-byte poly1305 authenticator, followed by encrypted message, which is
+  /// the same layout, as `pack` gives. Unlike `pack`, empty message is
+  /// allowed, giving 16-byte cipher, as libsodium does.
+  #[napi]
+  pub async fn pack_libsodium_easy(&self, m: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<EncrResult> {
+    if let Some(err) = self.check_message_len(m.len()) {
+      return Ok(err);
+    }
+    compute_sized_under_label_in!(self, "pack_libsodium_easy", work_label, m.len(), {
+      if m.is_empty() {
+        xsalsa20poly1305::pack_empty(&n, &k)
+      } else {
+        nacl::secret_box::pack(&m, &n, &k)
+      }
+    })
+  }
+
+  /// Opens cipher from libsodium's `crypto_secretbox_easy`, or from `pack`.
+  /// Cipher of 16 bytes, which is only an authenticator, opens to an empty
+  /// message.
+  #[napi]
+  pub async fn open_libsodium_easy(&self, c: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<EncrResult> {
+    if let Some(err) = self.check_message_len(c.len().saturating_sub(SBOX_POLY_LENGTH as usize)) {
+      return Ok(err);
+    }
+    compute_sized_under_label_in!(self, "open_libsodium_easy", work_label, c.len(), {
+      if c.len() == (SBOX_POLY_LENGTH as usize) {
+        xsalsa20poly1305::open_empty(&c, &n, &k)
+      } else {
+        nacl::secret_box::open(&c, &n, &k)
+      }
+    })
+  }
+
+
+
+  /// Packs like `pack_format_w_n`, returning cipher as base64url string
+  /// without padding.
+  #[napi]
+  pub async fn pack_format_w_n_base64url(
+    &self, m: Buffer, n: Buffer, k: Buffer, work_label: u32
+  ) -> Result<EncodedEncrResult> {
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(m.len()) {
+      return Ok(EncodedEncrResult::ConfigurationErr(msg));
+    }
+    compute_under_label_in!(self, "pack_format_w_n_base64url", work_label, {
+      nacl::secret_box::format_wn::pack(&m, &n, &k).map(|c| URL_SAFE_NO_PAD.encode(c))
+    }, into_napi_encoded_ok)
+  }
+
+  /// Opens with-nonce cipher `c` with `old_k`, and packs its message with
+  /// `new_k` and a fresh random nonce, without message leaving worker thread.
+  #[napi]
+  pub async fn rewrap_format_w_n(
+    &self, c: Buffer, old_k: Buffer, new_k: Buffer, work_label: u32
+  ) -> Result<EncrResult> {
+    let n = random_bytes(SBOX_NONCE_LENGTH as usize)?;
+    compute_under_label_in!(self, "rewrap_format_w_n", work_label, {
+      nacl::secret_box::format_wn::open(&c, &old_k).and_then(|mut m| {
+        let new_c = nacl::secret_box::format_wn::pack(&m, &n, &new_k);
+        wipe(&mut m);
+        new_c
+      })
+    })
+  }
+
+  /// Packs message with a fresh random nonce, giving nonce, encrypted message
+  /// and 16-byte authenticator (MAC) as separate buffers, for protocols,
+  /// that carry them in distinct fields.
+  #[napi]
+  pub async fn pack_detached_w_n(&self, m: Buffer, k: Buffer, work_label: u32) -> Result<DetachedPackResult> {
+    if let Some(EncrResult::ConfigurationErr(msg)) = self.check_message_len(m.len()) {
+      return Ok(DetachedPackResult::ConfigurationErr(msg));
+    }
+    let n = random_bytes(SBOX_NONCE_LENGTH as usize)?;
+    let result = under_label!(self, "pack_detached_w_n", work_label, self.rt.spawn(async move {
+      nacl::secret_box::pack(&m, &n, &k).map(|c| (n, c))
+    }).await);
+    match result {
+      Ok((n, mut c)) => {
+        let cipher = c.split_off(SBOX_POLY_LENGTH as usize);
+        Ok(DetachedPackResult::Ok { nonce: n.into(), cipher: cipher.into(), mac: c.into() })
+      },
+      Err(err) => Ok(DetachedPackResult::ConfigurationErr(err.message))
+    }
+  }
+
+  /// Opens cipher, made by `pack_detached_w_n`.
+  #[napi]
+  pub async fn open_detached_w_n(
+    &self, detached: JsDetachedCipher, k: Buffer, work_label: u32
+  ) -> Result<EncrResult> {
+    if let Some(err) = self.check_message_len(detached.cipher.len()) {
+      return Ok(err);
+    }
+    if detached.mac.len() != SBOX_POLY_LENGTH as usize {
+      return Ok(EncrResult::ConfigurationErr(format!(
+        "MAC should have {} bytes, but it is {} bytes long.", SBOX_POLY_LENGTH, detached.mac.len()
+      )));
+    }
+    compute_under_label_in!(self, "open_detached_w_n", work_label, {
+      let mut c = Vec::with_capacity(detached.mac.len() + detached.cipher.len());
+      c.extend_from_slice(&detached.mac);
+      c.extend_from_slice(&detached.cipher);
+      nacl::secret_box::open(&c, &detached.nonce, &k)
+    })
+  }
+
+  /// Opens cipher `c`, made by `pack` with nonce `old_n`, and packs its
+  /// message with the same key and a fresh random nonce into with-nonce
+  /// format, for remediation of nonce reuse. Message doesn't leave worker
+  /// thread.
+  #[napi]
+  pub async fn renonce(
+    &self, c: Buffer, old_n: Buffer, k: Buffer, work_label: u32
+  ) -> Result<EncrResult> {
+    let n = random_bytes(SBOX_NONCE_LENGTH as usize)?;
+    compute_under_label_in!(self, "renonce", work_label, {
+      nacl::secret_box::open(&c, &old_n, &k).and_then(|mut m| {
+        let new_c = nacl::secret_box::format_wn::pack(&m, &n, &k);
+        wipe(&mut m);
+        new_c
+      })
+    })
+  }
+
+  /// Encrypts file at `src_path` into `dst_path` in segments of given size,
+  /// reading and writing files on tokio's blocking pool, so that file I/O
+  /// doesn't hold workers. Result has length of encrypted content. Progress
+  /// is reported in percents. Destination, that is the source file, under
+  /// the same or another path, is refused.
+  #[napi]
+  pub async fn encrypt_file(
+    &self, src_path: String, dst_path: String, k: Buffer, segment_size: u32, work_label: u32,
+    report_progress: ThreadsafeFunction<u32>
+  ) -> Result<FileEncrResult> {
+    let base_nonce = random_bytes(SBOX_NONCE_LENGTH as usize)?;
+    let result = under_label!(self, "encrypt_file", work_label, self.rt.spawn_work(true, move || {
+      let cb = |p: u32| {
+        report_progress.call(Ok(p), ThreadsafeFunctionCallMode::Blocking);
+      };
+      files::encrypt_file(&src_path, &dst_path, &k, segment_size as usize, &base_nonce, &cb)
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, into_napi_file_ok(result))
+  }
+
+  /// Decrypts file, produced by `encrypt_file`, writing content into
+  /// `dst_path`. Destination file is removed, if decryption fails. Like in
+  /// `encrypt_file`, destination can't be the source file.
+  #[napi]
+  pub async fn decrypt_file(
+    &self, src_path: String, dst_path: String, k: Buffer, work_label: u32,
+    report_progress: ThreadsafeFunction<u32>
+  ) -> Result<FileEncrResult> {
+    let result = under_label!(self, "decrypt_file", work_label, self.rt.spawn_work(true, move || {
+      let cb = |p: u32| {
+        report_progress.call(Ok(p), ThreadsafeFunctionCallMode::Blocking);
+      };
+      files::decrypt_file(&src_path, &dst_path, &k, &cb)
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, into_napi_file_ok(result))
+  }
+
+  /// Derives subkey by numeric id and 8-byte context from 32-byte master key,
+  /// giving the same subkeys as libsodium's crypto_kdf_derive_from_key.
+  /// Subkey length should be from 16 to 64 bytes. Id is a number, as JS can't
+  /// pass u64, and it should be a non-negative integer.
+  #[napi]
+  pub async fn derive_subkey(
+    &self, master_k: Buffer, subkey_id: i64, context: Buffer, subkey_len: u32, work_label: u32
+  ) -> Result<EncrResult> {
+    if subkey_id < 0 {
+      return Ok(EncrResult::ConfigurationErr(format!("Subkey id should not be negative, but it is {}", subkey_id)));
+    }
+    compute_under_label_in!(self, "derive_subkey", work_label, {
+      kdf::derive_subkey(&master_k, subkey_id as u64, &context, subkey_len as usize)
+    })
+  }
+
+  /// Packs message in with-nonce format with a fresh random nonce, and with
+  /// one-time key, derived from shared secret and message counter by
+  /// HKDF-SHA512, so that exposure of one message key doesn't expose others.
+  /// Counter should be a non-negative integer, and is never to be reused with
+  /// the same shared secret.
+  #[napi]
+  pub async fn pack_with_derived_key(
+    &self, shared_secret: Buffer, counter: i64, m: Buffer, work_label: u32
+  ) -> Result<EncrResult> {
+    if counter < 0 {
+      return Ok(EncrResult::ConfigurationErr(format!("Counter should not be negative, but it is {}", counter)));
+    }
+    if let Some(err) = self.check_message_len(m.len()) {
+      return Ok(err);
+    }
+    let n = random_bytes(SBOX_NONCE_LENGTH as usize)?;
+    compute_sized_under_label_in!(self, "pack_with_derived_key", work_label, m.len(), {
+      kdf::message_key(&shared_secret, counter as u64).and_then(|k| {
+        let k = SecretBytes(k);
+        nacl::secret_box::format_wn::pack(&m, &n, &k.0)
+      })
+    })
+  }
+
+  /// Opens cipher, made by `pack_with_derived_key` with the same shared secret
+  /// and counter.
+  #[napi]
+  pub async fn open_with_derived_key(
+    &self, shared_secret: Buffer, counter: i64, c: Buffer, work_label: u32
+  ) -> Result<EncrResult> {
+    if counter < 0 {
+      return Ok(EncrResult::ConfigurationErr(format!("Counter should not be negative, but it is {}", counter)));
+    }
+    if let Some(err) = self.check_message_len(
+      c.len().saturating_sub((SBOX_NONCE_LENGTH + SBOX_POLY_LENGTH) as usize)
+    ) {
+      return Ok(err);
+    }
+    compute_sized_under_label_in!(self, "open_with_derived_key", work_label, c.len(), {
+      kdf::message_key(&shared_secret, counter as u64).and_then(|k| {
+        let k = SecretBytes(k);
+        nacl::secret_box::format_wn::open(&c, &k.0)
+      })
+    })
+  }
+
+
+  /// Makes symmetric ratchet from initial chain key. Sender and receiver
+  /// should each make ratchet from the same chain key.
+  #[napi]
+  pub fn make_ratchet(&self, chain_key: Buffer) -> Result<JsRatchet> {
+    let chain = ratchet::Chain::new(&chain_key).map_err(Error::from_reason)?;
+    Ok(JsRatchet {
+      rt: self.rt.clone(),
+      labels: self.labels.clone(),
+      events: self.events.clone(),
+      throw_on_verification_failure: self.throw_on_verification_failure,
+      chain: Arc::new(Mutex::new(chain))
+    })
+  }
+
+  /// Makes ring of given reusable output buffers, into which its `pack` and
+  /// `open` write results, instead of returning new buffers.
+  #[napi]
+  pub fn make_output_ring(&self, buffers: Vec<Buffer>) -> Result<JsOutputRing> {
+    if buffers.is_empty() {
+      return Err(Error::from_reason("Output ring should have at least one buffer"));
+    }
+    Ok(JsOutputRing {
+      rt: self.rt.clone(),
+      labels: self.labels.clone(),
+      events: self.events.clone(),
+      throw_on_verification_failure: self.throw_on_verification_failure,
+      max_message_bytes: self.max_message_bytes,
+      slots: Mutex::new(buffers.into_iter().map(|buf| RingSlot { buf: Some(buf), in_use: false }).collect()),
+      cursor: Mutex::new(0)
+    })
+  }
+
+  /// Makes sealer, that packs many messages with given key, advancing nonce
+  /// for every message. Initial nonce is random.
+  #[napi]
+  pub fn make_sealer(&self, k: Buffer) -> Result<JsSealer> {
+    if k.len() != SBOX_KEY_LENGTH as usize {
+      return Err(Error::from_reason(format!(
+        "Key k should have {} bytes, but it is {} bytes long.", SBOX_KEY_LENGTH, k.len()
+      )));
+    }
+    Ok(JsSealer {
+      rt: self.rt.clone(),
+      labels: self.labels.clone(),
+      events: self.events.clone(),
+      throw_on_verification_failure: self.throw_on_verification_failure,
+      max_message_bytes: self.max_message_bytes,
+      blocking_pool_min_bytes: self.blocking_pool_min_bytes,
+      inline_below_bytes: self.inline_below_bytes,
+      k: Arc::new(SecretBytes(k.to_vec())),
+      base_nonce: random_bytes(SBOX_NONCE_LENGTH as usize)?,
+      next_index: Mutex::new(0)
+    })
+  }
+
+  /// Makes packer of stream, that takes content in chunks, as they arrive,
+  /// giving back encrypted stream in segments of given size (64KB by
+  /// default). Initial nonce is random.
+  #[napi]
+  pub fn make_stream_packer(&self, k: Buffer, segment_size: Option<u32>) -> Result<JsStreamPacker> {
+    let segment_size = segment_size.unwrap_or(DEFAULT_STREAM_CHUNK_LENGTH) as usize;
+    let base_nonce = random_bytes(SBOX_NONCE_LENGTH as usize)?;
+    let packer = segments::SegmentsPacker::new(&k, segment_size, base_nonce)
+    .map_err(|err| Error::from_reason(err.message))?;
+    Ok(JsStreamPacker {
+      rt: self.rt.clone(),
+      labels: self.labels.clone(),
+      events: self.events.clone(),
+      throw_on_verification_failure: self.throw_on_verification_failure,
+      packer: Arc::new(Mutex::new(Some(packer)))
+    })
+  }
+
+  /// Makes opener of stream, made by `StreamPacker`, that takes encrypted
+  /// stream in chunks of any size.
+  #[napi]
+  pub fn make_stream_opener(&self, k: Buffer) -> Result<JsStreamOpener> {
+    let opener = segments::SegmentsOpener::new(&k).map_err(|err| Error::from_reason(err.message))?;
+    Ok(JsStreamOpener {
+      rt: self.rt.clone(),
+      labels: self.labels.clone(),
+      events: self.events.clone(),
+      throw_on_verification_failure: self.throw_on_verification_failure,
+      opener: Arc::new(Mutex::new(Some(opener)))
+    })
+  }
+
+  fn clone(&self) -> Self {
+    JsAsyncSBoxCryptor {
+      rt: self.rt.clone(),
+      labels: self.labels.clone(),
+      events: self.events.clone(),
+      throw_on_verification_failure: self.throw_on_verification_failure,
+      max_num_of_threads: self.max_num_of_threads,
+      max_message_bytes: self.max_message_bytes,
+      blocking_pool_min_bytes: self.blocking_pool_min_bytes,
+      inline_below_bytes: self.inline_below_bytes,
+      memory_budget_bytes: self.memory_budget_bytes
+    }
+  }
+
+}
+
+
+#[napi(object, js_name = "RatchetCipher")]
+pub struct JsRatchetCipher {
+  pub cipher: Buffer,
+  pub index: u32
+}
+
+/// Symmetric ratchet, where every message is packed with its own key, derived
+/// from a chain key, which is advanced with every message.
+#[napi(js_name = "Ratchet")]
+pub struct JsRatchet {
+  rt: Arc<CryptoPool>,
+  labels: Arc<WorkLabels>,
+  events: Arc<OpEvents>,
+  throw_on_verification_failure: bool,
+  chain: Arc<Mutex<ratchet::Chain>>
+}
+
+#[napi]
+impl JsRatchet {
+
+  /// Packs message with key of the next message in chain, returning cipher
+  /// together with message's index, needed by receiver to open it.
+  #[napi]
+  pub async fn pack_next(&self, m: Buffer, work_label: u32) -> Result<JsRatchetCipher> {
+    // admission comes first, so that rejected call doesn't use up an index
+    admit_under_label(&self.labels, work_label)?;
+    let (index, msg_key) = match self.chain.lock().unwrap().next_key() {
+      Ok(next) => next,
+      Err(msg) => {
+        decrement_label_count(&self.labels, work_label);
+        return Err(Error::from_reason(msg));
+      }
+    };
+    under_admitted_label!(self, "pack_next", work_label, compute_in!(self, {
+      nacl::secret_box::pack(&m, &msg_key.n, &msg_key.k).map(|c| JsRatchetCipher {
+        cipher: c.into(),
+        index
+      })
+    }))
+  }
+
+  /// Opens cipher of message at given index. Keys of messages, skipped by
+  /// this call, are kept for opening them later, when they come out of order.
+  #[napi]
+  pub async fn open_at(&self, c: Buffer, index: u32, work_label: u32) -> Result<EncrResult> {
+    // admission comes first, so that rejected call doesn't take the key
+    admit_under_label(&self.labels, work_label)?;
+    let msg_key = match self.chain.lock().unwrap().key_at(index) {
+      Ok(msg_key) => msg_key,
+      Err(msg) => {
+        decrement_label_count(&self.labels, work_label);
+        return Ok(EncrResult::ConfigurationErr(msg));
+      }
+    };
+    let (result, msg_key) = under_admitted_label!(self, "open_at", work_label, self.rt.spawn(async move {
+      let result = nacl::secret_box::open(&c, &msg_key.n, &msg_key.k);
+      (result, msg_key)
+    }).await);
+    if result.is_err() {
+      // key stays usable for a genuine message at this index
+      self.chain.lock().unwrap().put_back(index, msg_key);
+    }
+    rejecting_failures(self.throw_on_verification_failure, work_label, into_napi_ok(result))
+  }
+
+}
+
+
+const STREAM_NOT_ACTIVE: &str = "Stream is either finished, failed, or busy with previous call";
+
+/// Packer of stream, which content comes in chunks. Every call resolves with
+/// encrypted segments, that are complete with it, possibly none, and `finish`
+/// gives the rest. Calls should go one after another, like they do from
+/// `transform` and `flush` of Node's `Transform` stream, which calls back
+/// after pushing resolved chunks, and hence stream's backpressure holds work
+/// here too. Overlapping call gets configuration error. Packer can't be used
+/// after it fails or finishes.
+#[napi(js_name = "StreamPacker")]
+pub struct JsStreamPacker {
+  rt: Arc<CryptoPool>,
+  labels: Arc<WorkLabels>,
+  events: Arc<OpEvents>,
+  throw_on_verification_failure: bool,
+  packer: Arc<Mutex<Option<segments::SegmentsPacker>>>
+}
+
+#[napi]
+impl JsStreamPacker {
+
+  /// Takes next chunk of content.
+  #[napi]
+  pub async fn push(&self, chunk: Buffer, work_label: u32) -> Result<SegmentsResult> {
+    let packer = self.packer.clone();
+    let result = under_label!(self, "stream_pack_push", work_label, self.rt.spawn(async move {
+      let mut taken = packer.lock().unwrap().take().ok_or_else(stream_not_active_err)?;
+      let result = taken.push(&chunk);
+      if result.is_ok() {
+        *packer.lock().unwrap() = Some(taken);
+      }
+      result
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, Ok(into_segments_result(result)))
+  }
+
+  /// Ends stream, resolving with its last segment.
+  #[napi]
+  pub async fn finish(&self, work_label: u32) -> Result<SegmentsResult> {
+    let packer = self.packer.clone();
+    let result = under_label!(self, "stream_pack_finish", work_label, self.rt.spawn(async move {
+      let mut taken = packer.lock().unwrap().take().ok_or_else(stream_not_active_err)?;
+      taken.finish()
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, Ok(into_segments_result(result)))
+  }
+
+}
+
+/// Opener of stream, made by `StreamPacker`, that is used like packer, taking
+/// chunks of encrypted stream, and resolving with content of segments, that
+/// are complete and verified. Segment, that may be the last one, is held till
+/// either more bytes come, or `finish` is called, so that truncated stream
+/// fails in `finish`, with segments before it already given out.
+#[napi(js_name = "StreamOpener")]
+pub struct JsStreamOpener {
+  rt: Arc<CryptoPool>,
+  labels: Arc<WorkLabels>,
+  events: Arc<OpEvents>,
+  throw_on_verification_failure: bool,
+  opener: Arc<Mutex<Option<segments::SegmentsOpener>>>
+}
+
+#[napi]
+impl JsStreamOpener {
+
+  /// Takes next chunk of encrypted stream.
+  #[napi]
+  pub async fn push(&self, chunk: Buffer, work_label: u32) -> Result<SegmentsResult> {
+    let opener = self.opener.clone();
+    let result = under_label!(self, "stream_open_push", work_label, self.rt.spawn(async move {
+      let mut taken = opener.lock().unwrap().take().ok_or_else(stream_not_active_err)?;
+      let result = taken.push(&chunk);
+      if result.is_ok() {
+        *opener.lock().unwrap() = Some(taken);
+      }
+      result
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, Ok(into_segments_result(result)))
+  }
+
+  /// Ends stream, resolving with content of its last segment, or failing,
+  /// when stream is truncated.
+  #[napi]
+  pub async fn finish(&self, work_label: u32) -> Result<SegmentsResult> {
+    let opener = self.opener.clone();
+    let result = under_label!(self, "stream_open_finish", work_label, self.rt.spawn(async move {
+      let mut taken = opener.lock().unwrap().take().ok_or_else(stream_not_active_err)?;
+      taken.finish()
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, Ok(into_segments_result(result)))
+  }
+
+}
+
+fn stream_not_active_err() -> nacl::Error {
+  nacl::Error { condition: nacl::ErrorCondition::Configuration, message: STREAM_NOT_ACTIVE.to_string() }
+}
+
+/// Packs messages with one key in with-nonce format, taking a new nonce for
+/// every message, so that nonces are never reused within sealer's lifetime.
+/// Opening needs no state, as with-nonce format carries nonce with cipher.
+#[napi(js_name = "Sealer")]
+pub struct JsSealer {
+  rt: Arc<CryptoPool>,
+  labels: Arc<WorkLabels>,
+  events: Arc<OpEvents>,
+  throw_on_verification_failure: bool,
+  max_message_bytes: Option<usize>,
+  blocking_pool_min_bytes: Option<usize>,
+  inline_below_bytes: Option<usize>,
+  k: Arc<SecretBytes>,
+  base_nonce: Vec<u8>,
+  next_index: Mutex<u64>
+}
+
+#[napi]
+impl JsSealer {
+
+  /// Packs message in with-nonce format with the next nonce.
+  #[napi]
+  pub async fn next(&self, m: Buffer, work_label: u32) -> Result<EncrResult> {
+    if let Some(msg) = message_len_err(self.max_message_bytes, m.len()) {
+      return Ok(EncrResult::ConfigurationErr(msg));
+    }
+    let n = {
+      let mut next_index = self.next_index.lock().unwrap();
+      let index = *next_index;
+      *next_index = match index.checked_add(1) {
+        Some(i) => i,
+        None => return Ok(EncrResult::ConfigurationErr("Sealer has run out of nonces".to_string()))
+      };
+      files::nonce_for_box(&self.base_nonce, index)
+    };
+    let k = self.k.clone();
+    compute_sized_under_label_in!(self, "sealer_next", work_label, m.len(), {
+      nacl::secret_box::format_wn::pack(&m, &n, &k.0)
+    })
+  }
+
+  /// Number of messages, packed by this sealer.
+  #[napi(getter)]
+  pub fn count(&self) -> i64 {
+    *self.next_index.lock().unwrap() as i64
+  }
+
+}
+
+#[napi]
+pub enum RingResult {
+  Ok { index: u32, len: u32 },
+	CipherVerificationErr,
+	NoBufferAvailable,
+	ConfigurationErr(String)
+}
+
+impl VerificationFailure for RingResult {
+  fn verification_failure(&self) -> Option<&'static str> {
+    match self {
+      RingResult::CipherVerificationErr => Some(CIPHER_VERIFICATION_FAILURE),
+      _ => None
+    }
+  }
+}
+
+struct RingSlot {
+  /// Buffer, which is taken out, while operation writes into it.
+  buf: Option<Buffer>,
+  in_use: bool
+}
+
+/// Ring of reusable output buffers, registered by caller, who keeps own
+/// references to them. Every operation writes its result into the next free
+/// buffer, that is long enough, and returns its index with result length, so
+/// that result is `buffers[index].subarray(0, len)`. Buffer stays taken,
+/// until it is given back with `release(index)`. Operations still allocate
+/// result inside of nacl, copying it into ring buffer, but make no new JS
+/// buffers, and hence add no garbage on JS side.
+#[napi(js_name = "OutputRing")]
+pub struct JsOutputRing {
+  rt: Arc<CryptoPool>,
+  labels: Arc<WorkLabels>,
+  events: Arc<OpEvents>,
+  throw_on_verification_failure: bool,
+  max_message_bytes: Option<usize>,
+  slots: Mutex<Vec<RingSlot>>,
+  cursor: Mutex<usize>
+}
+
+#[napi]
+impl JsOutputRing {
+
+  /// Packs message like secret box `pack`, writing cipher into ring buffer.
+  #[napi]
+  pub async fn pack(&self, m: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<RingResult> {
+    if let Some(msg) = message_len_err(self.max_message_bytes, m.len()) {
+      return Ok(RingResult::ConfigurationErr(msg));
+    }
+    let out_len = m.len() + (SBOX_POLY_LENGTH as usize);
+    self.write_to_ring("ring_pack", work_label, out_len, move || nacl::secret_box::pack(&m, &n, &k)).await
+  }
+
+  /// Opens cipher like secret box `open`, writing plaintext into ring buffer.
+  #[napi]
+  pub async fn open(&self, c: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<RingResult> {
+    let out_len = c.len().saturating_sub(SBOX_POLY_LENGTH as usize);
+    if let Some(msg) = message_len_err(self.max_message_bytes, out_len) {
+      return Ok(RingResult::ConfigurationErr(msg));
+    }
+    self.write_to_ring("ring_open", work_label, out_len, move || nacl::secret_box::open(&c, &n, &k)).await
+  }
+
+  /// Gives buffer back to ring, for following operations to write into.
+  #[napi]
+  pub fn release(&self, index: u32) -> Result<()> {
+    let mut slots = self.slots.lock().unwrap();
+    match slots.get_mut(index as usize) {
+      Some(slot) if slot.in_use && slot.buf.is_some() => {
+        slot.in_use = false;
+        Ok(())
+      },
+      Some(_) => Err(Error::from_reason(format!("Ring buffer {} isn't taken by a finished operation", index))),
+      None => Err(Error::from_reason(format!("There is no ring buffer {}", index)))
+    }
+  }
+
+  /// Number of buffers, that aren't taken.
+  #[napi(getter)]
+  pub fn available(&self) -> u32 {
+    self.slots.lock().unwrap().iter().filter(|slot| !slot.in_use).count() as u32
+  }
+
+  fn take_slot(&self, out_len: usize) -> Option<(usize, Buffer)> {
+    let mut slots = self.slots.lock().unwrap();
+    let mut cursor = self.cursor.lock().unwrap();
+    let num_of_slots = slots.len();
+    for i in 0..num_of_slots {
+      let index = (*cursor + i) % num_of_slots;
+      let slot = &mut slots[index];
+      if !slot.in_use && slot.buf.as_ref().is_some_and(|buf| buf.len() >= out_len) {
+        slot.in_use = true;
+        *cursor = (index + 1) % num_of_slots;
+        return slot.buf.take().map(|buf| (index, buf));
+      }
+    }
+    None
+  }
+
+  async fn write_to_ring(
+    &self, op: &'static str, work_label: u32, out_len: usize,
+    work: impl FnOnce() -> core::result::Result<Vec<u8>, nacl::Error> + Send + 'static
+  ) -> Result<RingResult> {
+    admit_under_label(&self.labels, work_label)?;
+    let (index, mut buf) = match self.take_slot(out_len) {
+      Some(slot) => slot,
+      None => {
+        decrement_label_count(&self.labels, work_label);
+        return Ok(RingResult::NoBufferAvailable);
+      }
+    };
+    let start = Instant::now();
+    let (result, buf) = self.rt.spawn(async move {
+      let result = work().map(|mut out| {
+        buf[0..out.len()].copy_from_slice(&out);
+        wipe(&mut out);
+        out.len()
+      });
+      (result, buf)
+    }).await;
+    if let Some(waited) = pool::take_queue_wait() {
+      record_label_wait(&self.labels, work_label, waited);
+    }
+    decrement_label_count(&self.labels, work_label);
+    self.events.emit(op, work_label, start, &result);
+    let mut slots = self.slots.lock().unwrap();
+    let slot = &mut slots[index];
+    slot.buf = Some(buf);
+    slot.in_use = result.is_ok();
+    rejecting_failures(self.throw_on_verification_failure, work_label, match result {
+      Ok(len) => Ok(RingResult::Ok { index: index as u32, len: len as u32 }),
+      Err(err) => match err.condition {
+        nacl::ErrorCondition::CipherVerification => Ok(RingResult::CipherVerificationErr),
+        _ => Ok(RingResult::ConfigurationErr(err.message))
+      }
+    })
+  }
+
+}
+
+#[napi(object, js_name = "LabelWaitStats")]
+pub struct JsLabelWaitStats {
+  /// Number of tasks, which waits are recorded.
+  pub count: f64,
+  pub avg_ms: f64,
+  pub max_ms: f64
+}
+
+#[napi(js_name = "WorkReservation")]
+pub struct JsWorkReservation {
+  labels: Arc<WorkLabels>,
+  work_label: u32,
+  key_id: Option<u32>,
+  released: bool
+}
+
+#[napi]
+impl JsWorkReservation {
+
+  #[napi(getter)]
+  pub fn work_label(&self) -> u32 {
+    self.work_label
+  }
+
+  /// Key id, when reservation is made with `try_reserve_for_key`.
+  #[napi(getter)]
+  pub fn key_id(&self) -> Option<u32> {
+    self.key_id
+  }
+
+  #[napi]
+  pub fn release(&mut self) {
+    if self.released { return; }
+    self.released = true;
+    decrement_label_count(&self.labels, self.work_label);
+    if let Some(key_id) = self.key_id {
+      decrement_key_count(&self.labels, key_id);
+    }
+  }
+
+}
+
+impl Drop for JsWorkReservation {
+  // runs in napi finalizer, so that forgotten reservation doesn't hold slot
+  fn drop(&mut self) {
+    self.release();
+  }
+}
+
+
+/// Checks length of key, naming it in error, before key is hashed for cache
+/// or dispatched to worker.
+fn check_pbox_key_len(name: &str, key: &[u8]) -> Result<()> {
+  if key.len() != PBOX_KEY_LENGTH as usize {
+    return Err(Error::from_reason(format!(
+      "Key array {} should have PBOX_KEY_LENGTH = {} bytes in it, but it is {} bytes long.",
+      name, PBOX_KEY_LENGTH, key.len()
+    )));
+  }
+  Ok(())
+}
+
+#[napi(js_name = "AsyncPBox")]
+pub struct JsAsyncPBox {
+  labels: Arc<WorkLabels>,
+  events: Arc<OpEvents>,
+  throw_on_verification_failure: bool,
+  derivation_cache: Option<Arc<Mutex<DerivationCache>>>,
+  rt: Arc<CryptoPool>
+}
+
+#[napi]
+impl JsAsyncPBox {
+
+  /// Calculates public key from secret key. Secret key of wrong length gives
+  /// configuration error. Result is taken from derivation cache, when it is
+  /// enabled with `pbox_cache_entries` option.
+  #[napi]
+  pub async fn generate_pubkey(&self, sk: Buffer) -> Result<EncrResult> {
+    let cache_key = self.derivation_cache.as_ref()
+    .map(|_| DerivationCache::key_of(b"generate_pubkey", &[&sk]));
+    if let Some(pk) = self.cached(&cache_key) {
+      return Ok(EncrResult::Ok(pk.into()));
+    }
+    let result = self.rt.spawn(async move {
+      nacl::public_box::generate_pubkey(&sk)
+    }).await;
+    if let Ok(pk) = &result {
+      self.remember(cache_key, pk);
+    }
+    into_napi_ok(result)
+  }
+
+  /// Calculates DH shared key, taking it from derivation cache, when it is
+  /// enabled with `pbox_cache_entries` option. Lengths of both keys are
+  /// checked before cache lookup and dispatch, with error naming wrong key.
+  #[napi]
+  pub async fn calc_dhshared_key(&self, pk: Buffer, sk: Buffer) -> Result<Buffer> {
+    check_pbox_key_len("pk", &pk)?;
+    check_pbox_key_len("sk", &sk)?;
+    let cache_key = self.derivation_cache.as_ref()
+    .map(|_| DerivationCache::key_of(b"calc_dhshared_key", &[&pk, &sk]));
+    if let Some(dhshared_key) = self.cached(&cache_key) {
+      return Ok(dhshared_key.into());
+    }
+    let result = self.rt.spawn(async move {
+      nacl::public_box::calc_dhshared_key(&pk, &sk)
+    }).await;
+    match result {
+      Ok(dhshared_key) => {
+        self.remember(cache_key, &dhshared_key);
+        Ok(dhshared_key.into())
+      },
+      Err(err) => Err(Error::from_reason(err.message))
+    }
+  }
+
+  /// Wipes and drops all entries of derivation cache.
+  #[napi]
+  pub fn clear_derivation_cache(&self) {
+    if let Some(cache) = &self.derivation_cache {
+      cache.lock().unwrap().clear();
+    }
+  }
+
+  fn cached(&self, cache_key: &Option<[u8; 64]>) -> Option<Vec<u8>> {
+    match (&self.derivation_cache, cache_key) {
+      (Some(cache), Some(key)) => cache.lock().unwrap().get(key),
+      _ => None
+    }
+  }
+
+  fn remember(&self, cache_key: Option<[u8; 64]>, value: &[u8]) {
+    if let (Some(cache), Some(key)) = (&self.derivation_cache, cache_key) {
+      cache.lock().unwrap().insert(key, value);
+    }
+  }
+
+  /// Debugging aid, that calculates shared key on both sides, i.e. with
+  /// `pk_b` and `sk_a`, and with `pk_a` and `sk_b`, telling with constant-time
+  /// comparison whether these keys are equal.
+  #[napi]
+  pub async fn dh_agrees(&self, pk_a: Buffer, sk_a: Buffer, pk_b: Buffer, sk_b: Buffer) -> Result<bool> {
+    check_pbox_key_len("pk_a", &pk_a)?;
+    check_pbox_key_len("sk_a", &sk_a)?;
+    check_pbox_key_len("pk_b", &pk_b)?;
+    check_pbox_key_len("sk_b", &sk_b)?;
+    compute_in!(self, {
+      nacl::public_box::calc_dhshared_key(&pk_b, &sk_a).and_then(|mut key_a| {
+        let agree = nacl::public_box::calc_dhshared_key(&pk_a, &sk_b).map(|mut key_b| {
+          let agree = nacl::compare(&key_a, &key_b);
+          wipe(&mut key_b);
+          agree
+        });
+        wipe(&mut key_a);
+        agree
+      })
+    })
+  }
+
+  /// Packs message to recipient's public key with a fresh ephemeral keypair,
+  /// doing key generation, DH and packing in one worker task. Ephemeral
+  /// public key should be passed to recipient together with cipher. Ephemeral
+  /// key is taken from seeded generator, when it is given.
+  #[napi]
+  pub async fn pack_ephemeral(
+    &self, m: Buffer, recipient_pk: Buffer, work_label: u32, rng: Option<&JsSeededRandom>
+  ) -> Result<EphemeralPackResult> {
+    debug_check_args!(recipient_pk: PBOX_KEY_LENGTH);
+    let ephemeral_sk = random_bytes_from(rng, PBOX_KEY_LENGTH as usize)?;
+    let result = under_label!(self, "pack_ephemeral", work_label, self.rt.spawn(async move {
+      ephemeral::pack(&m, &recipient_pk, ephemeral_sk)
+    }).await);
+    match result {
+      Ok((ephemeral_pk, cipher)) => Ok(EphemeralPackResult::Ok {
+        ephemeral_pk: ephemeral_pk.into(), cipher: cipher.into()
+      }),
+      Err(err) => Ok(EphemeralPackResult::ConfigurationErr(err.message))
+    }
+  }
+
+  /// Opens cipher, made by `pack_ephemeral`.
+  #[napi]
+  pub async fn open_ephemeral(
+    &self, ephemeral_pk: Buffer, c: Buffer, recipient_sk: Buffer, work_label: u32
+  ) -> Result<EncrResult> {
+    debug_check_args!(ephemeral_pk: PBOX_KEY_LENGTH, recipient_sk: PBOX_KEY_LENGTH);
+    compute_under_label_in!(self, "open_ephemeral", work_label, ephemeral::open(&ephemeral_pk, &c, &recipient_sk))
+  }
+
+  /// Seals message to recipient with ephemeral key, like `pack_ephemeral`,
+  /// together with sender's Ed25519 signature, that binds message, ephemeral
+  /// key and recipient. Signature and sender's signing public key are inside
+  /// of cipher, hence only recipient learns who the sender is. Result is
+  /// ephemeral public key (32 bytes), followed by cipher.
+  #[napi]
+  pub async fn seal_signed(
+    &self, m: Buffer, recipient_pk: Buffer, sender_sign_sk: Buffer, work_label: u32
+  ) -> Result<EncrResult> {
+    let ephemeral_sk = random_bytes(PBOX_KEY_LENGTH as usize)?;
+    compute_under_label_in!(self, "seal_signed", work_label, {
+      ephemeral::pack_signed(&m, &recipient_pk, &sender_sign_sk, ephemeral_sk)
+    })
+  }
+
+  /// Opens sealed message, made by `seal_signed`, returning it with sender's
+  /// signing public key, when both cipher and signature are valid. Valid
+  /// signature only proves that message comes from holder of returned key,
+  /// hence caller should check that it is a known sender's key.
+  #[napi]
+  pub async fn seal_signed_open(
+    &self, sealed: Buffer, recipient_sk: Buffer, work_label: u32
+  ) -> Result<SignedSealOpenResult> {
+    let result = under_label!(self, "seal_signed_open", work_label, self.rt.spawn(async move {
+      ephemeral::open_signed(&sealed, &recipient_sk)
+    }).await);
+    rejecting_failures(self.throw_on_verification_failure, work_label, match result {
+      Ok((plaintext, sender_pk)) => Ok(SignedSealOpenResult::Ok { plaintext: plaintext.into(), sender_pk: sender_pk.into() }),
+      Err(err) => match err.condition {
+        nacl::ErrorCondition::CipherVerification => Ok(SignedSealOpenResult::CipherVerificationErr),
+        nacl::ErrorCondition::SignatureVerification => Ok(SignedSealOpenResult::SignatureVerificationErr),
+        nacl::ErrorCondition::Configuration => Ok(SignedSealOpenResult::ConfigurationErr(err.message))
+      }
+    })
+  }
+
+  /// Packs message once with a fresh data key, and wraps data key to every
+  /// member with one ephemeral key pair, like `pack_ephemeral` does, all in
+  /// one task. Member opens data key with `open_ephemeral`, using returned
+  /// ephemeral public key, and then opens cipher with secret box.
+  #[napi]
+  pub async fn seal_to_group(
+    &self, m: Buffer, member_pks: Vec<Buffer>, work_label: u32
+  ) -> Result<GroupSealResult> {
+    if member_pks.is_empty() {
+      return Ok(GroupSealResult::ConfigurationErr("No group members are given".to_string()));
+    }
+    let data_k = SecretBytes(random_bytes(SBOX_KEY_LENGTH as usize)?);
+    let n = random_bytes(SBOX_NONCE_LENGTH as usize)?;
+    let ephemeral_sk = random_bytes(PBOX_KEY_LENGTH as usize)?;
+    let result = under_label!(self, "seal_to_group", work_label, self.rt.spawn(async move {
+      let cipher = nacl::secret_box::pack(&m, &n, &data_k.0)?;
+      let pks = member_pks.iter().map(|pk| pk.as_ref()).collect::<Vec<&[u8]>>();
+      let (ephemeral_pk, wrapped_keys) = ephemeral::pack_to_many(&data_k.0, &pks, ephemeral_sk)?;
+      Ok::<_, nacl::Error>((cipher, n, ephemeral_pk, member_pks, wrapped_keys))
+    }).await);
+    match result {
+      Ok((cipher, n, ephemeral_pk, member_pks, wrapped_keys)) => Ok(GroupSealResult::Ok {
+        cipher: cipher.into(),
+        nonce: n.into(),
+        ephemeral_pk: ephemeral_pk.into(),
+        members: member_pks.into_iter().zip(wrapped_keys).map(|(pk, wrapped_key)| JsGroupMemberKey {
+          pk, wrapped_key: wrapped_key.into()
+        }).collect()
+      }),
+      Err(err) => Ok(GroupSealResult::ConfigurationErr(err.message))
+    }
+  }
+
+
+  /// Raw X25519 multiplication of 32-byte point by 32-byte scalar. Scalar is
+  /// clamped, as in RFC 7748. Small order points, giving all-zero result, are
+  /// reported as configuration error.
+  #[napi]
+  pub async fn scalarmult(&self, scalar: Buffer, point: Buffer, work_label: u32) -> Result<EncrResult> {
+    compute_under_label_in!(self, "scalarmult", work_label, x25519::scalarmult(&scalar, &point))
+  }
+
+  /// Raw X25519 multiplication of base point by 32-byte scalar, clamped like
+  /// in `scalarmult`.
+  #[napi]
+  pub async fn scalarmult_base(&self, scalar: Buffer, work_label: u32) -> Result<EncrResult> {
+    compute_under_label_in!(self, "scalarmult_base", work_label, x25519::scalarmult_base(&scalar))
+  }
+
+  fn clone(&self) -> Self {
+    JsAsyncPBox {
+      rt: self.rt.clone(),
+      labels: self.labels.clone(),
+      events: self.events.clone(),
+      throw_on_verification_failure: self.throw_on_verification_failure,
+      derivation_cache: self.derivation_cache.clone()
+    }
+  }
+
+}
+
+
+#[napi(js_name = "Keypair")]
+pub struct JsKeypair {
+  #[napi]
+  pub skey: Vec<u8>,
+  #[napi]
+  pub pkey: Vec<u8>
+}
+
+
+#[napi(object, js_name = "VerificationWithDigest")]
+pub struct JsVerificationWithDigest {
+  pub valid: bool,
+  pub digest: Buffer
+}
+
+
+#[napi(object, js_name = "Identity")]
+pub struct JsIdentity {
+  pub skey: Buffer,
+  pub pkey: Buffer,
+  pub fingerprint: String
+}
+
+/// Public keys of identity, ready for publishing, with signing key, box key
+/// of the same secret, and fingerprint of signing key, like in `Identity`.
+#[napi(object, js_name = "IdentityBundle")]
+pub struct JsIdentityBundle {
+  pub signing_pk: Buffer,
+  pub box_pk: Buffer,
+  pub fingerprint: String
+}
+
+impl OpOutcome for BatchVerificationResult {
+  fn result_kind(&self) -> &'static str {
+    match self {
+      BatchVerificationResult::Verified { .. } => "Ok",
+      BatchVerificationResult::FirstFailure { .. } => "SignatureVerificationErr"
+    }
+  }
+}
+
+impl OpOutcome for JsIdentity {
+  fn result_kind(&self) -> &'static str {
+    "Ok"
+  }
+}
+
+/// Number of bytes from sha512 of public key, used in identity fingerprint.
+const FINGERPRINT_LENGTH: usize = 16;
+
+fn check_seed_len(seed: &[u8]) -> Result<()> {
+  if seed.len() != nacl::sign::SEED_LENGTH {
+    return Err(Error::from_reason(format!(
+      "Seed array should have SIGNING_SEED_LENGTH = {} bytes in it, but it is {} bytes long.",
+      nacl::sign::SEED_LENGTH, seed.len()
+    )));
+  }
+  Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Bytes of given hex string, which should be valid, as it comes from code.
+fn from_hex(hex: &str) -> Vec<u8> {
+  (0..hex.len()).step_by(2)
+  .map(|i| u8::from_str_radix(&hex[i..(i + 2)], 16).unwrap())
+  .collect()
+}
+
+
+#[napi(object, js_name = "SignTestVector")]
+pub struct JsSignTestVector {
+  pub seed: Buffer,
+  pub pkey: Buffer,
+  pub m: Buffer,
+  pub sig: Buffer
+}
+
+
+#[napi(object, js_name = "DetachedSignedItem")]
+pub struct JsDetachedSignedItem {
+  pub sig: Buffer,
+  pub m: Buffer,
+  pub pk: Buffer
+}
+
+#[napi]
+pub enum BatchVerificationResult {
+  Verified { valid: Vec<bool> },
+  FirstFailure { index: u32 }
+}
+
+const CONTEXT_SIGNATURE_PREFIX: &[u8] = b"napi-nacl context signature";
+const MAX_SIGNATURE_CONTEXT_LENGTH: usize = 255;
+
+/// Data, that is actually signed for message with context, i.e. ASCII string
+/// "napi-nacl context signature", context length as one byte, context, and
+/// message, like dom2 prefix of Ed25519ctx, which nacl's Ed25519 can't take.
+fn context_signed_data(context: &[u8], m: &[u8]) -> core::result::Result<Vec<u8>, nacl::Error> {
+  if context.len() > MAX_SIGNATURE_CONTEXT_LENGTH {
+    return Err(nacl::Error {
+      condition: nacl::ErrorCondition::Configuration,
+      message: format!(
+        "Context should have at most {} bytes, but it is {} bytes long.", MAX_SIGNATURE_CONTEXT_LENGTH, context.len()
+      )
+    });
+  }
+  let mut data = Vec::with_capacity(CONTEXT_SIGNATURE_PREFIX.len() + 1 + context.len() + m.len());
+  data.extend_from_slice(CONTEXT_SIGNATURE_PREFIX);
+  data.push(context.len() as u8);
+  data.extend_from_slice(context);
+  data.extend_from_slice(m);
+  Ok(data)
+}
+
+#[napi(js_name = "AsyncSigning")]
+pub struct JsAsyncSigning {
+  labels: Arc<WorkLabels>,
+  events: Arc<OpEvents>,
+  throw_on_verification_failure: bool,
+  blocking_pool_min_bytes: Option<usize>,
+  inline_below_bytes: Option<usize>,
+  rt: Arc<CryptoPool>
+}
+
+#[napi]
+impl JsAsyncSigning {
+
+  /// Signs message. Messages of at least `signing_blocking_pool_min_bytes`
+  /// are signed on tokio's blocking pool, here and in `signature_under_label`,
+  /// and are verified there in `verify`.
+  #[napi]
+  pub async fn signature(&self, m: Buffer, sk: Buffer) -> Result<Buffer> {
+    debug_check_args!(sk: SIGNING_SECRET_KEY_LENGTH);
+    compute_sized_in!(self, m.len(), nacl::sign::signature(&m, &sk))
+  }
+
+  #[napi]
+  pub async fn verify(&self, sig: Buffer, m: Buffer, pk: Buffer) -> Result<bool> {
+    debug_check_args!(pk: SIGNING_PUBLIC_KEY_LENGTH);
+    compute_sized_in!(self, m.len(), nacl::sign::verify(&sig, &m, &pk))
+  }
+
+  /// Signs message like `signature`, but under work label, with malformed
+  /// inputs reported in result instead of being thrown.
+  #[napi]
+  pub async fn signature_under_label(&self, m: Buffer, sk: Buffer, work_label: u32) -> Result<EncrResult> {
+    debug_check_args!(sk: SIGNING_SECRET_KEY_LENGTH);
+    compute_sized_under_label_in!(self, "signature_under_label", work_label, m.len(), {
+      nacl::sign::signature(&m, &sk)
+    })
+  }
+
+  /// Signs message, returning signed message in layout of NaCl's
+  /// `crypto_sign`, i.e. 64-byte Ed25519 signature, followed by message
+  /// bytes, so that `signed.subarray(0, 64)` is the signature, that `verify`
+  /// takes with `signed.subarray(64)`.
+  #[napi]
+  pub async fn sign_attached(&self, m: Buffer, sk: Buffer, work_label: u32) -> Result<EncrResult> {
+    compute_under_label_in!(self, "sign_attached", work_label, nacl::sign::sign(&m, &sk))
+  }
+
+  /// Opens signed message, made by `sign_attached`, or by `crypto_sign`,
+  /// returning message, when signature is valid, and
+  /// `SignatureVerificationErr` otherwise.
+  #[napi]
+  pub async fn open_attached(&self, signed: Buffer, pk: Buffer, work_label: u32) -> Result<EncrResult> {
+    compute_under_label_in!(self, "open_attached", work_label, nacl::sign::open(&signed, &pk))
+  }
+
+  /// Signs JSON value, canonicalized by RFC 8785 (JCS), so that signature
+  /// doesn't depend on serializer's key order and whitespace. Values, that
+  /// JCS can't take, like non-finite numbers, give configuration error.
+  #[napi]
+  pub async fn sign_json(&self, value: serde_json::Value, sk: Buffer, work_label: u32) -> Result<EncrResult> {
+    compute_under_label_in!(self, "sign_json", work_label, {
+      jcs::canonicalize(&value).map_err(|message| nacl::Error {
+        condition: nacl::ErrorCondition::Configuration, message
+      }).and_then(|json| nacl::sign::signature(json.as_bytes(), &sk))
+    })
+  }
+
+  /// Verifies signature, made by `sign_json`, of JSON value, which is
+  /// canonicalized by RFC 8785 (JCS) before verification.
+  #[napi]
+  pub async fn verify_json(
+    &self, sig: Buffer, value: serde_json::Value, pk: Buffer, work_label: u32
+  ) -> Result<bool> {
+    under_label!(self, "verify_json", work_label, compute_in!(self, {
+      jcs::canonicalize(&value).map_err(|message| nacl::Error {
+        condition: nacl::ErrorCondition::Configuration, message
+      }).and_then(|json| nacl::sign::verify(&sig, json.as_bytes(), &pk))
+    }))
+  }
+
+  /// Signs message, bound to context of up to 255 bytes, so that signature
+  /// fails verification under other contexts, as well as plain verification.
+  /// Signed data is ASCII string "napi-nacl context signature", context
+  /// length as one byte, context, and message.
+  #[napi]
+  pub async fn sign_with_context(
+    &self, m: Buffer, context: Buffer, sk: Buffer, work_label: u32
+  ) -> Result<EncrResult> {
+    compute_under_label_in!(self, "sign_with_context", work_label, {
+      context_signed_data(&context, &m).and_then(|data| nacl::sign::signature(&data, &sk))
+    })
+  }
+
+  /// Verifies signature, made by `sign_with_context` with the same context.
+  #[napi]
+  pub async fn verify_with_context(
+    &self, sig: Buffer, m: Buffer, context: Buffer, pk: Buffer, work_label: u32
+  ) -> Result<bool> {
+    under_label!(self, "verify_with_context", work_label, compute_in!(self, {
+      context_signed_data(&context, &m).and_then(|data| nacl::sign::verify(&sig, &data, &pk))
+    }))
+  }
+
+  /// Signs message like `signature`, returning signature as base64url string
+  /// without padding.
+  #[napi]
+  pub async fn signature_base64url(&self, m: Buffer, sk: Buffer) -> Result<String> {
+    compute_in!(self, nacl::sign::signature(&m, &sk).map(|sig| URL_SAFE_NO_PAD.encode(sig)))
+  }
+
+  /// Verifies signature of message, given as a sequence of chunks. Ed25519
+  /// passes message through sha512 twice, hence chunks are joined, but it is
+  /// done on worker thread, instead of `Buffer.concat` on JS thread.
+  #[napi]
+  pub async fn verify_chunks(
+    &self, sig: Buffer, chunks: Vec<Buffer>, pk: Buffer, work_label: u32
+  ) -> Result<bool> {
+    under_label!(self, "verify_chunks", work_label, compute_in!(self, {
+      let mut m = Vec::with_capacity(chunks.iter().map(|chunk| chunk.len()).sum());
+      for chunk in chunks.iter() {
+        m.extend_from_slice(chunk);
+      }
+      nacl::sign::verify(&sig, &m, &pk)
+    }))
+  }
+
+  /// Verifies detached signatures of all items in one worker task, giving
+  /// validity of each item. With `stop_on_first_failure`, verification stops
+  /// at the first invalid item, and its index is returned. Items with
+  /// malformed signatures or keys are invalid.
+  #[napi]
+  pub async fn verify_detached_batch(
+    &self, items: Vec<JsDetachedSignedItem>, stop_on_first_failure: bool, work_label: u32
+  ) -> Result<BatchVerificationResult> {
+    Ok(under_label!(self, "verify_detached_batch", work_label, self.rt.spawn(async move {
+      let mut valid = Vec::with_capacity(items.len());
+      for (i, item) in items.iter().enumerate() {
+        let ok = matches!(nacl::sign::verify(&item.sig, &item.m, &item.pk), Ok(true));
+        if !ok && stop_on_first_failure {
+          return BatchVerificationResult::FirstFailure { index: i as u32 };
+        }
+        valid.push(ok);
+      }
+      BatchVerificationResult::Verified { valid }
+    }).await))
+  }
+
+  /// Finds which of known public keys has made given signature of message,
+  /// returning index of that key, or `null`, when signature is not valid for
+  /// any of them. Keys are tried in given order in one worker task.
+  #[napi]
+  pub async fn identify_signer(
+    &self, sig: Buffer, m: Buffer, known_pks: Vec<Buffer>, work_label: u32
+  ) -> Result<Option<u32>> {
+    Ok(under_label!(self, "identify_signer", work_label, self.rt.spawn(async move {
+      known_pks.iter().position(|pk| matches!(nacl::sign::verify(&sig, &m, pk), Ok(true))).map(|i| i as u32)
+    }).await))
+  }
+
+  /// Verifies signature, like `verify`, also returning sha512 of message `m`.
+  #[napi]
+  pub async fn verify_with_digest(
+    &self, sig: Buffer, m: Buffer, pk: Buffer, work_label: u32
+  ) -> Result<JsVerificationWithDigest> {
+    under_label!(self, "verify_with_digest", work_label, compute_in!(self, {
+      nacl::sign::verify(&sig, &m, &pk).map(|valid| {
+        let mut digest = vec![0u8; 64];
+        nacl::sha512::hash_sha512(&mut digest, &m);
+        JsVerificationWithDigest { valid, digest: digest.into() }
+      })
+    }))
+  }
+
+  /// Generates signing keypair together with its fingerprint, which is hex of
+  /// the first 16 bytes of sha512 of public key.
+  #[napi]
+  pub async fn generate_identity(&self, seed: Buffer, work_label: u32) -> Result<JsIdentity> {
+    check_seed_len(&seed)?;
+    Ok(under_label!(self, "generate_identity", work_label, self.rt.spawn(async move {
+      let keypair = nacl::sign::generate_keypair(&seed);
+      let mut pkey_hash = [0u8; 64];
+      nacl::sha512::hash_sha512(&mut pkey_hash, &keypair.pkey);
+      JsIdentity {
+        skey: Vec::from(keypair.skey).into(),
+        pkey: Vec::from(keypair.pkey).into(),
+        fingerprint: to_hex(&pkey_hash[0..FINGERPRINT_LENGTH])
+      }
+    }).await))
+  }
+
+  /// Runs known-answer test vectors through signing, returning pass/fail for
+  /// each vector. When no vectors are given, Ed25519 vectors from RFC 8032
+  /// are used. Note that nacl refuses to sign empty messages, hence signature
+  /// of vector with empty message, like the first RFC one, is only verified.
+  #[napi]
+  pub async fn run_sign_test_vectors(&self, vectors: Option<Vec<JsSignTestVector>>) -> Result<Vec<bool>> {
+    let vectors = match vectors {
+      Some(vectors) => vectors.into_iter().map(|v| sign_vectors::SignVector {
+        seed: v.seed.to_vec(),
+        pkey: v.pkey.to_vec(),
+        m: v.m.to_vec(),
+        sig: v.sig.to_vec()
+      }).collect(),
+      None => sign_vectors::rfc8032_vectors()
+    };
+    Ok(self.rt.spawn(async move {
+      vectors.iter().map(sign_vectors::passes).collect()
+    }).await)
+  }
+
+  #[napi]
+  pub async fn generate_keypair(&self, seed: Buffer) -> Result<JsKeypair> {
+    check_seed_len(&seed)?;
+    let keypair = self.rt.spawn(async move {
+      nacl::sign::generate_keypair(&seed)
+    }).await;
+    Ok(JsKeypair {
+      skey: Vec::from(keypair.skey),
+      pkey: Vec::from(keypair.pkey)
+    })
+  }
+
+  fn clone(&self) -> Self {
+    JsAsyncSigning {
+      rt: self.rt.clone(),
+      labels: self.labels.clone(),
+      events: self.events.clone(),
+      throw_on_verification_failure: self.throw_on_verification_failure,
+      blocking_pool_min_bytes: self.blocking_pool_min_bytes,
+      inline_below_bytes: self.inline_below_bytes
+    }
+  }
+
+}
+
+
+#[cfg(feature = "scrypt")]
+#[napi(object, js_name = "ScryptResult")]
+pub struct JsScryptResult {
+  pub salt: Buffer,
+  pub derived_key: Buffer
+}
+
+/// Parameters of scrypt, picked by `calibrate_scrypt`.
+#[cfg(feature = "scrypt")]
+#[napi(object, js_name = "ScryptParams")]
+pub struct JsScryptParams {
+  pub log_n: u8,
+  pub r: u32,
+  pub p: u32
+}
+
+/// Limit of progress reports of all scrypt operations of a cryptor, taken
+/// together, letting at most one intermediate report through in a gap.
+#[cfg(feature = "scrypt")]
+struct ProgressRateLimit {
+  min_gap: Duration,
+  last_report: Mutex<Option<Instant>>
+}
+
+#[cfg(feature = "scrypt")]
+impl ProgressRateLimit {
+
+  fn new(reports_per_sec: u32) -> Result<Self> {
+    if reports_per_sec == 0 {
+      return Err(Error::from_reason("Rate of scrypt progress reports should be greater than zero"));
+    }
+    Ok(ProgressRateLimit {
+      min_gap: Duration::from_secs(1) / reports_per_sec,
+      last_report: Mutex::new(None)
+    })
+  }
+
+  fn take_slot(&self) -> bool {
+    let mut last_report = self.last_report.lock().unwrap();
+    match *last_report {
+      Some(last_time) if last_time.elapsed() < self.min_gap => false,
+      _ => {
+        *last_report = Some(Instant::now());
+        true
+      }
+    }
+  }
+
+}
+
+/// Passes scrypt progress to JS callback. When minimal interval is set,
+/// reports are coalesced, so that callback is called at most once in the
+/// interval. Reports are also dropped, when cryptor's rate limit of all
+/// scrypt reports is reached. Final 100% report is always delivered.
+#[cfg(feature = "scrypt")]
+struct ThrottledProgress<'a> {
+  report_progress: &'a ThreadsafeFunction<u32>,
+  min_interval: Option<Duration>,
+  rate_limit: Option<&'a ProgressRateLimit>,
+  last_report: Cell<Option<(Instant, u32)>>
+}
+
+#[cfg(feature = "scrypt")]
+impl<'a> ThrottledProgress<'a> {
+
+  fn new(
+    report_progress: &'a ThreadsafeFunction<u32>, min_interval_ms: Option<u32>,
+    rate_limit: Option<&'a ProgressRateLimit>
+  ) -> Self {
+    ThrottledProgress {
+      report_progress,
+      min_interval: min_interval_ms.map(|ms| Duration::from_millis(ms as u64)),
+      rate_limit,
+      last_report: Cell::new(None)
+    }
+  }
+
+  fn report(&self, p: u32) {
+    if let Some(min_interval) = self.min_interval
+    && p < 100
+    && let Some((last_time, _)) = self.last_report.get()
+    && last_time.elapsed() < min_interval {
+      return;
+    }
+    if let Some(rate_limit) = self.rate_limit
+    && p < 100
+    && !rate_limit.take_slot() {
+      return;
+    }
+    self.last_report.set(Some((Instant::now(), p)));
+    self.report_progress.call(Ok(p), ThreadsafeFunctionCallMode::Blocking);
+  }
+
+  fn completed<T>(&self, result: core::result::Result<T, nacl::Error>) -> core::result::Result<T, nacl::Error> {
+    if result.is_ok() && (self.min_interval.is_some() || self.rate_limit.is_some()) {
+      match self.last_report.get() {
+        Some((_, 100)) => {},
+        _ => self.report(100)
+      }
+    }
+    result
+  }
+
+}
+
+#[cfg(feature = "scrypt")]
+const DEFAULT_SCRYPT_SALT_LENGTH: u32 = 16;
+#[cfg(feature = "scrypt")]
+const MIN_SCRYPT_SALT_LENGTH: u32 = 16;
+#[cfg(feature = "scrypt")]
+const DEFAULT_MAX_SCRYPT_DK_LENGTH: u32 = 64 * 1024;
+#[cfg(feature = "scrypt")]
+const CALIBRATION_SCRYPT_R: u32 = 8;
+#[cfg(feature = "scrypt")]
+const CALIBRATION_MIN_LOG_N: u8 = 10;
+#[cfg(feature = "scrypt")]
+const CALIBRATION_PROBE_LOG_N: u8 = 14;
+
+
+#[napi(object, js_name = "CryptorOptions")]
+#[derive(Default, Clone, PartialEq)]
+pub struct JsCryptorOptions {
+  /// Maximum length of message in secret box operations. Longer messages and
+  /// ciphers are rejected with configuration error, without being passed to
+  /// worker threads. There is no limit by default.
+  pub max_message_bytes: Option<u32>,
+  /// Memory, in bytes, that concurrent secret box operations may take, used
+  /// by `max_concurrent_for`. There is no budget by default.
+  pub memory_budget_bytes: Option<f64>,
+  /// Expected number of work labels, with work in progress at the same time,
+  /// for which label map is allocated upfront, so that it isn't resized under
+  /// load.
+  pub expected_labels: Option<u32>,
+  /// Maximum length of key, derived by scrypt. Default is 64KB. Scrypt
+  /// options are ignored in builds without `scrypt` feature.
+  pub max_scrypt_dk_len: Option<u32>,
+  /// Secret box `pack`, `open` and their with-nonce format variants, with
+  /// messages or ciphers of at least this many bytes, are done on tokio's
+  /// blocking pool, so that worker threads stay responsive for small
+  /// operations. Such work still counts under its work label, in
+  /// `can_start_under_work_label`, though it doesn't take a worker.
+  /// By default all work is done on workers.
+  pub blocking_pool_min_bytes: Option<u32>,
+  /// Size of LRU cache of results of pbox `generate_pubkey` and
+  /// `calc_dhshared_key`, which are found by SHA-512 of inputs. Cache is off
+  /// by default. Note that it keeps DH shared keys, which are secret, in
+  /// memory for longer, until they are evicted or `clear_derivation_cache`
+  /// is called, and that hits resolve faster than misses, telling whether
+  /// given keys were used recently.
+  pub pbox_cache_entries: Option<u32>,
+  /// Secret box `pack`, `open` and their with-nonce format variants, as well
+  /// as signing `signature`, `verify` and `signature_under_label`, with
+  /// messages or ciphers shorter than this many bytes, are done right on JS
+  /// thread, inside of async call, as dispatch to worker costs more than
+  /// crypto on small inputs. Such work still counts under its work label
+  /// while it runs. By default all work is dispatched.
+  pub inline_below_bytes: Option<u32>,
+  /// Signing `signature`, `verify` and `signature_under_label`, with messages
+  /// of at least this many bytes, are done on tokio's blocking pool, like
+  /// above, so that occasional large signings don't hold up workers.
+  pub signing_blocking_pool_min_bytes: Option<u32>,
+  /// When set, scrypt is done on tokio's blocking pool, counting like above.
+  pub scrypt_on_blocking_pool: Option<bool>,
+  /// Maximum rate of intermediate progress reports of all scrypt operations
+  /// of cryptor together, per second. Reports above it are dropped, while
+  /// final 100% report of every operation is always delivered. There is no
+  /// limit by default.
+  pub max_scrypt_progress_reports_per_sec: Option<u32>,
+  /// When set, labeled operations reject with "Pool saturated" error right
+  /// away, when `can_start_under_work_label` gives zero for their label,
+  /// instead of waiting for a worker. Operations under reserved slots count
+  /// as new tasks, and can be rejected too. Mode can be changed later with
+  /// `set_reject_when_saturated`. It is off by default.
+  pub reject_when_saturated: Option<bool>,
+  /// When set, operations reject with errors, named after variants
+  /// `CipherVerificationErr` and `SignatureVerificationErr`, instead of
+  /// resolving with these variants. Error messages end with work label of
+  /// failed operation, like ", under work label 7". Batch operations, like
+  /// `open_many`, still give per-item results.
+  pub throw_on_verification_failure: Option<bool>
+}
+
+
+#[napi(js_name = "Cryptor")]
+pub struct JsCryptor {
+  rt: Arc<CryptoPool>,
+  thread_name: String,
+  #[cfg(feature = "scrypt")]
+  max_scrypt_dk_len: u32,
+  #[cfg(feature = "scrypt")]
+  scrypt_on_blocking_pool: bool,
+  #[cfg(feature = "scrypt")]
+  scrypt_progress_limit: Option<Arc<ProgressRateLimit>>,
+  sbox: JsAsyncSBoxCryptor,
+  pbox: JsAsyncPBox,
+  signing: JsAsyncSigning,
+  #[cfg(feature = "scrypt-salt-reuse-check")]
+  on_salt_reuse: Option<Arc<WeakThreadsafeFunction<String>>>
+}
+
+/// Cryptor, shared by the whole process, made by the first `Cryptor.shared`,
+/// together with parameters, it was made with.
+static SHARED_CRYPTOR: Mutex<Option<(SharedCryptorParams, JsCryptor)>> = Mutex::new(None);
+
+#[derive(PartialEq)]
+struct SharedCryptorParams {
+  max_num_of_threads: u32,
+  thread_name: String,
+  options: JsCryptorOptions
+}
+
+#[napi]
+impl JsCryptor {
+
+  /// Returns cryptor, that uses one process-wide pool of threads, made on the
+  /// first call with given parameters. Later calls return cryptors with the
+  /// same pool, work label accounting and event callback, and should come
+  /// with the same parameters, as calls with other parameters throw. Absent
+  /// options are the same as empty ones.
+  ///
+  /// Process-wide means that Node's worker threads get this cryptor too,
+  /// and that there is one `on_event` callback for all of them. It is called
+  /// on JS thread, that has set it, with events of operations from all
+  /// threads, and is replaced by `on_event` from any of them. Hence, set it
+  /// from one thread only, or use `make_treaded` cryptors in workers, that
+  /// need their own events.
+  #[napi(factory)]
+  pub fn shared(
+    max_num_of_threads: u32, thread_name: String, options: Option<JsCryptorOptions>
+  ) -> Result<Self> {
+    let params = SharedCryptorParams {
+      max_num_of_threads,
+      thread_name,
+      options: options.unwrap_or_default()
+    };
+    let mut shared = SHARED_CRYPTOR.lock().unwrap();
+    if let Some((shared_params, cryptor)) = shared.as_ref() {
+      if *shared_params != params {
+        return Err(Error::from_reason(format!(
+          "Shared cryptor is already made with other parameters, with {} threads named {}",
+          shared_params.max_num_of_threads, shared_params.thread_name
+        )));
+      }
+      return Ok(cryptor.clone());
+    }
+    let cryptor = JsCryptor::make_treaded(
+      params.max_num_of_threads, params.thread_name.clone(), Some(params.options.clone())
+    )?;
+    *shared = Some((params, cryptor.clone()));
+    Ok(cryptor)
+  }
+
+  #[napi(factory)]
+  pub fn make_treaded(
+    max_num_of_threads: u32, thread_name: String, options: Option<JsCryptorOptions>
+  ) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let capacity = options.expected_labels.unwrap_or(0) as usize;
+    let labels = WorkLabels::new(
+      DashMap::with_capacity(capacity), max_num_of_threads, options.reject_when_saturated.unwrap_or(false)
+    );
+    let rt = Arc::new(CryptoPool::new(max_num_of_threads, thread_name.clone()));
+    let sbox = JsAsyncSBoxCryptor {
+      rt: rt.clone(),
+      labels: Arc::new(labels),
+      events: Arc::new(OpEvents::default()),
+      throw_on_verification_failure: options.throw_on_verification_failure.unwrap_or(false),
+      max_num_of_threads,
+      max_message_bytes: options.max_message_bytes.map(|max| max as usize),
+      blocking_pool_min_bytes: options.blocking_pool_min_bytes.map(|min| min as usize),
+      inline_below_bytes: options.inline_below_bytes.map(|max| max as usize),
+      memory_budget_bytes: options.memory_budget_bytes
+    };
+    let pbox = JsAsyncPBox {
+      rt: rt.clone(),
+      labels: sbox.labels.clone(),
+      events: sbox.events.clone(),
+      throw_on_verification_failure: sbox.throw_on_verification_failure,
+      derivation_cache: options.pbox_cache_entries
+      .filter(|&entries| entries > 0)
+      .map(|entries| Arc::new(Mutex::new(DerivationCache::new(entries as usize))))
+    };
+    let signing = JsAsyncSigning {
+      rt: rt.clone(),
+      labels: sbox.labels.clone(),
+      events: sbox.events.clone(),
+      throw_on_verification_failure: sbox.throw_on_verification_failure,
+      blocking_pool_min_bytes: options.signing_blocking_pool_min_bytes.map(|min| min as usize),
+      inline_below_bytes: sbox.inline_below_bytes
+    };
+    Ok(JsCryptor {
+      rt,
+      thread_name,
+      #[cfg(feature = "scrypt")]
+      max_scrypt_dk_len: options.max_scrypt_dk_len.unwrap_or(DEFAULT_MAX_SCRYPT_DK_LENGTH),
+      #[cfg(feature = "scrypt")]
+      scrypt_on_blocking_pool: options.scrypt_on_blocking_pool.unwrap_or(false),
+      #[cfg(feature = "scrypt")]
+      scrypt_progress_limit: match options.max_scrypt_progress_reports_per_sec {
+        Some(rate) => Some(Arc::new(ProgressRateLimit::new(rate)?)),
+        None => None
+      },
+      sbox,
+      pbox,
+      signing,
+      #[cfg(feature = "scrypt-salt-reuse-check")]
+      on_salt_reuse: None
+    })
+  }
+
+  fn clone(&self) -> Self {
+    JsCryptor {
+      rt: self.rt.clone(),
+      thread_name: self.thread_name.clone(),
+      #[cfg(feature = "scrypt")]
+      max_scrypt_dk_len: self.max_scrypt_dk_len,
+      #[cfg(feature = "scrypt")]
+      scrypt_on_blocking_pool: self.scrypt_on_blocking_pool,
+      #[cfg(feature = "scrypt")]
+      scrypt_progress_limit: self.scrypt_progress_limit.clone(),
+      sbox: self.sbox.clone(),
+      pbox: self.pbox.clone(),
+      signing: self.signing.clone(),
+      #[cfg(feature = "scrypt-salt-reuse-check")]
+      on_salt_reuse: self.on_salt_reuse.clone()
+    }
+  }
+
+  /// Name, given to all worker threads of this cryptor. Names are set when
+  /// threads are started, and can't be changed afterwards. There are no
+  /// worker threads in builds with `no-thread-pool` feature.
+  #[napi(getter)]
+  pub fn thread_name(&self) -> String {
+    self.thread_name.clone()
+  }
+
+  /// Handles `sbox`, `pbox` and `signing` are all alike. Every getter call
+  /// returns a new handle object, which shares thread pool, work label
+  /// accounting and event callback with this cryptor, hence handles can be
+  /// cached. Each handle keeps thread pool alive, until both cryptor and all
+  /// its handles are garbage collected.
+  #[napi(getter)]
+  pub fn sbox(&self) -> JsAsyncSBoxCryptor {
+    self.sbox.clone()
+  }
+
+  #[napi(getter)]
+  pub fn pbox(&self) -> JsAsyncPBox {
+    self.pbox.clone()
+  }
+
+  #[napi(getter)]
+  pub fn signing(&self) -> JsAsyncSigning {
+    self.signing.clone()
+  }
+
+  /// Measures throughput and latency of given operation ("pack", "open",
+  /// "sign" or "scrypt") on this cryptor's threads. Operations are submitted
+  /// by `concurrency` parallel lanes, `iterations` in total, with messages of
+  /// `input_size` bytes.
+  #[napi]
+  pub async fn benchmark(
+    &self, op: String, input_size: u32, iterations: u32, concurrency: u32
+  ) -> Result<bench::JsBenchmarkResult> {
+    bench::run_benchmark(self.rt.clone(), op, input_size, iterations, concurrency).await
+  }
+
+  /// Makes sure that all worker threads are up, so that first operations
+  /// don't pay thread start latency. Resolves when every worker has run a
+  /// warmup task, hence it waits, if workers are busy.
+  #[napi]
+  pub async fn warmup(&self) {
+    self.rt.warmup(self.sbox.max_num_of_threads).await
+  }
+
+  /// Number of tasks in progress under all work labels, counting reserved
+  /// slots.
+  #[napi]
+  pub fn total_tasks_in_flight(&self) -> u32 {
+    self.sbox.labels.in_flight.load(Ordering::SeqCst)
+  }
 
-macro_rules! compute_in {
-  ($self:ident, $code:expr) => {
-    {
-      let result = $self.rt.spawn(async move {
-        $code
-      }).await.unwrap();
-      match result {
-        Ok(r) => Ok(r.into()),
-        Err(err) => Err(Error::from_reason(err.message))
+  /// Returns counts of tasks in progress under work labels, counting reserved
+  /// slots, as an object with labels for keys.
+  #[napi]
+  pub fn export_label_state(&self) -> HashMap<String, u32> {
+    self.sbox.labels.counts.iter()
+    .map(|entry| (entry.key().to_string(), *entry.value()))
+    .collect()
+  }
+
+  /// Replaces counts of tasks under work labels with given ones, like those
+  /// from `export_label_state`. Imported counts are taken as work in
+  /// progress by admission methods and `quiesce`, until they are replaced by
+  /// another import, e.g. of an empty object. Import is refused, while there
+  /// is actual work in progress, or reserved slots.
+  #[napi]
+  pub fn import_label_state(&self, state: HashMap<String, u32>) -> Result<()> {
+    let mut counts = Vec::with_capacity(state.len());
+    for (label, count) in state {
+      let work_label = label.parse::<u32>().map_err(|_| Error::from_reason(format!(
+        "Work label should be an unsigned 32-bit integer, but it is {}", label
+      )))?;
+      if count > 0 {
+        counts.push((work_label, count));
       }
     }
+    let total = counts.iter().try_fold(0u32, |total, &(_, count)| total.checked_add(count))
+    .ok_or_else(|| Error::from_reason("Total of imported counts overflows"))?;
+    if !import_label_counts(&self.sbox.labels, counts, total) {
+      return Err(Error::from_reason("Label state can't be imported, while there is work in progress"));
+    }
+    Ok(())
   }
-}
 
-macro_rules! compute_under_label_in {
-  ($self:ident, $work_label:ident, $code:expr) => {
-    {
-      $self.increment_label_count($work_label);
-      let result = $self.rt.spawn(async move {
-        $code
-      }).await.unwrap();
-      $self.decrement_label_count($work_label);
-      into_napi_ok(result)
+  /// Resolves when there are no tasks in progress under work labels, nor
+  /// reserved slots. New work is still accepted while waiting, and it is
+  /// waited for too, hence callers should stop sending work first, or set
+  /// `reject_new_work`. With it, labeled operations, started while waiting,
+  /// reject with "Cryptor is quiescing" error, and reservations give `null`,
+  /// till this call resolves. Work, done without work labels, like scrypt,
+  /// isn't waited for.
+  #[napi]
+  pub async fn quiesce(&self, reject_new_work: Option<bool>) {
+    let labels = &self.sbox.labels;
+    let _rejecting = reject_new_work.unwrap_or(false).then(|| RejectingNewWork::new(labels));
+    loop {
+      let all_done = labels.all_done.notified();
+      if labels.in_flight.load(Ordering::SeqCst) == 0 {
+        return;
+      }
+      all_done.await;
     }
   }
-}
 
+  /// Sets callback, that gets an event after every operation, done under
+  /// work label, with operation's name, duration and kind of result.
+  /// Callback doesn't keep Node's event loop alive. Cryptors from `shared`
+  /// have one callback for all worker threads, see there.
+  #[napi]
+  pub fn on_event(
+    &self, cb: ThreadsafeFunction<events::JsOpEvent, Unknown<'static>, events::JsOpEvent, Status, true, true>
+  ) {
+    self.sbox.events.set(cb);
+  }
 
-#[napi(js_name = "AsyncSBoxCryptor")]
-pub struct JsAsyncSBoxCryptor {
-  labels: DashMap<u32, u32>,
-  max_num_of_threads: u32,
-  rt: Arc<Runtime>
 }
 
+/// Key derivation with scrypt, absent in builds without `scrypt` feature.
+#[cfg(feature = "scrypt")]
 #[napi]
-impl JsAsyncSBoxCryptor {
+impl JsCryptor {
 
+  /// Derives key with scrypt. Its working memory, 128 * r * 2^log_n bytes,
+  /// is allocated on every call, unless it is reserved on a worker with
+  /// `prealloc_scrypt` for these log_n and r.
   #[napi]
-  pub fn can_start_under_work_label(&self, work_label: u32) -> u32 {
-    let num_of_work_queues = self.labels.len() as u32;
-    let idle = if self.max_num_of_threads <= num_of_work_queues { 0 } else {
-      self.max_num_of_threads - num_of_work_queues
-    };
-    if idle == 0 {
-      // there are more work queues in progress then there are threads,
-      // but if given work queue isn't in progress, we allow one task to be added
-      return if self.labels.contains_key(&work_label) { 0 } else { 1 }
-    }
-    match self.labels.get(&work_label) {
-      Some(under_label_already) => {
-        if idle <= *under_label_already  { 0 } else {
-          idle - *under_label_already
-        } 
-      },
-      None => idle
+  #[allow(clippy::too_many_arguments)]
+  pub async fn scrypt(
+    &self, passwd: Buffer, salt: Buffer, log_n: u8, r: u32, p: u32, dk_len: u32,
+    report_progress: ThreadsafeFunction<u32>, min_progress_interval_ms: Option<u32>
+  ) -> Result<Buffer> {
+    self.check_dk_len(dk_len)?;
+    #[cfg(feature = "scrypt-salt-reuse-check")]
+    if let Some(on_salt_reuse) = &self.on_salt_reuse
+    && salt_reuse::record_scrypt_use(&salt, log_n, r, p, dk_len) {
+      on_salt_reuse.call(
+        Ok("scrypt is called with the same salt and parameters more than once".to_string()),
+        ThreadsafeFunctionCallMode::NonBlocking
+      );
+    }
+    let rate_limit = self.scrypt_progress_limit.clone();
+    let result = self.rt.spawn_work(self.scrypt_on_blocking_pool, move || {
+      let cb = ThrottledProgress::new(&report_progress, min_progress_interval_ms, rate_limit.as_deref());
+      cb.completed(scrypt::scrypt(
+        &passwd, &salt, log_n, r as usize, p as usize, dk_len as usize, &|p| cb.report(p)
+      ))
+    }).await;
+    match result {
+      Ok(key) => Ok(key.into()),
+      Err(err) => Err(Error::from_reason(err.message))
     }
   }
 
-  fn increment_label_count(&self, work_label: u32) {
-    match self.labels.get_mut(&work_label) {
-      Some(mut label_count) => {
-        *label_count += 1;
+  /// Derives several keys with one scrypt run, which derives material of all
+  /// keys' total length, and splits it into keys of lengths, given in
+  /// `key_lens`, in that order.
+  #[napi]
+  #[allow(clippy::too_many_arguments)]
+  pub async fn scrypt_derive(
+    &self, passwd: Buffer, salt: Buffer, log_n: u8, r: u32, p: u32, key_lens: Vec<u32>,
+    report_progress: ThreadsafeFunction<u32>, min_progress_interval_ms: Option<u32>
+  ) -> Result<Vec<Buffer>> {
+    if key_lens.contains(&0) {
+      return Err(Error::from_reason("Length of every derived key should be greater than zero"));
+    }
+    let dk_len = key_lens.iter().try_fold(0u32, |total, &len| total.checked_add(len))
+    .ok_or_else(|| Error::from_reason("Total length of derived keys is too big"))?;
+    self.check_dk_len(dk_len)?;
+    #[cfg(feature = "scrypt-salt-reuse-check")]
+    if let Some(on_salt_reuse) = &self.on_salt_reuse
+    && salt_reuse::record_scrypt_use(&salt, log_n, r, p, dk_len) {
+      on_salt_reuse.call(
+        Ok("scrypt is called with the same salt and parameters more than once".to_string()),
+        ThreadsafeFunctionCallMode::NonBlocking
+      );
+    }
+    let rate_limit = self.scrypt_progress_limit.clone();
+    let result = self.rt.spawn_work(self.scrypt_on_blocking_pool, move || {
+      let cb = ThrottledProgress::new(&report_progress, min_progress_interval_ms, rate_limit.as_deref());
+      cb.completed(scrypt::scrypt(
+        &passwd, &salt, log_n, r as usize, p as usize, dk_len as usize, &|p| cb.report(p)
+      ))
+    }).await;
+    match result {
+      Ok(mut material) => {
+        let mut keys = Vec::with_capacity(key_lens.len());
+        let mut offset = 0;
+        for len in key_lens {
+          let end = offset + (len as usize);
+          keys.push(material[offset..end].to_vec().into());
+          offset = end;
+        }
+        wipe(&mut material);
+        Ok(keys)
       },
-      None => {
-        self.labels.insert(work_label, 1);
-      }
+      Err(err) => Err(Error::from_reason(err.message))
     }
   }
 
-  fn decrement_label_count(&self, work_label: u32) {
-    match self.labels.get_mut(&work_label) {
-      Some(mut label_count) => {
-        *label_count -= 1;
-      },
-      None => {
-        return;
+  /// Reserves working memory of scrypt with given log_n and r, i.e. 128 * r *
+  /// 2^log_n bytes, on every worker, replacing memory, reserved before.
+  /// Following scrypt calls with these log_n and r reuse it, one call at a
+  /// time on each worker, and other calls allocate their own memory. Nothing
+  /// is shared between workers, and memory is wiped after every call. This
+  /// doesn't help scrypt on blocking pool, set with `scrypt_on_blocking_pool`.
+  /// Returns error, when memory can't be allocated on some worker.
+  #[napi]
+  pub async fn prealloc_scrypt(&self, log_n: u8, r: u32) -> Result<()> {
+    if r == 0 {
+      return Err(Error::from_reason("r should be greater than zero"));
+    }
+    scrypt::memory_words(log_n, r as usize).map_err(|err| Error::from_reason(err.message))?;
+    let failure = Arc::new(Mutex::new(None));
+    let worker_failure = failure.clone();
+    self.rt.on_every_worker(self.sbox.max_num_of_threads, move || {
+      if let Err(err) = scrypt::reserve(log_n, r as usize) {
+        *worker_failure.lock().unwrap() = Some(err.message);
       }
+    }).await;
+    match failure.lock().unwrap().take() {
+      Some(message) => Err(Error::from_reason(message)),
+      None => Ok(())
     }
-    self.labels.remove_if(&work_label, |_, &label_count| { label_count <= 0 });
   }
 
+  /// Derives signing keypair from passphrase, with scrypt giving 32-byte seed
+  /// for `generate_keypair`, all in one worker task, under given work label.
+  /// Seed is wiped, and never gets to JS. Same passphrase, salt and
+  /// parameters always give the same keypair.
   #[napi]
-  pub async fn open(&self, c: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<EncrResult> {
-    compute_under_label_in!(self, work_label, nacl::secret_box::open(&c, &n, &k))
+  pub async fn keypair_from_passphrase(
+    &self, passwd: Buffer, salt: Buffer, log_n: u8, r: u32, p: u32, work_label: u32
+  ) -> Result<JsKeypair> {
+    let seed_len = nacl::sign::SEED_LENGTH as u32;
+    #[cfg(feature = "scrypt-salt-reuse-check")]
+    if let Some(on_salt_reuse) = &self.on_salt_reuse
+    && salt_reuse::record_scrypt_use(&salt, log_n, r, p, seed_len) {
+      on_salt_reuse.call(
+        Ok("scrypt is called with the same salt and parameters more than once".to_string()),
+        ThreadsafeFunctionCallMode::NonBlocking
+      );
+    }
+    let sbox = &self.sbox;
+    let result = under_label!(sbox, "keypair_from_passphrase", work_label, {
+      self.rt.spawn_work(self.scrypt_on_blocking_pool, move || {
+        scrypt::scrypt(&passwd, &salt, log_n, r as usize, p as usize, seed_len as usize, &|_| {})
+        .map(|mut seed| {
+          let keypair = nacl::sign::generate_keypair(&seed);
+          wipe(&mut seed);
+          keypair
+        })
+      }).await
+    });
+    match result {
+      Ok(keypair) => Ok(JsKeypair {
+        skey: Vec::from(keypair.skey),
+        pkey: Vec::from(keypair.pkey)
+      }),
+      Err(err) => Err(Error::from_reason(err.message))
+    }
   }
 
+  /// Does scrypt with a freshly generated random salt of `salt_len` bytes
+  /// (16 by default), returning both salt and derived key.
   #[napi]
-  pub async fn pack(&self, m: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<EncrResult> {
-    compute_under_label_in!(self, work_label, nacl::secret_box::pack(&m, &n, &k))
+  #[allow(clippy::too_many_arguments)]
+  pub async fn scrypt_with_random_salt(
+    &self, passwd: Buffer, log_n: u8, r: u32, p: u32, dk_len: u32, salt_len: Option<u32>,
+    report_progress: ThreadsafeFunction<u32>, min_progress_interval_ms: Option<u32>
+  ) -> Result<JsScryptResult> {
+    self.check_dk_len(dk_len)?;
+    let salt_len = salt_len.unwrap_or(DEFAULT_SCRYPT_SALT_LENGTH);
+    if salt_len < MIN_SCRYPT_SALT_LENGTH {
+      return Err(Error::from_reason(format!(
+        "Salt should be at least {} bytes long, but {} was requested", MIN_SCRYPT_SALT_LENGTH, salt_len
+      )));
+    }
+    let salt = random_bytes(salt_len as usize)?;
+    let salt_for_task = salt.clone();
+    let rate_limit = self.scrypt_progress_limit.clone();
+    let result = self.rt.spawn_work(self.scrypt_on_blocking_pool, move || {
+      let cb = ThrottledProgress::new(&report_progress, min_progress_interval_ms, rate_limit.as_deref());
+      cb.completed(scrypt::scrypt(
+        &passwd, &salt_for_task, log_n, r as usize, p as usize, dk_len as usize, &|p| cb.report(p)
+      ))
+    }).await;
+    match result {
+      Ok(derived_key) => Ok(JsScryptResult { salt: salt.into(), derived_key: derived_key.into() }),
+      Err(err) => Err(Error::from_reason(err.message))
+    }
   }
 
+  /// Derives key like `scrypt`, without progress reports, giving time that
+  /// work waited for a worker, and time of work itself.
   #[napi]
-  pub async fn open_format_w_n(&self, c: Buffer, k: Buffer, work_label: u32) -> Result<EncrResult> {
-    compute_under_label_in!(self, work_label, nacl::secret_box::format_wn::open(&c, &k))
+  pub async fn scrypt_timed(
+    &self, passwd: Buffer, salt: Buffer, log_n: u8, r: u32, p: u32, dk_len: u32
+  ) -> Result<JsTimedBuffer> {
+    self.check_dk_len(dk_len)?;
+    let (result, timing) = self.rt.spawn_timed(move || {
+      scrypt::scrypt(&passwd, &salt, log_n, r as usize, p as usize, dk_len as usize, &|_| {})
+    }).await;
+    match result {
+      Ok(key) => Ok(JsTimedBuffer {
+        result: key.into(), queued_ms: as_ms(timing.queued), compute_ms: as_ms(timing.computed),
+        immediate: timing.immediate
+      }),
+      Err(err) => Err(Error::from_reason(err.message))
+    }
   }
 
+  /// Picks scrypt parameters, that take roughly given time per hash on this
+  /// machine, using at most given memory, i.e. 128 * r * 2^log_n bytes. This
+  /// runs a probe hash on a worker, and with r = 8 picks the largest log_n
+  /// within both time and memory, raising p, when memory caps log_n first.
+  /// Both target time and memory cap should be finite positive numbers.
   #[napi]
-  pub async fn pack_format_w_n(&self, m: Buffer, n: Buffer, k: Buffer, work_label: u32) -> Result<EncrResult> {
-    compute_under_label_in!(self, work_label, nacl::secret_box::format_wn::pack(&m, &n, &k))
+  pub async fn calibrate_scrypt(&self, target_ms: f64, max_memory_bytes: f64) -> Result<JsScryptParams> {
+    if !target_ms.is_finite() || (target_ms <= 0.0) {
+      return Err(Error::from_reason(format!("Target time should be a finite positive number, but it is {}", target_ms)));
+    }
+    if !max_memory_bytes.is_finite() || (max_memory_bytes <= 0.0) {
+      return Err(Error::from_reason(format!(
+        "Memory cap should be a finite positive number, but it is {}", max_memory_bytes
+      )));
+    }
+    let r = CALIBRATION_SCRYPT_R;
+    let bytes_per_n = 128.0 * (r as f64);
+    let max_n_by_memory = max_memory_bytes / bytes_per_n;
+    if max_n_by_memory < (1u64 << CALIBRATION_MIN_LOG_N) as f64 {
+      return Err(Error::from_reason(format!(
+        "Memory cap should be at least {} bytes, but it is {}",
+        bytes_per_n * (1u64 << CALIBRATION_MIN_LOG_N) as f64, max_memory_bytes
+      )));
+    }
+    let max_log_n_by_memory = max_n_by_memory.log2().floor().min(63.0) as u8;
+    let probe_log_n = CALIBRATION_PROBE_LOG_N.min(max_log_n_by_memory);
+    let (result, timing) = self.rt.spawn_timed(move || {
+      scrypt::scrypt(b"calibration", b"calibration salt", probe_log_n, r as usize, 1, 32, &|_| {})
+    }).await;
+    if let Err(err) = result {
+      return Err(Error::from_reason(err.message));
+    }
+    let ms_per_n = as_ms(timing.computed).max(f64::MIN_POSITIVE) / (1u64 << probe_log_n) as f64;
+    let n_by_time = target_ms / ms_per_n;
+    if n_by_time < max_n_by_memory {
+      let log_n = n_by_time.log2().floor().max(CALIBRATION_MIN_LOG_N as f64) as u8;
+      Ok(JsScryptParams { log_n, r, p: 1 })
+    } else {
+      let p = (n_by_time / (1u64 << max_log_n_by_memory) as f64).floor().clamp(1.0, u32::MAX as f64) as u32;
+      Ok(JsScryptParams { log_n: max_log_n_by_memory, r, p })
+    }
   }
 
-  fn clone(&self) -> Self {
-    JsAsyncSBoxCryptor {
-      rt: self.rt.clone(),
-      labels: self.labels.clone(),
-      max_num_of_threads: self.max_num_of_threads
+  fn check_dk_len(&self, dk_len: u32) -> Result<()> {
+    if dk_len == 0 {
+      return Err(Error::from_reason("Length of derived key should be greater than zero"));
+    }
+    if dk_len > self.max_scrypt_dk_len {
+      return Err(Error::from_reason(format!(
+        "Length of derived key is {} bytes, exceeding maximum of {} bytes", dk_len, self.max_scrypt_dk_len
+      )));
     }
+    Ok(())
   }
 
 }
 
-
-#[napi(js_name = "AsyncPBox")]
-pub struct JsAsyncPBox {
-  rt: Arc<Runtime>
-}
-
+#[cfg(feature = "scrypt-salt-reuse-check")]
 #[napi]
-impl JsAsyncPBox {
+impl JsCryptor {
 
+  /// Sets callback, that is called with a warning every time `scrypt` gets
+  /// salt and parameters, already used in this process. This is available
+  /// only in builds with `scrypt-salt-reuse-check` feature.
   #[napi]
-  pub async fn generate_pubkey(&self, sk: Buffer) -> Result<Buffer> {
-    compute_in!(self, nacl::public_box::generate_pubkey(&sk))
+  pub fn on_scrypt_salt_reuse(&mut self, cb: WeakThreadsafeFunction<String>) {
+    self.on_salt_reuse = Some(Arc::new(cb));
   }
 
-  #[napi]
-  pub async fn calc_dhshared_key(&self, pk: Buffer, sk: Buffer) -> Result<Buffer> {
-    compute_in!(self, nacl::public_box::calc_dhshared_key(&pk, &sk))
+}
+
+/// Extension point for native code, linked together with this module. It is
+/// absent in builds with `no-thread-pool` feature.
+#[cfg(not(feature = "no-thread-pool"))]
+impl JsCryptor {
+
+  /// Returns handle of runtime, on which all crypto work of this cryptor is
+  /// done, so that adjacent native work can share the same pool of threads.
+  ///
+  /// Tasks that are spawned on this handle compete with crypto operations
+  /// for worker threads, which are sized with `max_num_of_threads`. Such tasks
+  /// should not block workers with IO, locks or sleeps, as every blocked
+  /// worker stalls crypto calls and throws off admission by
+  /// `can_start_under_work_label`. Blocking work should go to
+  /// `Handle::spawn_blocking`, or to a separate runtime.
+  pub fn runtime_handle(&self) -> Handle {
+    self.rt.handle().clone()
   }
 
 }
 
+#[napi]
+pub fn copy_nonce_from_format_w_n(c: Buffer) -> Result<Buffer> {
+  match nacl::secret_box::format_wn::copy_nonce_from(&c) {
+    Ok(r) => Ok(r.into()),
+    Err(err) => Err(Error::from_reason(err.message))
+  }
+}
 
-#[napi(js_name = "Keypair")]
-pub struct JsKeypair {
-  #[napi]
-  pub skey: Vec<u8>,
-  #[napi]
-  pub pkey: Vec<u8>
+/// Splits with-nonce cipher into nonce and body, i.e. cipher without nonce,
+/// that can go into `open`. Cipher, too short to have nonce and non-empty
+/// body, gives configuration error.
+#[napi]
+pub fn split_format_w_n(c: Buffer) -> SplitFormatWNResult {
+  let min_len = (SBOX_NONCE_LENGTH + SBOX_POLY_LENGTH) as usize;
+  if c.len() <= min_len {
+    return SplitFormatWNResult::ConfigurationErr(format!(
+      "Array c should be longer than {} bytes, but it is only {} bytes long.", min_len, c.len()
+    ));
+  }
+  let (nonce, body) = c.split_at(SBOX_NONCE_LENGTH as usize);
+  SplitFormatWNResult::Ok { nonce: nonce.to_vec().into(), body: body.to_vec().into() }
 }
 
+/// Returns length of with-nonce cipher of message with given length. Layout of
+/// such cipher is nonce (`SBOX_NONCE_LENGTH` bytes), followed by poly MAC
+/// (`SBOX_POLY_LENGTH` bytes), followed by encrypted message, that has the
+/// same length as message. Empty messages aren't packed, giving error here.
+#[napi]
+pub fn format_w_n_len_for(plaintext_len: u32) -> Result<u32> {
+  if plaintext_len == 0 {
+    return Err(Error::from_reason("Message should not be empty"));
+  }
+  plaintext_len.checked_add(SBOX_NONCE_LENGTH + SBOX_POLY_LENGTH).ok_or_else(|| Error::from_reason(format!(
+    "With-nonce cipher of {} bytes long message doesn't fit into u32", plaintext_len
+  )))
+}
 
-#[napi(js_name = "AsyncSigning")]
-pub struct JsAsyncSigning {
-  rt: Arc<Runtime>
+/// Returns length of message in with-nonce cipher of given length, inverting
+/// `format_w_n_len_for`. Length, too short for nonce, poly MAC and non-empty
+/// message, gives error.
+#[napi]
+pub fn format_w_n_plaintext_len_for(cipher_len: u32) -> Result<u32> {
+  let overhead = SBOX_NONCE_LENGTH + SBOX_POLY_LENGTH;
+  if cipher_len <= overhead {
+    return Err(Error::from_reason(format!(
+      "With-nonce cipher should be longer than {} bytes, but it is only {} bytes long.", overhead, cipher_len
+    )));
+  }
+  Ok(cipher_len - overhead)
 }
 
+/// Tells, with constant-time comparison, whether two nonces, kept apart from
+/// their ciphers, are equal, for audits of nonce reuse under one key.
 #[napi]
-impl JsAsyncSigning {
+pub fn same_nonce(n1: Buffer, n2: Buffer) -> Result<bool> {
+  for n in [&n1, &n2] {
+    if n.len() != SBOX_NONCE_LENGTH as usize {
+      return Err(Error::from_reason(format!(
+        "Nonce should have {} bytes, but it is {} bytes long.", SBOX_NONCE_LENGTH, n.len()
+      )));
+    }
+  }
+  Ok(nacl::compare(&n1, &n2))
+}
 
-  #[napi]
-  pub async fn signature(&self, m: Buffer, sk: Buffer) -> Result<Buffer> {
-    compute_in!(self, nacl::sign::signature(&m, &sk))
+/// Tells, like `same_nonce`, whether two with-nonce ciphers have the same
+/// nonce in them.
+#[napi]
+pub fn same_nonce_w_n(c1: Buffer, c2: Buffer) -> Result<bool> {
+  let (n1, n2) = match (
+    nacl::secret_box::format_wn::copy_nonce_from(&c1), nacl::secret_box::format_wn::copy_nonce_from(&c2)
+  ) {
+    (Ok(n1), Ok(n2)) => (n1, n2),
+    (Err(err), _) | (_, Err(err)) => return Err(Error::from_reason(err.message))
+  };
+  Ok(nacl::compare(n1, n2))
+}
+
+const NONCE_COUNTER_LENGTH: usize = 8;
+
+fn nonce_counter_range(n: &[u8], offset: u32) -> Result<std::ops::Range<usize>> {
+  if n.len() != SBOX_NONCE_LENGTH as usize {
+    return Err(Error::from_reason(format!(
+      "Nonce should have {} bytes, but it is {} bytes long.", SBOX_NONCE_LENGTH, n.len()
+    )));
+  }
+  let offset = offset as usize;
+  if offset > n.len() - NONCE_COUNTER_LENGTH {
+    return Err(Error::from_reason(format!(
+      "Counter offset should be at most {}, but it is {}", n.len() - NONCE_COUNTER_LENGTH, offset
+    )));
   }
+  Ok(offset..(offset + NONCE_COUNTER_LENGTH))
+}
 
-  #[napi]
-  pub async fn verify(&self, sig: Buffer, m: Buffer, pk: Buffer) -> Result<bool> {
-    compute_in!(self, nacl::sign::verify(&sig, &m, &pk))
+fn nonce_from_counter(counter: BigInt, offset: u32, base: Option<Buffer>, big_endian: bool) -> Result<Buffer> {
+  let (negative, value, lossless) = counter.get_u64();
+  if negative || !lossless {
+    return Err(Error::from_reason("Counter should be an unsigned 64-bit integer"));
   }
+  let mut n = match base {
+    Some(base) => base.to_vec(),
+    None => vec![0u8; SBOX_NONCE_LENGTH as usize]
+  };
+  let range = nonce_counter_range(&n, offset)?;
+  n[range].copy_from_slice(&(if big_endian { value.to_be_bytes() } else { value.to_le_bytes() }));
+  Ok(n.into())
+}
 
-  #[napi]
-  pub async fn generate_keypair(&self, seed: Buffer) -> Result<JsKeypair> {
-    let keypair = self.rt.spawn(async move {
-      nacl::sign::generate_keypair(&seed)
-    }).await.unwrap();
-    Ok(JsKeypair {
-      skey: Vec::from(keypair.skey),
-      pkey: Vec::from(keypair.pkey)
-    })
+fn counter_from_nonce(n: Buffer, offset: u32, big_endian: bool) -> Result<BigInt> {
+  let range = nonce_counter_range(&n, offset)?;
+  let mut bytes = [0u8; NONCE_COUNTER_LENGTH];
+  bytes.copy_from_slice(&n[range]);
+  Ok(BigInt::from(if big_endian { u64::from_be_bytes(bytes) } else { u64::from_le_bytes(bytes) }))
+}
+
+/// Makes 24-byte nonce with counter written as 8-byte big-endian unsigned
+/// integer at bytes `offset..offset+8`. Other bytes are taken from `base`
+/// nonce, when it is given, or are zeros.
+#[napi]
+pub fn nonce_from_counter_be(counter: BigInt, offset: u32, base: Option<Buffer>) -> Result<Buffer> {
+  nonce_from_counter(counter, offset, base, true)
+}
+
+/// Makes 24-byte nonce with counter written as 8-byte little-endian unsigned
+/// integer at bytes `offset..offset+8`, like libsodium's `sodium_increment`
+/// counts, when offset is 0. Other bytes are taken from `base` nonce, when it
+/// is given, or are zeros. Nonces of `Sealer` and of file encryption have
+/// this layout with zero offset.
+#[napi]
+pub fn nonce_from_counter_le(counter: BigInt, offset: u32, base: Option<Buffer>) -> Result<Buffer> {
+  nonce_from_counter(counter, offset, base, false)
+}
+
+/// Reads counter from nonce, made by `nonce_from_counter_be`.
+#[napi]
+pub fn counter_from_nonce_be(n: Buffer, offset: u32) -> Result<BigInt> {
+  counter_from_nonce(n, offset, true)
+}
+
+/// Reads counter from nonce, made by `nonce_from_counter_le`.
+#[napi]
+pub fn counter_from_nonce_le(n: Buffer, offset: u32) -> Result<BigInt> {
+  counter_from_nonce(n, offset, false)
+}
+
+/// Serializes JSON value in canonical form of RFC 8785 (JCS), which is what
+/// `sign_json` signs.
+#[napi]
+pub fn canonical_json(value: serde_json::Value) -> Result<String> {
+  jcs::canonicalize(&value).map_err(Error::from_reason)
+}
+
+/// Signs message on calling thread, without cryptor, for occasional small
+/// signing work.
+#[napi]
+pub fn sign_sync(m: Buffer, sk: Buffer) -> Result<Buffer> {
+  match nacl::sign::signature(&m, &sk) {
+    Ok(sig) => Ok(sig.into()),
+    Err(err) => Err(Error::from_reason(err.message))
   }
+}
 
+/// Verifies signature on calling thread, without cryptor, for occasional
+/// verification of small messages.
+#[napi]
+pub fn verify_sync(sig: Buffer, m: Buffer, pk: Buffer) -> Result<bool> {
+  nacl::sign::verify(&sig, &m, &pk).map_err(|err| Error::from_reason(err.message))
 }
 
+/// Zeroes bytes of given buffer in place, for clearing sensitive data, like
+/// decrypted secrets, without waiting for GC. Only this buffer's memory is
+/// wiped: copies, made in JS, e.g. strings decoded from it, or other buffers,
+/// sliced with copy, stay intact. Views into the same memory get zeroes.
+#[napi]
+pub fn wipe_buffer(mut buf: Buffer) {
+  wipe(&mut buf);
+}
 
-#[napi(js_name = "Cryptor")]
-pub struct JsCryptor {
-  rt: Arc<Runtime>,
-  sbox: JsAsyncSBoxCryptor
+/// Compares keys in constant time, and is meant for secret key material.
+/// Both arrays should have one of key lengths, used in this module, i.e. 32
+/// or 64 bytes. Keys of different lengths are not equal.
+#[napi]
+pub fn keys_equal(a: Buffer, b: Buffer) -> Result<bool> {
+  for key in [&a, &b] {
+    if (key.len() != 32) && (key.len() != 64) {
+      return Err(Error::from_reason(format!(
+        "Key array should have either 32 or 64 bytes in it, but it is {} bytes long.", key.len()
+      )));
+    }
+  }
+  Ok(nacl::compare(&a, &b))
 }
 
+/// Makes public identity bundle from Ed25519 signing public key, deriving
+/// X25519 box key from it, like libsodium's
+/// crypto_sign_ed25519_pk_to_curve25519.
 #[napi]
-impl JsCryptor {
+pub fn export_identity_bundle(signing_pk: Buffer) -> Result<JsIdentityBundle> {
+  let box_pk = x25519::ed25519_pk_to_x25519(&signing_pk).map_err(|err| Error::from_reason(err.message))?;
+  let mut pkey_hash = [0u8; 64];
+  nacl::sha512::hash_sha512(&mut pkey_hash, &signing_pk);
+  Ok(JsIdentityBundle {
+    signing_pk: signing_pk.to_vec().into(),
+    box_pk: box_pk.into(),
+    fingerprint: to_hex(&pkey_hash[0..FINGERPRINT_LENGTH])
+  })
+}
 
-  #[napi(factory)]
-  pub fn make_treaded(max_num_of_threads: u32, thread_name: String) -> Self {
-    let threaded_rt = Builder::new_multi_thread()
-      .worker_threads(max_num_of_threads.try_into().unwrap())
-      .thread_name(thread_name)
-      .build()
-      .unwrap();
-    let rt = Arc::new(threaded_rt);
-    let sbox = JsAsyncSBoxCryptor {
-      rt: rt.clone(),
-      labels: DashMap::new(),
-      max_num_of_threads
-    };
-    JsCryptor { rt, sbox }
+const SAS_PREFIX: &[u8] = b"napi-nacl short auth string";
+const MAX_SAS_DIGITS: u32 = 18;
+
+/// Makes short authentication string of given number of decimal digits, up
+/// to 18, from two public keys, for comparison by people over another
+/// channel. Keys are put in lexicographic order, so that both parties get the
+/// same string, whichever key is theirs. Digits are the first 16 bytes of
+/// SHA-512("napi-nacl short auth string" || smaller pk || larger pk), as a
+/// big-endian number, modulo 10^digits, with leading zeros.
+#[napi]
+pub fn short_auth_string(pk_a: Buffer, pk_b: Buffer, digits: u32) -> Result<String> {
+  check_pbox_key_len("pk_a", &pk_a)?;
+  check_pbox_key_len("pk_b", &pk_b)?;
+  if (digits == 0) || (digits > MAX_SAS_DIGITS) {
+    return Err(Error::from_reason(format!(
+      "Number of digits should be from 1 to {}, but it is {}", MAX_SAS_DIGITS, digits
+    )));
   }
+  let (first, second) = if pk_a[..] <= pk_b[..] { (&pk_a, &pk_b) } else { (&pk_b, &pk_a) };
+  let mut hasher = nacl::sha512::Sha512::new();
+  hasher.update(SAS_PREFIX);
+  hasher.update(first);
+  hasher.update(second);
+  let hash = hasher.digest();
+  let mut head = [0u8; 16];
+  head.copy_from_slice(&hash[0..16]);
+  let code = u128::from_be_bytes(head) % 10u128.pow(digits);
+  Ok(format!("{:0width$}", code, width = digits as usize))
+}
 
-  #[napi(getter)]
-  pub fn sbox(&self) -> JsAsyncSBoxCryptor {
-    self.sbox.clone()
+/// Key in JSON form, with `use` being either "secret", or "public", `alg`
+/// being JWK name of algorithm, and key bytes `k` in base64url without
+/// padding. Key id `kid` is optional.
+#[napi(object, js_name = "JsonKey")]
+pub struct JsJsonKey {
+  pub r#use: String,
+  pub alg: String,
+  pub kid: Option<String>,
+  pub k: String
+}
+
+#[napi(object, js_name = "SigningJsonKeys")]
+pub struct JsSigningJsonKeys {
+  pub skey: JsJsonKey,
+  pub pkey: JsJsonKey
+}
+
+/// Key, imported from JSON form, with its key id, if there is one.
+#[napi(object, js_name = "ImportedKey")]
+pub struct JsImportedKey {
+  pub k: Buffer,
+  pub kid: Option<String>,
+  pub is_secret: bool
+}
+
+fn json_key(secret: bool, alg: &str, kid: Option<String>, k: &[u8]) -> JsJsonKey {
+  JsJsonKey {
+    r#use: (if secret { "secret" } else { "public" }).to_string(),
+    alg: alg.to_string(),
+    kid,
+    k: URL_SAFE_NO_PAD.encode(k)
   }
+}
 
-  #[napi(getter)]
-  pub fn pbox(&self) -> JsAsyncPBox {
-    JsAsyncPBox { rt: self.rt.clone() }
+/// Turns secret box key into JSON form with optional key id.
+#[napi]
+pub fn sbox_key_to_jwk(k: Buffer, kid: Option<String>) -> Result<JsJsonKey> {
+  if k.len() != SBOX_KEY_LENGTH as usize {
+    return Err(Error::from_reason(format!(
+      "Key k should have {} bytes, but it is {} bytes long.", SBOX_KEY_LENGTH, k.len()
+    )));
   }
+  Ok(json_key(true, SBOX_JWK_ALG_NAME, kid, &k))
+}
 
-  #[napi(getter)]
-  pub fn signing(&self) -> JsAsyncSigning {
-    JsAsyncSigning { rt: self.rt.clone() }
+/// Turns signing secret key into JSON forms of secret and public keys, both
+/// with the same optional key id. Public key is taken from the last 32 bytes
+/// of secret key.
+#[napi]
+pub fn signing_keypair_to_jwk(sk: Buffer, kid: Option<String>) -> Result<JsSigningJsonKeys> {
+  if sk.len() != SIGNING_SECRET_KEY_LENGTH as usize {
+    return Err(Error::from_reason(format!(
+      "Secret key should have {} bytes, but it is {} bytes long.", SIGNING_SECRET_KEY_LENGTH, sk.len()
+    )));
   }
+  let pk = &sk[(SIGNING_SECRET_KEY_LENGTH - SIGNING_PUBLIC_KEY_LENGTH) as usize..];
+  Ok(JsSigningJsonKeys {
+    skey: json_key(true, SIGNING_JWK_ALG_NAME, kid.clone(), &sk),
+    pkey: json_key(false, SIGNING_JWK_ALG_NAME, kid, pk)
+  })
+}
 
-  #[napi]
-  pub async fn scrypt(
-    &self, passwd: Buffer, salt: Buffer, log_n: u8, r: u32, p: u32, dk_len: u32,
-    report_progress: ThreadsafeFunction<u32>
-  ) -> Result<Buffer> {
-    compute_in!(self, {
-      let cb = |p: u32| {
-        report_progress.call(Ok(p), ThreadsafeFunctionCallMode::Blocking);
-      };
-      nacl::scrypt(&passwd, &salt, log_n, r as usize, p as usize, dk_len as usize, &cb)
-    })
+/// Takes key from JSON form, checking that it is for expected algorithm, and
+/// that it has proper length, keeping key id.
+#[napi]
+pub fn key_from_jwk(jwk: JsJsonKey, expected_alg: String) -> Result<JsImportedKey> {
+  if jwk.alg != expected_alg {
+    return Err(Error::from_reason(format!(
+      "Key is for algorithm {}, while {} is expected", jwk.alg, expected_alg
+    )));
+  }
+  let is_secret = match jwk.r#use.as_str() {
+    "secret" => true,
+    "public" => false,
+    other => return Err(Error::from_reason(format!("Unknown key use '{}'", other)))
+  };
+  let expected_len = match (jwk.alg.as_str(), is_secret) {
+    (SBOX_JWK_ALG_NAME, true) => SBOX_KEY_LENGTH,
+    (PBOX_JWK_ALG_NAME, _) => PBOX_KEY_LENGTH,
+    (SIGNING_JWK_ALG_NAME, true) => SIGNING_SECRET_KEY_LENGTH,
+    (SIGNING_JWK_ALG_NAME, false) => SIGNING_PUBLIC_KEY_LENGTH,
+    _ => return Err(Error::from_reason(format!(
+      "Algorithm {} has no {} keys", jwk.alg, jwk.r#use
+    )))
+  };
+  let k = URL_SAFE_NO_PAD.decode(&jwk.k).map_err(|err| Error::from_reason(format!(
+    "Key bytes are not valid base64url: {}", err
+  )))?;
+  if k.len() != expected_len as usize {
+    return Err(Error::from_reason(format!(
+      "Key should have {} bytes, but it is {} bytes long.", expected_len, k.len()
+    )));
+  }
+  Ok(JsImportedKey { k: k.into(), kid: jwk.kid, is_secret })
+}
+
+#[napi(object, js_name = "SBoxAlgorithm")]
+pub struct JsSBoxAlgorithm {
+  pub jwk_alg_name: String,
+  pub key_length: u32,
+  pub nonce_length: u32,
+  pub poly_length: u32
+}
+
+#[napi(object, js_name = "PBoxAlgorithm")]
+pub struct JsPBoxAlgorithm {
+  pub jwk_alg_name: String,
+  pub key_length: u32,
+  pub nonce_length: u32,
+  pub poly_length: u32
+}
+
+#[napi(object, js_name = "SigningAlgorithm")]
+pub struct JsSigningAlgorithm {
+  pub jwk_alg_name: String,
+  pub seed_length: u32,
+  pub secret_key_length: u32,
+  pub public_key_length: u32
+}
+
+#[napi(object, js_name = "Algorithms")]
+pub struct JsAlgorithms {
+  pub sbox: JsSBoxAlgorithm,
+  pub pbox: JsPBoxAlgorithm,
+  pub signing: JsSigningAlgorithm
+}
+
+/// Returns parameters of all algorithms, also given by individual constants.
+#[napi]
+pub fn algorithms() -> JsAlgorithms {
+  JsAlgorithms {
+    sbox: JsSBoxAlgorithm {
+      jwk_alg_name: SBOX_JWK_ALG_NAME.to_string(),
+      key_length: SBOX_KEY_LENGTH,
+      nonce_length: SBOX_NONCE_LENGTH,
+      poly_length: SBOX_POLY_LENGTH
+    },
+    pbox: JsPBoxAlgorithm {
+      jwk_alg_name: PBOX_JWK_ALG_NAME.to_string(),
+      key_length: PBOX_KEY_LENGTH,
+      nonce_length: nacl::public_box::NONCE_LENGTH as u32,
+      poly_length: nacl::public_box::POLY_LENGTH as u32
+    },
+    signing: JsSigningAlgorithm {
+      jwk_alg_name: SIGNING_JWK_ALG_NAME.to_string(),
+      seed_length: SIGNING_SEED_LENGTH,
+      secret_key_length: SIGNING_SECRET_KEY_LENGTH,
+      public_key_length: SIGNING_PUBLIC_KEY_LENGTH
+    }
   }
+}
 
+#[napi(object, js_name = "Capabilities")]
+pub struct JsCapabilities {
+  pub secretbox: bool,
+  pub r#box: bool,
+  pub sign: bool,
+  pub scrypt: bool,
+  pub x25519_scalarmult: bool,
+  pub secretstream: bool,
+  pub aead: bool,
+  /// Whether crypto work is done on cryptor's own worker threads. Builds
+  /// with `no-thread-pool` feature start no threads of their own, yet work
+  /// still runs off JS thread, on the single worker of napi's async runtime,
+  /// one call at a time.
+  pub thread_pool: bool,
+  pub scrypt_salt_reuse_check: bool,
+  pub debug_arg_checks: bool
 }
 
+/// Returns which primitives and build features are available in this build,
+/// for feature detection at runtime.
 #[napi]
-pub fn copy_nonce_from_format_w_n(c: Buffer) -> Result<Buffer> {
-  match nacl::secret_box::format_wn::copy_nonce_from(&c) {
-    Ok(r) => Ok(r.into()),
-    Err(err) => Err(Error::from_reason(err.message))
+pub fn capabilities() -> JsCapabilities {
+  JsCapabilities {
+    secretbox: true,
+    r#box: true,
+    sign: true,
+    scrypt: cfg!(feature = "scrypt"),
+    x25519_scalarmult: true,
+    secretstream: false,
+    aead: false,
+    thread_pool: cfg!(not(feature = "no-thread-pool")),
+    scrypt_salt_reuse_check: cfg!(feature = "scrypt-salt-reuse-check"),
+    debug_arg_checks: cfg!(feature = "debug-arg-checks")
   }
 }
 
@@ -310,3 +3986,262 @@ pub const SIGNING_PUBLIC_KEY_LENGTH: u32 = nacl::sign::PUBLIC_KEY_LENGTH as u32;
 pub fn plus_five(x: u32) -> u32 {
   x + 5
 }
+
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  fn test_labels(max_num_of_threads: u32) -> Arc<WorkLabels> {
+    Arc::new(WorkLabels::new(DashMap::new(), max_num_of_threads, false))
+  }
+
+  fn reserve(labels: &Arc<WorkLabels>, work_label: u32) -> JsWorkReservation {
+    increment_label_count(labels, work_label);
+    JsWorkReservation { labels: labels.clone(), work_label, key_id: None, released: false }
+  }
+
+  #[test]
+  fn quiescing_uncounts_new_tasks() {
+    let labels = test_labels(3);
+    let in_progress = reserve(&labels, 1);
+    let rejecting = RejectingNewWork::new(&labels);
+    let second = RejectingNewWork::new(&labels);
+    increment_label_count(&labels, 2);
+    assert!(uncount_if_quiescing(&labels, 2));
+    assert!(try_admit(&labels, 1));
+    assert!(uncount_if_quiescing(&labels, 1));
+    assert_eq!(labels.in_flight.load(Ordering::SeqCst), 1);
+    assert_eq!(labels.counts.get(&1).map(|count| *count), Some(1));
+    assert!(!labels.counts.contains_key(&2));
+    drop(rejecting);
+    increment_label_count(&labels, 2);
+    assert!(uncount_if_quiescing(&labels, 2), "other quiesce still rejects");
+    drop(second);
+    increment_label_count(&labels, 2);
+    assert!(!uncount_if_quiescing(&labels, 2));
+    assert_eq!(labels.in_flight.load(Ordering::SeqCst), 2);
+    drop(in_progress);
+    decrement_label_count(&labels, 2);
+    assert_eq!(labels.in_flight.load(Ordering::SeqCst), 0);
+  }
+
+  #[test]
+  fn import_keeps_counts_of_concurrent_tasks() {
+    let labels = test_labels(4);
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = {
+      let labels = labels.clone();
+      let stop = stop.clone();
+      std::thread::spawn(move || {
+        while !stop.load(Ordering::SeqCst) {
+          increment_label_count(&labels, 5);
+          decrement_label_count(&labels, 5);
+        }
+      })
+    };
+    for _ in 0..200000 {
+      import_label_counts(&labels, vec![(1, 2)], 2);
+      import_label_counts(&labels, Vec::new(), 0);
+    }
+    stop.store(true, Ordering::SeqCst);
+    worker.join().unwrap();
+    assert!(import_label_counts(&labels, Vec::new(), 0));
+    assert_eq!(labels.in_flight.load(Ordering::SeqCst), 0);
+    assert!(labels.counts.is_empty());
+  }
+
+  fn test_ratchet(labels: &Arc<WorkLabels>, chain_key: &[u8]) -> JsRatchet {
+    JsRatchet {
+      rt: Arc::new(CryptoPool::new(1, "ratchet-test".to_string())),
+      labels: labels.clone(),
+      events: Arc::new(OpEvents::default()),
+      throw_on_verification_failure: false,
+      chain: Arc::new(Mutex::new(ratchet::Chain::new(chain_key).unwrap()))
+    }
+  }
+
+  #[test]
+  fn ratchet_keys_survive_saturated_rejection() {
+    let chain_key = [9u8; 32];
+    let labels = Arc::new(WorkLabels::new(DashMap::new(), 1, true));
+    let sender = test_ratchet(&labels, &chain_key);
+    let receiver = test_ratchet(&labels, &chain_key);
+    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    rt.block_on(async {
+      let sent = sender.pack_next(b"first".to_vec().into(), 1).await.unwrap();
+      assert_eq!(sent.index, 0);
+
+      // label 1 is saturated by a task in progress, with one thread
+      let in_progress = reserve(&labels, 1);
+      assert!(sender.pack_next(b"second".to_vec().into(), 1).await.is_err());
+      assert!(receiver.open_at(sent.cipher.to_vec().into(), sent.index, 1).await.is_err());
+      drop(in_progress);
+
+      match receiver.open_at(sent.cipher.to_vec().into(), sent.index, 1).await.unwrap() {
+        EncrResult::Ok(m) => assert_eq!(&m[..], b"first"),
+        _ => panic!("message should open after rejection")
+      }
+      let next = sender.pack_next(b"second".to_vec().into(), 1).await.unwrap();
+      assert_eq!(next.index, 1, "rejected pack uses up no index");
+      assert!(matches!(
+        receiver.open_at(next.cipher.to_vec().into(), next.index, 1).await.unwrap(), EncrResult::Ok(_)
+      ));
+    });
+    assert_eq!(labels.in_flight.load(Ordering::SeqCst), 0);
+  }
+
+  /// Micro-benchmark of label map under contention, that guides choice of
+  /// its shard count. Run it with
+  /// `cargo test --release label_map_shards -- --ignored --nocapture`.
+  #[test]
+  #[ignore]
+  fn label_map_shards_benchmark() {
+    const THREADS: u32 = 16;
+    const ROUNDS: u32 = 100_000;
+    for shards in [2, 0, 8, 64, 256] {
+      let counts = if shards == 0 {
+        DashMap::new()
+      } else {
+        DashMap::with_shard_amount(shards)
+      };
+      let labels = Arc::new(WorkLabels::new(counts, THREADS, false));
+      let start = Instant::now();
+      let workers: Vec<_> = (0..THREADS).map(|work_label| {
+        let labels = labels.clone();
+        std::thread::spawn(move || {
+          for _ in 0..ROUNDS {
+            increment_label_count(&labels, work_label);
+            std::hint::black_box(slots_under_label(&labels, work_label));
+            decrement_label_count(&labels, work_label);
+          }
+        })
+      }).collect();
+      for worker in workers {
+        worker.join().unwrap();
+      }
+      let shards = if shards == 0 { "default".to_string() } else { shards.to_string() };
+      println!("{} shards: {} ms", shards, start.elapsed().as_millis());
+    }
+  }
+
+  #[test]
+  fn dropped_reservation_releases_slot() {
+    let labels = test_labels(3);
+    let first = reserve(&labels, 1);
+    let second = reserve(&labels, 1);
+    assert_eq!(slots_under_label(&labels, 1), 0);
+    drop(first);
+    assert_eq!(slots_under_label(&labels, 1), 1);
+    assert_eq!(labels.in_flight.load(Ordering::SeqCst), 1);
+    drop(second);
+    assert_eq!(labels.in_flight.load(Ordering::SeqCst), 0);
+    assert!(labels.counts.get(&1).is_none());
+  }
+
+  #[test]
+  fn released_reservation_isnt_released_again_on_drop() {
+    let labels = test_labels(2);
+    let mut reservation = reserve(&labels, 1);
+    let _other = reserve(&labels, 2);
+    reservation.release();
+    drop(reservation);
+    assert_eq!(labels.in_flight.load(Ordering::SeqCst), 1);
+    assert_eq!(labels.counts.get(&2).map(|count| *count), Some(1));
+  }
+
+  #[test]
+  fn admission_ends_exactly_at_capacity() {
+    let labels = test_labels(4);
+    let mut reservations = Vec::new();
+    while try_admit(&labels, 1) {
+      reservations.push(JsWorkReservation { labels: labels.clone(), work_label: 1, key_id: None, released: false });
+      assert!(reservations.len() <= 4);
+    }
+    // one queue on 4 threads leaves 3 idle threads for it
+    assert_eq!(reservations.len(), 3);
+    assert_eq!(slots_under_label(&labels, 1), 0);
+    // new label gets one slot on a saturated pool
+    assert_eq!(slots_under_label(&labels, 2), 1);
+    reservations.pop();
+    assert_eq!(slots_under_label(&labels, 1), 1);
+  }
+
+  #[test]
+  fn concurrent_admission_does_not_overshoot() {
+    const THREADS: u32 = 8;
+    let labels = test_labels(THREADS);
+    for _ in 0..200 {
+      let barrier = Arc::new(std::sync::Barrier::new(THREADS as usize));
+      let admitters: Vec<_> = (0..THREADS).map(|_| {
+        let labels = labels.clone();
+        let barrier = barrier.clone();
+        std::thread::spawn(move || {
+          barrier.wait();
+          try_admit(&labels, 7)
+        })
+      }).collect();
+      let admitted = admitters.into_iter().map(|admitter| admitter.join().unwrap())
+      .filter(|&admitted| admitted).count() as u32;
+      assert_eq!(admitted, THREADS - 1);
+      assert_eq!(labels.in_flight.load(Ordering::SeqCst), admitted);
+      assert_eq!(slots_under_label(&labels, 7), 0);
+      for _ in 0..admitted {
+        decrement_label_count(&labels, 7);
+      }
+      assert_eq!(labels.in_flight.load(Ordering::SeqCst), 0);
+      assert!(labels.counts.get(&7).is_none());
+    }
+  }
+
+  #[test]
+  fn seed_length_is_checked_before_keypair_generation() {
+    for len in [0, 1, nacl::sign::SEED_LENGTH - 1, nacl::sign::SEED_LENGTH + 1, 64] {
+      let seed = vec![7u8; len];
+      let err = check_seed_len(&seed).unwrap_err();
+      assert!(err.reason.contains(&format!("but it is {} bytes long", len)), "{}", err.reason);
+    }
+    let seed = [7u8; nacl::sign::SEED_LENGTH];
+    assert!(check_seed_len(&seed).is_ok());
+    let keypair = nacl::sign::generate_keypair(&seed);
+    assert_eq!(keypair.pkey.len(), nacl::sign::PUBLIC_KEY_LENGTH);
+  }
+
+  #[test]
+  fn pbox_key_length_errors_name_argument() {
+    let key = [7u8; PBOX_KEY_LENGTH as usize];
+    let short = [7u8; PBOX_KEY_LENGTH as usize - 1];
+    assert!(check_pbox_key_len("pk_b", &key).is_ok());
+    let err = check_pbox_key_len("pk_b", &short).unwrap_err();
+    assert!(err.reason.contains("Key array pk_b should have"), "{}", err.reason);
+
+    let labels = test_labels(1);
+    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    for cache_entries in [None, Some(4)] {
+      let pbox = JsAsyncPBox {
+        labels: labels.clone(),
+        events: Arc::new(OpEvents::default()),
+        throw_on_verification_failure: false,
+        derivation_cache: cache_entries.map(|entries| Arc::new(Mutex::new(DerivationCache::new(entries)))),
+        rt: Arc::new(CryptoPool::new(1, "pbox-test".to_string()))
+      };
+      rt.block_on(async {
+        let err = pbox.calc_dhshared_key(short.to_vec().into(), key.to_vec().into()).await.err().unwrap();
+        assert!(err.reason.contains("Key array pk should have"), "{}", err.reason);
+        let err = pbox.calc_dhshared_key(key.to_vec().into(), short.to_vec().into()).await.err().unwrap();
+        assert!(err.reason.contains("Key array sk should have"), "{}", err.reason);
+        let dhshared_key = pbox.calc_dhshared_key(key.to_vec().into(), key.to_vec().into()).await.unwrap();
+        assert_eq!(&dhshared_key[..], &nacl::public_box::calc_dhshared_key(&key, &key).unwrap()[..]);
+      });
+    }
+  }
+
+  #[test]
+  fn nacl_keypair_generation_panics_on_short_seed() {
+    // this is why seeds are checked by check_seed_len
+    let short_seed = [7u8; nacl::sign::SEED_LENGTH - 1];
+    assert!(std::panic::catch_unwind(|| nacl::sign::generate_keypair(&short_seed)).is_err());
+  }
+
+}
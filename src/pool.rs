@@ -0,0 +1,301 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pool, on which crypto work is done. By default it is a tokio multi-thread
+//! runtime, owned by cryptor. With `no-thread-pool` feature cryptor starts no
+//! threads, and work is done right inside of async call, on a thread that
+//! polls it, while public interface stays the same. Async calls are polled
+//! by napi's own tokio runtime, which isn't optional, and which is hence
+//! limited to a single worker thread in such builds.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::time::{ Duration, Instant };
+#[cfg(not(feature = "no-thread-pool"))]
+use std::sync::{ Arc, Barrier };
+#[cfg(not(feature = "no-thread-pool"))]
+use std::sync::atomic::{ AtomicU32, Ordering };
+#[cfg(not(feature = "no-thread-pool"))]
+use tokio::runtime::{ Builder, Handle, Runtime };
+#[cfg(not(feature = "no-thread-pool"))]
+use tokio::sync::Mutex;
+
+/// Replaces napi's default async runtime, that has a worker per core, with
+/// one, that has a single worker, as it does all crypto work in builds with
+/// `no-thread-pool` feature.
+#[cfg(feature = "no-thread-pool")]
+#[napi_derive::module_init]
+fn init_napi_runtime() {
+  let rt = tokio::runtime::Builder::new_multi_thread()
+    .worker_threads(1)
+    .enable_all()
+    .build()
+    .unwrap();
+  napi::bindgen_prelude::create_custom_tokio_runtime(rt);
+}
+
+thread_local! {
+  /// Time that the last work, awaited on this thread, waited for a worker.
+  static LAST_QUEUE_WAIT: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Takes time that the last work, completed in a future, polled on calling
+/// thread, had waited for a worker. Since it is set when pool's future
+/// completes, it should be taken right after `.await`, before any other
+/// await point.
+pub(crate) fn take_queue_wait() -> Option<Duration> {
+  LAST_QUEUE_WAIT.take()
+}
+
+/// Records that the last work on calling thread didn't wait, as it was done
+/// right there, without pool.
+pub(crate) fn set_no_queue_wait() {
+  LAST_QUEUE_WAIT.set(Some(Duration::ZERO));
+}
+
+/// Time that work waited for a worker, and time of work itself, with flag,
+/// telling whether there was an idle worker, when work was submitted.
+pub(crate) struct Timing {
+  pub queued: Duration,
+  pub computed: Duration,
+  pub immediate: bool
+}
+
+pub(crate) struct CryptoPool {
+  #[cfg(not(feature = "no-thread-pool"))]
+  rt: Runtime,
+  #[cfg(not(feature = "no-thread-pool"))]
+  num_of_workers: u32,
+  /// Number of tasks, submitted to workers, and not yet done.
+  #[cfg(not(feature = "no-thread-pool"))]
+  pending: Arc<AtomicU32>,
+  /// Held by `on_every_worker`, as its tasks block workers, till all of them
+  /// meet, and tasks of two overlapping calls would take workers from each
+  /// other, never meeting.
+  #[cfg(not(feature = "no-thread-pool"))]
+  every_worker_lock: Mutex<()>
+}
+
+/// Counts task as pending, until it is dropped.
+#[cfg(not(feature = "no-thread-pool"))]
+struct PendingTask(Arc<AtomicU32>);
+
+#[cfg(not(feature = "no-thread-pool"))]
+impl PendingTask {
+  fn new(pending: &Arc<AtomicU32>) -> Self {
+    pending.fetch_add(1, Ordering::SeqCst);
+    PendingTask(pending.clone())
+  }
+}
+
+#[cfg(not(feature = "no-thread-pool"))]
+impl Drop for PendingTask {
+  fn drop(&mut self) {
+    self.0.fetch_sub(1, Ordering::SeqCst);
+  }
+}
+
+impl CryptoPool {
+
+  #[cfg(not(feature = "no-thread-pool"))]
+  pub fn new(max_num_of_threads: u32, thread_name: String) -> Self {
+    let rt = Builder::new_multi_thread()
+      .worker_threads(max_num_of_threads.try_into().unwrap())
+      .thread_name(thread_name)
+      .build()
+      .unwrap();
+    CryptoPool {
+      rt,
+      num_of_workers: max_num_of_threads,
+      pending: Arc::new(AtomicU32::new(0)),
+      every_worker_lock: Mutex::new(())
+    }
+  }
+
+  #[cfg(feature = "no-thread-pool")]
+  pub fn new(_max_num_of_threads: u32, _thread_name: String) -> Self {
+    CryptoPool {}
+  }
+
+  /// Starts given work in the pool, returning future of its result.
+  #[cfg(not(feature = "no-thread-pool"))]
+  pub fn spawn<T: Send + 'static>(
+    &self, work: impl Future<Output = T> + Send + 'static
+  ) -> impl Future<Output = T> + Send + 'static {
+    let submitted = Instant::now();
+    let pending = PendingTask::new(&self.pending);
+    let handle = self.rt.spawn(async move {
+      let queued = submitted.elapsed();
+      let result = work.await;
+      drop(pending);
+      (result, queued)
+    });
+    async move {
+      let (result, queued) = handle.await.unwrap();
+      LAST_QUEUE_WAIT.set(Some(queued));
+      result
+    }
+  }
+
+  /// Returns given work, which is done when returned future is awaited.
+  #[cfg(feature = "no-thread-pool")]
+  #[allow(clippy::manual_async_fn)] // keeps future 'static, like in pool builds
+  pub fn spawn<T: Send + 'static>(
+    &self, work: impl Future<Output = T> + Send + 'static
+  ) -> impl Future<Output = T> + Send + 'static {
+    async move {
+      let result = work.await;
+      LAST_QUEUE_WAIT.set(Some(Duration::ZERO));
+      result
+    }
+  }
+
+  /// Starts given work either on one of workers, or on tokio's blocking
+  /// pool, which is separate from workers, and grows on demand.
+  #[cfg(not(feature = "no-thread-pool"))]
+  pub fn spawn_work<T: Send + 'static>(
+    &self, on_blocking_pool: bool, work: impl FnOnce() -> T + Send + 'static
+  ) -> impl Future<Output = T> + Send + 'static {
+    let submitted = Instant::now();
+    let timed_work = move || {
+      let queued = submitted.elapsed();
+      (work(), queued)
+    };
+    let handle = if on_blocking_pool {
+      self.rt.spawn_blocking(timed_work)
+    } else {
+      let pending = PendingTask::new(&self.pending);
+      self.rt.spawn(async move {
+        let result = timed_work();
+        drop(pending);
+        result
+      })
+    };
+    async move {
+      let (result, queued) = handle.await.unwrap();
+      LAST_QUEUE_WAIT.set(Some(queued));
+      result
+    }
+  }
+
+  /// Does given work, when returned future is awaited, as there are no
+  /// pools in this build.
+  #[cfg(feature = "no-thread-pool")]
+  #[allow(clippy::manual_async_fn)] // keeps future 'static, like in pool builds
+  pub fn spawn_work<T: Send + 'static>(
+    &self, _on_blocking_pool: bool, work: impl FnOnce() -> T + Send + 'static
+  ) -> impl Future<Output = T> + Send + 'static {
+    async move {
+      let result = work();
+      LAST_QUEUE_WAIT.set(Some(Duration::ZERO));
+      result
+    }
+  }
+
+  /// Starts given work like `spawn`, also returning timing of it.
+  pub fn spawn_timed<T: Send + 'static>(
+    &self, work: impl FnOnce() -> T + Send + 'static
+  ) -> impl Future<Output = (T, Timing)> + Send + 'static {
+    let immediate = self.has_idle_worker();
+    let submitted = Instant::now();
+    self.spawn(async move {
+      let started = Instant::now();
+      let result = work();
+      (result, Timing { queued: started - submitted, computed: started.elapsed(), immediate })
+    })
+  }
+
+  /// Tells whether there are fewer tasks, submitted to workers, than there
+  /// are workers.
+  #[cfg(not(feature = "no-thread-pool"))]
+  fn has_idle_worker(&self) -> bool {
+    self.pending.load(Ordering::SeqCst) < self.num_of_workers
+  }
+
+  /// Work starts right away in this build without pool.
+  #[cfg(feature = "no-thread-pool")]
+  fn has_idle_worker(&self) -> bool {
+    true
+  }
+
+  /// Occupies all given number of workers at once with tasks, that wait for
+  /// each other, so that every worker is confirmed to be running.
+  pub async fn warmup(&self, num_of_workers: u32) {
+    self.on_every_worker(num_of_workers, || {}).await
+  }
+
+  /// Does given work once on each of given number of workers, occupying them
+  /// all at once, like `warmup`. Calls go one after another.
+  #[cfg(not(feature = "no-thread-pool"))]
+  pub async fn on_every_worker(&self, num_of_workers: u32, work: impl Fn() + Send + Sync + 'static) {
+    let _every_worker = self.every_worker_lock.lock().await;
+    let barrier = Arc::new(Barrier::new(num_of_workers as usize));
+    let work = Arc::new(work);
+    let tasks: Vec<_> = (0..num_of_workers).map(|_| {
+      let barrier = barrier.clone();
+      let work = work.clone();
+      self.spawn(async move {
+        work();
+        barrier.wait();
+      })
+    }).collect();
+    for task in tasks {
+      task.await;
+    }
+  }
+
+  /// Does given work once, as there are no workers in this build.
+  #[cfg(feature = "no-thread-pool")]
+  pub async fn on_every_worker(&self, _num_of_workers: u32, work: impl Fn() + Send + Sync + 'static) {
+    work();
+  }
+
+  #[cfg(not(feature = "no-thread-pool"))]
+  pub fn handle(&self) -> &Handle {
+    self.rt.handle()
+  }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+  use std::sync::{ Arc, mpsc };
+  use std::time::Duration;
+  use super::CryptoPool;
+
+  #[test]
+  fn overlapping_warmups_complete() {
+    let pool = Arc::new(CryptoPool::new(2, "warmup-test".to_string()));
+    let callers = tokio::runtime::Builder::new_multi_thread().worker_threads(2).build().unwrap();
+    let warmups: Vec<_> = (0..3).map(|_| {
+      let pool = pool.clone();
+      callers.spawn(async move { pool.warmup(2).await })
+    }).collect();
+    let (done_tx, done_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+      callers.block_on(async move {
+        for warmup in warmups {
+          warmup.await.unwrap();
+        }
+      });
+      let _ = done_tx.send(());
+      callers.shutdown_background();
+    });
+    assert!(done_rx.recv_timeout(Duration::from_secs(10)).is_ok(), "overlapping warmups deadlocked");
+  }
+
+}
@@ -0,0 +1,205 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Symmetric key ratchet. Every step of a chain turns chain key ck into
+//! - message key and nonce, which are the first 32 and following 24 bytes of
+//!   HMAC-SHA512(ck, 0x01),
+//! - next chain key, which is the first 32 bytes of HMAC-SHA512(ck, 0x02).
+//!
+//! Chain keys and message keys are wiped as soon as they are used, so that
+//! compromise of current state doesn't expose earlier messages.
+
+use std::collections::HashMap;
+use nacl::secret_box::{ KEY_LENGTH, NONCE_LENGTH };
+use crate::kdf::hmac_sha512;
+use crate::wipe;
+
+/// Maximum number of message keys, kept for messages, that are skipped by
+/// receiver, i.e. messages that arrive out of order.
+const MAX_SKIPPED_MESSAGES: usize = 1000;
+
+pub(crate) struct MessageKey {
+  pub k: [u8; KEY_LENGTH],
+  pub n: [u8; NONCE_LENGTH]
+}
+
+impl Drop for MessageKey {
+  fn drop(&mut self) {
+    wipe(&mut self.k);
+    wipe(&mut self.n);
+  }
+}
+
+pub(crate) struct Chain {
+  chain_key: [u8; KEY_LENGTH],
+  next_index: u32,
+  skipped: HashMap<u32, MessageKey>
+}
+
+impl Drop for Chain {
+  fn drop(&mut self) {
+    wipe(&mut self.chain_key);
+  }
+}
+
+impl Chain {
+
+  pub fn new(chain_key: &[u8]) -> Result<Self, String> {
+    if chain_key.len() != KEY_LENGTH {
+      return Err(format!(
+        "Chain key should have {} bytes, but it is {} bytes long.", KEY_LENGTH, chain_key.len()
+      ));
+    }
+    let mut ck = [0u8; KEY_LENGTH];
+    ck.copy_from_slice(chain_key);
+    Ok(Chain { chain_key: ck, next_index: 0, skipped: HashMap::new() })
+  }
+
+  fn step(&mut self) -> Result<(u32, MessageKey), String> {
+    let index = self.next_index;
+    self.next_index = index.checked_add(1).ok_or("Ratchet chain is exhausted")?;
+    let mut material = hmac_sha512(&self.chain_key, &[1]);
+    let mut msg_key = MessageKey { k: [0; KEY_LENGTH], n: [0; NONCE_LENGTH] };
+    msg_key.k.copy_from_slice(&material[0..KEY_LENGTH]);
+    msg_key.n.copy_from_slice(&material[KEY_LENGTH..(KEY_LENGTH + NONCE_LENGTH)]);
+    wipe(&mut material);
+    let mut next_chain_key = hmac_sha512(&self.chain_key, &[2]);
+    self.chain_key.copy_from_slice(&next_chain_key[0..KEY_LENGTH]);
+    wipe(&mut next_chain_key);
+    Ok((index, msg_key))
+  }
+
+  /// Advances chain, returning index and key of the next message.
+  pub fn next_key(&mut self) -> Result<(u32, MessageKey), String> {
+    self.step()
+  }
+
+  /// Returns key for message at given index, advancing chain, if needed, and
+  /// keeping keys of skipped messages.
+  pub fn key_at(&mut self, index: u32) -> Result<MessageKey, String> {
+    if index < self.next_index {
+      return self.skipped.remove(&index).ok_or(format!(
+        "Key for message {} has already been used, or skipped too long ago", index
+      ));
+    }
+    if (self.skipped.len() + (index - self.next_index) as usize) > MAX_SKIPPED_MESSAGES {
+      return Err(format!(
+        "Message {} is too far ahead of expected message {}", index, self.next_index
+      ));
+    }
+    loop {
+      let (i, msg_key) = self.step()?;
+      if i == index {
+        return Ok(msg_key);
+      }
+      self.skipped.insert(i, msg_key);
+    }
+  }
+
+  /// Puts back key of a message, that failed to open, so that it can be
+  /// tried again with a genuine message.
+  pub fn put_back(&mut self, index: u32, msg_key: MessageKey) {
+    self.skipped.insert(index, msg_key);
+  }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  const CHAIN_KEY: [u8; KEY_LENGTH] = [7; KEY_LENGTH];
+
+  fn same_key(a: &MessageKey, b: &MessageKey) -> bool {
+    a.k == b.k && a.n == b.n
+  }
+
+  #[test]
+  fn chain_key_length_is_checked() {
+    assert!(Chain::new(&[0; KEY_LENGTH - 1]).is_err());
+    assert!(Chain::new(&[0; KEY_LENGTH + 1]).is_err());
+  }
+
+  #[test]
+  fn chain_advances_by_hmac_steps() {
+    let mut chain = Chain::new(&CHAIN_KEY).unwrap();
+    let mut ck = CHAIN_KEY.to_vec();
+    for expected_index in 0..5 {
+      let (index, msg_key) = chain.next_key().unwrap();
+      assert_eq!(index, expected_index);
+      let material = hmac_sha512(&ck, &[1]);
+      assert_eq!(&msg_key.k[..], &material[0..KEY_LENGTH]);
+      assert_eq!(&msg_key.n[..], &material[KEY_LENGTH..(KEY_LENGTH + NONCE_LENGTH)]);
+      ck = hmac_sha512(&ck, &[2])[0..KEY_LENGTH].to_vec();
+    }
+  }
+
+  #[test]
+  fn receiver_gets_keys_out_of_order() {
+    let mut sender = Chain::new(&CHAIN_KEY).unwrap();
+    let sent: Vec<MessageKey> = (0..6).map(|_| sender.next_key().unwrap().1).collect();
+    let mut receiver = Chain::new(&CHAIN_KEY).unwrap();
+    for index in [3, 0, 5, 1, 4, 2] {
+      let msg_key = receiver.key_at(index).unwrap();
+      assert!(same_key(&msg_key, &sent[index as usize]), "key of message {}", index);
+    }
+    assert!(receiver.skipped.is_empty());
+    // every key is given only once
+    for index in 0..6 {
+      assert!(receiver.key_at(index).is_err());
+    }
+    assert!(same_key(&receiver.key_at(6).unwrap(), &sender.next_key().unwrap().1));
+  }
+
+  #[test]
+  fn skipping_is_limited() {
+    let mut chain = Chain::new(&CHAIN_KEY).unwrap();
+    assert!(chain.key_at(MAX_SKIPPED_MESSAGES as u32 + 1).is_err());
+    assert_eq!(chain.next_index, 0);
+    assert!(chain.key_at(MAX_SKIPPED_MESSAGES as u32).is_ok());
+    assert_eq!(chain.skipped.len(), MAX_SKIPPED_MESSAGES);
+    // kept skipped keys count towards the limit
+    let next = chain.next_index;
+    assert!(chain.key_at(next + 1).is_err());
+    assert!(chain.key_at(next).is_ok());
+    chain.key_at(0).unwrap();
+    assert!(chain.key_at(next + 2).is_ok());
+    assert_eq!(chain.skipped.len(), MAX_SKIPPED_MESSAGES);
+  }
+
+  #[test]
+  fn key_put_back_after_failed_open_opens_genuine_message() {
+    let mut sender = Chain::new(&CHAIN_KEY).unwrap();
+    let mut receiver = Chain::new(&CHAIN_KEY).unwrap();
+    sender.next_key().unwrap();
+    let (index, msg_key) = sender.next_key().unwrap();
+    let msg = b"genuine message";
+    let cipher = nacl::secret_box::pack(msg, &msg_key.n, &msg_key.k).unwrap();
+    let mut forged = cipher.clone();
+    forged[0] ^= 1;
+
+    let msg_key = receiver.key_at(index).unwrap();
+    assert!(nacl::secret_box::open(&forged, &msg_key.n, &msg_key.k).is_err());
+    receiver.put_back(index, msg_key);
+
+    let msg_key = receiver.key_at(index).unwrap();
+    assert_eq!(nacl::secret_box::open(&cipher, &msg_key.n, &msg_key.k).unwrap(), msg);
+    assert!(receiver.key_at(index).is_err());
+    // skipped key of message before is still there
+    assert!(receiver.key_at(index - 1).is_ok());
+  }
+
+}
@@ -0,0 +1,37 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Development aid, compiled only with `scrypt-salt-reuse-check` feature,
+//! that catches scrypt being called with the same salt and parameters more
+//! than once in a process lifetime, like it happens with hardcoded salts.
+
+use std::collections::HashSet;
+use std::sync::{ Mutex, OnceLock };
+
+static SEEN_SCRYPT_USES: OnceLock<Mutex<HashSet<Vec<u8>>>> = OnceLock::new();
+
+/// Records hash of given salt and parameters, returning true, if they have
+/// already been used in this process.
+pub(crate) fn record_scrypt_use(salt: &[u8], log_n: u8, r: u32, p: u32, dk_len: u32) -> bool {
+  let mut hasher = nacl::sha512::Sha512::new();
+  hasher.update(&[log_n]);
+  hasher.update(&r.to_be_bytes());
+  hasher.update(&p.to_be_bytes());
+  hasher.update(&dk_len.to_be_bytes());
+  hasher.update(salt);
+  let use_hash = hasher.digest();
+  let seen = SEEN_SCRYPT_USES.get_or_init(|| Mutex::new(HashSet::new()));
+  !seen.lock().unwrap().insert(use_hash)
+}
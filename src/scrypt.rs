@@ -0,0 +1,517 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Scrypt of RFC 7914, giving the same keys and progress reports as nacl's
+//! scrypt, but with working memory, that can be reserved on a thread and
+//! reused by following calls there. nacl's scrypt allocates its memory on
+//! every call, and takes no buffer.
+//!
+//! Reserved memory is per thread, in a thread local, hence nothing is shared
+//! between threads, and no locking is needed. Call with parameters, that
+//! need memory of reserved size, takes reserved memory for its duration,
+//! and wipes it before giving it back. Other calls allocate their own.
+
+use std::cell::RefCell;
+use crate::wipe;
+
+const SHA256_BLOCK_LENGTH: usize = 64;
+const SHA256_LENGTH: usize = 32;
+
+const SHA256_K: [u32; 64] = [
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2
+];
+
+const SHA256_IV: [u32; 8] = [
+  0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19
+];
+
+#[derive(Clone)]
+struct Sha256 {
+  h: [u32; 8],
+  buf: [u8; SHA256_BLOCK_LENGTH],
+  buf_len: usize,
+  total_len: u64
+}
+
+impl Sha256 {
+
+  fn new() -> Self {
+    Sha256 { h: SHA256_IV, buf: [0; SHA256_BLOCK_LENGTH], buf_len: 0, total_len: 0 }
+  }
+
+  fn compress(h: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for (i, word) in block.chunks_exact(4).enumerate() {
+      w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    for i in 16..64 {
+      let s0 = w[i-15].rotate_right(7) ^ w[i-15].rotate_right(18) ^ (w[i-15] >> 3);
+      let s1 = w[i-2].rotate_right(17) ^ w[i-2].rotate_right(19) ^ (w[i-2] >> 10);
+      w[i] = w[i-16].wrapping_add(s0).wrapping_add(w[i-7]).wrapping_add(s1);
+    }
+    let mut s = *h;
+    for i in 0..64 {
+      let ch = (s[4] & s[5]) ^ (!s[4] & s[6]);
+      let t1 = s[7]
+        .wrapping_add(s[4].rotate_right(6) ^ s[4].rotate_right(11) ^ s[4].rotate_right(25))
+        .wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+      let maj = (s[0] & s[1]) ^ (s[0] & s[2]) ^ (s[1] & s[2]);
+      let t2 = (s[0].rotate_right(2) ^ s[0].rotate_right(13) ^ s[0].rotate_right(22)).wrapping_add(maj);
+      s = [t1.wrapping_add(t2), s[0], s[1], s[2], s[3].wrapping_add(t1), s[4], s[5], s[6]];
+    }
+    for i in 0..8 {
+      h[i] = h[i].wrapping_add(s[i]);
+    }
+    wipe_words(&mut w);
+  }
+
+  fn update(&mut self, mut data: &[u8]) {
+    self.total_len += data.len() as u64;
+    if self.buf_len > 0 {
+      let take = (SHA256_BLOCK_LENGTH - self.buf_len).min(data.len());
+      self.buf[self.buf_len..(self.buf_len + take)].copy_from_slice(&data[0..take]);
+      self.buf_len += take;
+      data = &data[take..];
+      if self.buf_len < SHA256_BLOCK_LENGTH {
+        return;
+      }
+      Self::compress(&mut self.h, &self.buf);
+      self.buf_len = 0;
+    }
+    let mut blocks = data.chunks_exact(SHA256_BLOCK_LENGTH);
+    for block in &mut blocks {
+      Self::compress(&mut self.h, block);
+    }
+    let rest = blocks.remainder();
+    self.buf[0..rest.len()].copy_from_slice(rest);
+    self.buf_len = rest.len();
+  }
+
+  fn digest(mut self) -> [u8; SHA256_LENGTH] {
+    let bit_len = self.total_len.wrapping_mul(8);
+    let mut padding = [0u8; SHA256_BLOCK_LENGTH + 8];
+    padding[0] = 0x80;
+    let pad_len = if self.buf_len < 56 { 56 - self.buf_len } else { 120 - self.buf_len };
+    padding[pad_len..(pad_len + 8)].copy_from_slice(&bit_len.to_be_bytes());
+    self.update(&padding[0..(pad_len + 8)]);
+    let mut out = [0u8; SHA256_LENGTH];
+    for (i, word) in self.h.iter().enumerate() {
+      out[(4*i)..(4*i + 4)].copy_from_slice(&word.to_be_bytes());
+    }
+    wipe_words(&mut self.h);
+    wipe(&mut self.buf);
+    out
+  }
+
+}
+
+/// HMAC-SHA256 contexts, keyed once, and cloned for every message.
+struct HmacSha256 {
+  inner: Sha256,
+  outer: Sha256
+}
+
+impl HmacSha256 {
+
+  fn new(key: &[u8]) -> Self {
+    let mut block_key = [0u8; SHA256_BLOCK_LENGTH];
+    if key.len() > SHA256_BLOCK_LENGTH {
+      let mut hasher = Sha256::new();
+      hasher.update(key);
+      block_key[0..SHA256_LENGTH].copy_from_slice(&hasher.digest());
+    } else {
+      block_key[0..key.len()].copy_from_slice(key);
+    }
+    let mut pad = [0u8; SHA256_BLOCK_LENGTH];
+    for i in 0..SHA256_BLOCK_LENGTH {
+      pad[i] = block_key[i] ^ 0x36;
+    }
+    let mut inner = Sha256::new();
+    inner.update(&pad);
+    for i in 0..SHA256_BLOCK_LENGTH {
+      pad[i] = block_key[i] ^ 0x5c;
+    }
+    let mut outer = Sha256::new();
+    outer.update(&pad);
+    wipe(&mut block_key);
+    wipe(&mut pad);
+    HmacSha256 { inner, outer }
+  }
+
+  fn mac(&self, inner: Sha256) -> [u8; SHA256_LENGTH] {
+    let mut inner_hash = inner.digest();
+    let mut outer = self.outer.clone();
+    outer.update(&inner_hash);
+    wipe(&mut inner_hash);
+    outer.digest()
+  }
+
+}
+
+/// PBKDF2-HMAC-SHA256 with one iteration, as scrypt uses it.
+fn pbkdf2_sha256_once(passwd: &[u8], salt: &[u8], out: &mut [u8]) {
+  let hmac = HmacSha256::new(passwd);
+  let mut salted = hmac.inner.clone();
+  salted.update(salt);
+  for (i, chunk) in out.chunks_mut(SHA256_LENGTH).enumerate() {
+    let mut inner = salted.clone();
+    inner.update(&((i as u32) + 1).to_be_bytes());
+    let mut t = hmac.mac(inner);
+    chunk.copy_from_slice(&t[0..chunk.len()]);
+    wipe(&mut t);
+  }
+}
+
+fn wipe_words(words: &mut [u32]) {
+  for w in words.iter_mut() {
+    unsafe { std::ptr::write_volatile(w, 0); }
+  }
+}
+
+fn salsa20_8(b: &mut [u32]) {
+  let mut x = [0u32; 16];
+  x.copy_from_slice(&b[0..16]);
+  macro_rules! quarter {
+    ($a:expr, $b:expr, $c:expr, $d:expr) => {
+      x[$b] ^= x[$a].wrapping_add(x[$d]).rotate_left(7);
+      x[$c] ^= x[$b].wrapping_add(x[$a]).rotate_left(9);
+      x[$d] ^= x[$c].wrapping_add(x[$b]).rotate_left(13);
+      x[$a] ^= x[$d].wrapping_add(x[$c]).rotate_left(18);
+    }
+  }
+  for _ in 0..4 {
+    quarter!(0, 4, 8, 12);
+    quarter!(5, 9, 13, 1);
+    quarter!(10, 14, 2, 6);
+    quarter!(15, 3, 7, 11);
+    quarter!(0, 1, 2, 3);
+    quarter!(5, 6, 7, 4);
+    quarter!(10, 11, 8, 9);
+    quarter!(15, 12, 13, 14);
+  }
+  for i in 0..16 {
+    b[i] = b[i].wrapping_add(x[i]);
+  }
+}
+
+/// BlockMix with salsa20/8 of block b with 32 * r words, using y of the same
+/// size for temporary storage.
+fn blockmix_salsa8(b: &mut [u32], y: &mut [u32], r: usize) {
+  let mut x = [0u32; 16];
+  x.copy_from_slice(&b[((2*r - 1)*16)..(2*r*16)]);
+  for i in 0..(2*r) {
+    for j in 0..16 {
+      x[j] ^= b[i*16 + j];
+    }
+    salsa20_8(&mut x);
+    y[(i*16)..(i*16 + 16)].copy_from_slice(&x);
+  }
+  for i in 0..r {
+    b[(i*16)..(i*16 + 16)].copy_from_slice(&y[(2*i*16)..(2*i*16 + 16)]);
+    b[((r + i)*16)..((r + i)*16 + 16)].copy_from_slice(&y[((2*i + 1)*16)..((2*i + 1)*16 + 16)]);
+  }
+  wipe_words(&mut x);
+}
+
+/// Progress in percents, reported like nacl's scrypt does, i.e. 3 after
+/// PBKDF2, then in steps over 2 * N * p mixing rounds, and 100 at the end.
+struct Progress<'a> {
+  completed: u32,
+  delta_percent: u32,
+  delta_n: u32,
+  cb: &'a dyn Fn(u32)
+}
+
+impl<'a> Progress<'a> {
+
+  fn new(n: u32, p: u32, start_percent: u32, cb: &'a dyn Fn(u32)) -> Self {
+    cb(start_percent);
+    let total_n = 2u32.wrapping_mul(n).wrapping_mul(p);
+    let total_percent = 100 - start_percent;
+    let (delta_n, delta_percent) = if total_n < total_percent {
+      (1, total_percent / total_n)
+    } else {
+      (total_n / total_percent, 1)
+    };
+    Progress { completed: start_percent, delta_percent, delta_n, cb }
+  }
+
+  fn add_delta(&mut self) {
+    if self.completed <= 100 - self.delta_percent {
+      self.completed += self.delta_percent;
+      (self.cb)(self.completed);
+    }
+  }
+
+}
+
+/// SMix of block b with 32 * r words, using v with 32 * r * n words and xy
+/// with 64 * r words for temporary storage.
+fn smix(b: &mut [u32], r: usize, n: usize, v: &mut [u32], xy: &mut [u32], progress: &mut Progress) {
+  let block_len = 32*r;
+  let (x, y) = xy.split_at_mut(block_len);
+  x.copy_from_slice(b);
+  let mut report_at = progress.delta_n;
+  for i in 0..n {
+    v[(i*block_len)..((i + 1)*block_len)].copy_from_slice(x);
+    blockmix_salsa8(x, y, r);
+    if (i as u32) == report_at {
+      progress.add_delta();
+      report_at = report_at.wrapping_add(progress.delta_n);
+    }
+  }
+  report_at = progress.delta_n;
+  for i in 0..n {
+    let j = (x[(2*r - 1)*16] as usize) & (n - 1);
+    for (xk, vk) in x.iter_mut().zip(&v[(j*block_len)..((j + 1)*block_len)]) {
+      *xk ^= *vk;
+    }
+    blockmix_salsa8(x, y, r);
+    if (i as u32) == report_at {
+      progress.add_delta();
+      report_at = report_at.wrapping_add(progress.delta_n);
+    }
+  }
+  b.copy_from_slice(x);
+}
+
+const R_TIMES_P_LIMIT: usize = 1 << 30;
+const DK_LEN_LIMIT: u64 = ((1u64 << 32) - 1) * 32;
+const MAX_LOG_N: u8 = 31;
+
+thread_local! {
+  /// Working memory, reserved on this thread by `reserve`.
+  static RESERVED: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+}
+
+fn conf_err(message: String) -> nacl::Error {
+  nacl::Error { condition: nacl::ErrorCondition::Configuration, message }
+}
+
+/// Number of words in working memory of scrypt with given parameters, i.e.
+/// 32 * r * 2^log_n, which is 128 * r * 2^log_n bytes.
+pub(crate) fn memory_words(log_n: u8, r: usize) -> Result<usize, nacl::Error> {
+  if log_n > MAX_LOG_N {
+    return Err(conf_err("logN is too big".to_string()));
+  }
+  1usize.checked_shl(log_n as u32)
+  .and_then(|n| n.checked_mul(r))
+  .and_then(|rn| rn.checked_mul(32))
+  .ok_or_else(|| conf_err(format!("Memory of scrypt with logN = {} and r = {} is too big", log_n, r)))
+}
+
+fn alloc_words(len: usize) -> Result<Vec<u32>, nacl::Error> {
+  let mut words = Vec::new();
+  words.try_reserve_exact(len).map_err(|_| conf_err(format!(
+    "Can't allocate {} bytes of scrypt memory", (len as u64) * 4
+  )))?;
+  words.resize(len, 0);
+  Ok(words)
+}
+
+/// Reserves scrypt working memory on calling thread for following calls with
+/// given log_n and r, replacing memory, reserved before. Returns error, when
+/// memory can't be allocated, keeping no reservation in this case.
+pub(crate) fn reserve(log_n: u8, r: usize) -> Result<(), nacl::Error> {
+  let len = memory_words(log_n, r)?;
+  RESERVED.with(|reserved| {
+    let mut reserved = reserved.borrow_mut();
+    if reserved.len() == len {
+      return Ok(());
+    }
+    *reserved = Vec::new();
+    *reserved = alloc_words(len)?;
+    Ok(())
+  })
+}
+
+/// Derives key with scrypt, taking working memory, reserved on this thread,
+/// when it fits given parameters, and allocating it otherwise. Parameters
+/// are checked like nacl's scrypt checks them, with the same messages.
+pub(crate) fn scrypt(
+  passwd: &[u8], salt: &[u8], log_n: u8, r: usize, p: usize, dk_len: usize, progress_cb: &dyn Fn(u32)
+) -> Result<Vec<u8>, nacl::Error> {
+  if (r == 0) || (p == 0) {
+    return Err(conf_err("Both r and p should be greater than zero".to_string()));
+  }
+  if r.checked_mul(p).is_none_or(|rp| rp >= R_TIMES_P_LIMIT) {
+    return Err(conf_err("r * p is too big".to_string()));
+  }
+  if (dk_len as u64) > DK_LEN_LIMIT {
+    return Err(conf_err("dkLen is too big".to_string()));
+  }
+  let v_len = memory_words(log_n, r)?;
+  let n = 1usize << log_n;
+  let block_len = 32*r;
+
+  let mut v = RESERVED.with(|reserved| {
+    let mut reserved = reserved.borrow_mut();
+    if reserved.len() == v_len {
+      Some(std::mem::take(&mut *reserved))
+    } else {
+      None
+    }
+  });
+  let is_reserved = v.is_some();
+  if v.is_none() {
+    v = Some(alloc_words(v_len)?);
+  }
+  let mut v = v.unwrap();
+
+  let mut b_bytes = vec![0u8; 128*r*p];
+  pbkdf2_sha256_once(passwd, salt, &mut b_bytes);
+  let mut b: Vec<u32> = b_bytes.chunks_exact(4)
+  .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+  .collect();
+  let mut xy = vec![0u32; 2*block_len];
+  let mut progress = Progress::new(n as u32, p as u32, 3, progress_cb);
+  for i in 0..p {
+    smix(&mut b[(i*block_len)..((i + 1)*block_len)], r, n, &mut v, &mut xy, &mut progress);
+  }
+  for (bytes, word) in b_bytes.chunks_exact_mut(4).zip(&b) {
+    bytes.copy_from_slice(&word.to_le_bytes());
+  }
+  let mut dk = vec![0u8; dk_len];
+  pbkdf2_sha256_once(passwd, &b_bytes, &mut dk);
+  progress_cb(100);
+
+  wipe(&mut b_bytes);
+  wipe_words(&mut b);
+  wipe_words(&mut xy);
+  wipe_words(&mut v);
+  if is_reserved {
+    RESERVED.with(|reserved| {
+      let mut reserved = reserved.borrow_mut();
+      if reserved.is_empty() {
+        *reserved = v;
+      }
+    });
+  }
+  Ok(dk)
+}
+
+#[cfg(test)]
+mod tests {
+
+  use std::cell::RefCell;
+  use super::*;
+  use crate::from_hex;
+
+  fn sha256(data: &[u8]) -> [u8; SHA256_LENGTH] {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.digest()
+  }
+
+  #[test]
+  fn sha256_vectors() {
+    // FIPS 180-2 examples
+    assert_eq!(sha256(b"abc").to_vec(), from_hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+    assert_eq!(
+      sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").to_vec(),
+      from_hex("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1")
+    );
+    assert_eq!(sha256(b"").to_vec(), from_hex("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"));
+    // updates in pieces match one update
+    let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    let mut hasher = Sha256::new();
+    for piece in data.chunks(37) {
+      hasher.update(piece);
+    }
+    assert_eq!(hasher.digest(), sha256(&data));
+  }
+
+  /// Passphrase, salt, log_n, r, p and hex of 64-byte key.
+  type Vector = (&'static [u8], &'static [u8], u8, usize, usize, &'static str);
+
+  #[test]
+  fn rfc7914_vectors() {
+    // section 12 of RFC 7914
+    let vectors: [Vector; 3] = [
+      (b"", b"", 4, 1, 1, concat!(
+        "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442",
+        "fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+      )),
+      (b"password", b"NaCl", 10, 8, 16, concat!(
+        "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162",
+        "2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+      )),
+      (b"pleaseletmein", b"SodiumChloride", 14, 8, 1, concat!(
+        "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2",
+        "d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887"
+      ))
+    ];
+    for (passwd, salt, log_n, r, p, dk) in vectors {
+      assert_eq!(scrypt(passwd, salt, log_n, r, p, 64, &|_| {}).unwrap(), from_hex(dk), "logN {}", log_n);
+    }
+  }
+
+  #[test]
+  fn keys_and_progress_match_nacl() {
+    for (log_n, r, p, dk_len) in [(1, 1, 1, 1), (4, 2, 3, 33), (6, 1, 1, 100), (8, 3, 2, 64)] {
+      let reports = RefCell::new(Vec::new());
+      let nacl_reports = RefCell::new(Vec::new());
+      let dk = scrypt(b"passwd", b"salt", log_n, r, p, dk_len, &|pr| reports.borrow_mut().push(pr)).unwrap();
+      let nacl_dk = nacl::scrypt(
+        b"passwd", b"salt", log_n, r, p, dk_len, &|pr| nacl_reports.borrow_mut().push(pr)
+      ).unwrap();
+      assert_eq!(dk, nacl_dk, "logN {}, r {}, p {}", log_n, r, p);
+      assert_eq!(reports.into_inner(), nacl_reports.into_inner(), "logN {}, r {}, p {}", log_n, r, p);
+    }
+  }
+
+  #[test]
+  fn reserved_memory_is_reused() {
+    let expected = nacl::scrypt(b"passwd", b"salt", 6, 2, 1, 32, &|_| {}).unwrap();
+    reserve(6, 2).unwrap();
+    let reserved_ptr = RESERVED.with(|reserved| reserved.borrow().as_ptr());
+    for _ in 0..3 {
+      assert_eq!(scrypt(b"passwd", b"salt", 6, 2, 1, 32, &|_| {}).unwrap(), expected);
+      RESERVED.with(|reserved| {
+        let reserved = reserved.borrow();
+        assert_eq!(reserved.as_ptr(), reserved_ptr, "the same memory is given back");
+        assert!(reserved.iter().all(|&w| w == 0), "memory is wiped");
+      });
+    }
+    // other parameters allocate their own memory, keeping reservation
+    assert_eq!(
+      scrypt(b"passwd", b"salt", 5, 2, 1, 32, &|_| {}).unwrap(),
+      nacl::scrypt(b"passwd", b"salt", 5, 2, 1, 32, &|_| {}).unwrap()
+    );
+    RESERVED.with(|reserved| assert_eq!(reserved.borrow().as_ptr(), reserved_ptr));
+    // reservations are per thread
+    std::thread::spawn(|| {
+      RESERVED.with(|reserved| assert!(reserved.borrow().is_empty()));
+    }).join().unwrap();
+  }
+
+  #[test]
+  fn bad_parameters_are_rejected() {
+    assert!(scrypt(b"p", b"s", 32, 1, 1, 32, &|_| {}).is_err());
+    assert!(scrypt(b"p", b"s", 4, 0, 1, 32, &|_| {}).is_err());
+    assert!(scrypt(b"p", b"s", 4, 1, 0, 32, &|_| {}).is_err());
+    assert!(scrypt(b"p", b"s", 4, 1 << 15, 1 << 15, 32, &|_| {}).is_err());
+    assert!(reserve(31, usize::MAX).is_err());
+    assert!(memory_words(31, usize::MAX / 2).is_err());
+  }
+
+}
@@ -0,0 +1,230 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encryption of streams, which content arrives in chunks, and which length
+//! isn't known upfront.
+//!
+//! Encrypted stream is a sequence of with-nonce secret boxes:
+//! - header box, with 5 bytes of content: segment size as big-endian u32,
+//!   followed by 1 for empty stream, or 0, otherwise;
+//! - segment boxes, each with `segment size` bytes of content, except the
+//!   last one, which may be shorter, but not empty.
+//!
+//! Nonces are those of encrypted files, i.e. i-th box uses nonce of header
+//! with i added to its first 8 bytes, except that the last box has the last
+//! byte of its nonce flipped. Hence, opening detects reordered, replaced and
+//! dropped segments, as well as truncation at any segment boundary.
+
+use nacl::secret_box::{ KEY_LENGTH, NONCE_LENGTH, POLY_LENGTH, format_wn };
+use crate::files::nonce_for_box;
+use crate::wipe;
+
+const HEADER_CONTENT_LENGTH: usize = 5;
+const BOX_OVERHEAD: usize = NONCE_LENGTH + POLY_LENGTH;
+const HEADER_BOX_LENGTH: usize = BOX_OVERHEAD + HEADER_CONTENT_LENGTH;
+
+fn conf_err(message: String) -> nacl::Error {
+  nacl::Error { condition: nacl::ErrorCondition::Configuration, message }
+}
+
+fn nonce_for_last_box(base: &[u8], i: u64) -> [u8; NONCE_LENGTH] {
+  let mut n = nonce_for_box(base, i);
+  n[NONCE_LENGTH - 1] ^= 1;
+  n
+}
+
+fn check_key(k: &[u8]) -> Result<(), nacl::Error> {
+  if k.len() != KEY_LENGTH {
+    return Err(conf_err(format!(
+      "Key k should have {} bytes, but it is {} bytes long.", KEY_LENGTH, k.len()
+    )));
+  }
+  Ok(())
+}
+
+pub(crate) struct SegmentsPacker {
+  k: Vec<u8>,
+  base_nonce: Vec<u8>,
+  segment_size: usize,
+  pending: Vec<u8>,
+  next_box: u64
+}
+
+impl Drop for SegmentsPacker {
+  fn drop(&mut self) {
+    wipe(&mut self.k);
+    wipe(&mut self.pending);
+  }
+}
+
+impl SegmentsPacker {
+
+  pub fn new(k: &[u8], segment_size: usize, base_nonce: Vec<u8>) -> Result<Self, nacl::Error> {
+    check_key(k)?;
+    if (segment_size == 0) || (segment_size > (u32::MAX as usize)) {
+      return Err(conf_err(format!(
+        "Segment size should be from 1 to {} bytes, but it is {}", u32::MAX, segment_size
+      )));
+    }
+    Ok(SegmentsPacker { k: k.to_vec(), base_nonce, segment_size, pending: Vec::new(), next_box: 0 })
+  }
+
+  fn pack_header(&mut self, is_empty: bool) -> Result<Vec<u8>, nacl::Error> {
+    let mut header = [0u8; HEADER_CONTENT_LENGTH];
+    header[0..4].copy_from_slice(&(self.segment_size as u32).to_be_bytes());
+    header[4] = if is_empty { 1 } else { 0 };
+    self.next_box = 1;
+    format_wn::pack(&header, &nonce_for_box(&self.base_nonce, 0), &self.k)
+  }
+
+  /// Takes next chunk of content, returning boxes, that are complete with it.
+  /// Last segment is always held back, till `finish`, as it is packed with
+  /// the last nonce.
+  pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<Vec<u8>>, nacl::Error> {
+    let mut boxes = Vec::new();
+    if chunk.is_empty() {
+      return Ok(boxes);
+    }
+    if self.next_box == 0 {
+      boxes.push(self.pack_header(false)?);
+    }
+    self.pending.extend_from_slice(chunk);
+    let mut start = 0;
+    while (self.pending.len() - start) > self.segment_size {
+      let end = start + self.segment_size;
+      boxes.push(format_wn::pack(
+        &self.pending[start..end], &nonce_for_box(&self.base_nonce, self.next_box), &self.k
+      )?);
+      self.next_box += 1;
+      start = end;
+    }
+    wipe(&mut self.pending[0..start]);
+    self.pending.drain(0..start);
+    Ok(boxes)
+  }
+
+  /// Returns the remaining boxes, ending encrypted stream.
+  pub fn finish(&mut self) -> Result<Vec<Vec<u8>>, nacl::Error> {
+    if self.next_box == 0 {
+      return Ok(vec![self.pack_header(true)?]);
+    }
+    let last_box = format_wn::pack(
+      &self.pending, &nonce_for_last_box(&self.base_nonce, self.next_box), &self.k
+    )?;
+    wipe(&mut self.pending);
+    self.pending.clear();
+    Ok(vec![last_box])
+  }
+
+}
+
+struct StreamHeader {
+  base_nonce: Vec<u8>,
+  segment_size: usize,
+  is_empty: bool
+}
+
+pub(crate) struct SegmentsOpener {
+  k: Vec<u8>,
+  header: Option<StreamHeader>,
+  pending: Vec<u8>,
+  next_box: u64
+}
+
+impl Drop for SegmentsOpener {
+  fn drop(&mut self) {
+    wipe(&mut self.k);
+  }
+}
+
+impl SegmentsOpener {
+
+  pub fn new(k: &[u8]) -> Result<Self, nacl::Error> {
+    check_key(k)?;
+    Ok(SegmentsOpener { k: k.to_vec(), header: None, pending: Vec::new(), next_box: 1 })
+  }
+
+  fn open_header(&mut self) -> Result<(), nacl::Error> {
+    let header_box = &self.pending[0..HEADER_BOX_LENGTH];
+    let header = format_wn::open(header_box, &self.k)?;
+    let base_nonce = format_wn::copy_nonce_from(header_box)?.to_vec();
+    let mut segment_size = [0u8; 4];
+    segment_size.copy_from_slice(&header[0..4]);
+    let segment_size = u32::from_be_bytes(segment_size) as usize;
+    if segment_size == 0 {
+      return Err(conf_err("Stream header has zero segment size".to_string()));
+    }
+    self.header = Some(StreamHeader { base_nonce, segment_size, is_empty: header[4] == 1 });
+    self.pending.drain(0..HEADER_BOX_LENGTH);
+    Ok(())
+  }
+
+  fn open_box(&self, segment_box: &[u8], expected_n: &[u8]) -> Result<Vec<u8>, nacl::Error> {
+    if !nacl::compare(format_wn::copy_nonce_from(segment_box)?, expected_n) {
+      return Err(nacl::Error {
+        condition: nacl::ErrorCondition::CipherVerification,
+        message: format!("Segment {} of stream is out of place", self.next_box)
+      });
+    }
+    format_wn::open(segment_box, &self.k)
+  }
+
+  /// Takes next chunk of encrypted stream, returning content of boxes, that
+  /// are complete with it. Box, that may be the last one, is held back, till
+  /// either more bytes come, or `finish` is called.
+  pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<Vec<u8>>, nacl::Error> {
+    self.pending.extend_from_slice(chunk);
+    if self.header.is_none() {
+      if self.pending.len() < HEADER_BOX_LENGTH {
+        return Ok(Vec::new());
+      }
+      self.open_header()?;
+    }
+    let header = self.header.as_ref().unwrap();
+    if header.is_empty && !self.pending.is_empty() {
+      return Err(conf_err("Empty stream has unexpected bytes after header".to_string()));
+    }
+    let box_len = BOX_OVERHEAD + header.segment_size;
+    let mut contents = Vec::new();
+    let mut start = 0;
+    while (self.pending.len() - start) > box_len {
+      let end = start + box_len;
+      let n = nonce_for_box(&header.base_nonce, self.next_box);
+      contents.push(self.open_box(&self.pending[start..end], &n)?);
+      self.next_box += 1;
+      start = end;
+    }
+    self.pending.drain(0..start);
+    Ok(contents)
+  }
+
+  /// Opens the last box, checking that stream ends here.
+  pub fn finish(&mut self) -> Result<Vec<Vec<u8>>, nacl::Error> {
+    let header = self.header.as_ref().ok_or_else(|| conf_err(
+      "Stream ended before its header".to_string()
+    ))?;
+    if header.is_empty {
+      return Ok(Vec::new());
+    }
+    if self.pending.len() <= BOX_OVERHEAD {
+      return Err(conf_err("Stream is truncated".to_string()));
+    }
+    let n = nonce_for_last_box(&header.base_nonce, self.next_box);
+    let content = self.open_box(&self.pending, &n)?;
+    self.pending.clear();
+    Ok(vec![content])
+  }
+
+}
@@ -0,0 +1,112 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Known-answer checks of signing, with Ed25519 test vectors from section 7.1
+//! of RFC 8032.
+
+use crate::from_hex;
+
+pub(crate) struct SignVector {
+  pub seed: Vec<u8>,
+  pub pkey: Vec<u8>,
+  pub m: Vec<u8>,
+  pub sig: Vec<u8>
+}
+
+/// Seed, public key, message and signature of tests 1, 2 and 3.
+const RFC8032_VECTORS: [[&str; 4]; 3] = [
+  [
+    "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+    "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+    "",
+    "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+  ],
+  [
+    "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+    "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+    "72",
+    "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+  ],
+  [
+    "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+    "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+    "af82",
+    "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a"
+  ]
+];
+
+pub(crate) fn rfc8032_vectors() -> Vec<SignVector> {
+  RFC8032_VECTORS.iter().map(|[seed, pkey, m, sig]| SignVector {
+    seed: from_hex(seed),
+    pkey: from_hex(pkey),
+    m: from_hex(m),
+    sig: from_hex(sig)
+  }).collect()
+}
+
+/// Checks that keypair, generated from seed, has expected public key, that
+/// signature is the expected one, that it verifies, and that signature of
+/// altered message doesn't verify. nacl refuses to sign empty messages, like
+/// one of test 1, hence signature of empty message is only verified.
+pub(crate) fn passes(v: &SignVector) -> bool {
+  if v.seed.len() != nacl::sign::SEED_LENGTH {
+    return false;
+  }
+  let keypair = nacl::sign::generate_keypair(&v.seed);
+  if !nacl::compare(&keypair.pkey, &v.pkey) {
+    return false;
+  }
+  if !v.m.is_empty() {
+    match nacl::sign::signature(&v.m, &keypair.skey) {
+      Ok(sig) if nacl::compare(&sig, &v.sig) => {},
+      _ => return false
+    }
+  }
+  if !matches!(nacl::sign::verify(&v.sig, &v.m, &v.pkey), Ok(true)) {
+    return false;
+  }
+  let mut altered = v.m.clone();
+  altered.push(0);
+  matches!(nacl::sign::verify(&v.sig, &altered, &v.pkey), Ok(false))
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+
+  #[test]
+  fn rfc8032_vectors_pass() {
+    let vectors = rfc8032_vectors();
+    assert_eq!(vectors.len(), 3);
+    assert!(vectors[0].m.is_empty());
+    for (i, v) in vectors.iter().enumerate() {
+      assert!(passes(v), "RFC 8032 test {}", i + 1);
+    }
+  }
+
+  #[test]
+  fn altered_vectors_fail() {
+    for i in 0..3 {
+      let mut v = rfc8032_vectors().remove(i);
+      v.sig[0] ^= 1;
+      assert!(!passes(&v), "altered signature of test {}", i + 1);
+      let mut v = rfc8032_vectors().remove(i);
+      v.pkey[0] ^= 1;
+      assert!(!passes(&v), "altered public key of test {}", i + 1);
+    }
+  }
+
+}
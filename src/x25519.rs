@@ -0,0 +1,317 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Raw X25519 scalar multiplication of RFC 7748, which nacl uses inside of
+//! public box, but doesn't expose. This is a port of TweetNaCl's
+//! crypto_scalarmult, with field elements as 16 limbs of 16 bits.
+
+pub(crate) const SCALAR_LENGTH: usize = 32;
+pub(crate) const POINT_LENGTH: usize = 32;
+
+type Gf = [i64; 16];
+
+const GF_121665: Gf = [0xdb41, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+const BASE_POINT: [u8; POINT_LENGTH] = {
+  let mut p = [0u8; POINT_LENGTH];
+  p[0] = 9;
+  p
+};
+
+fn car25519(o: &mut Gf) {
+  for i in 0..16 {
+    o[i] += 1 << 16;
+    let c = o[i] >> 16;
+    if i < 15 {
+      o[i + 1] += c - 1;
+    } else {
+      o[0] += 38 * (c - 1);
+    }
+    o[i] -= c << 16;
+  }
+}
+
+/// Swaps p and q in constant time, when b is 1.
+fn sel25519(p: &mut Gf, q: &mut Gf, b: i64) {
+  let c = !(b - 1);
+  for i in 0..16 {
+    let t = c & (p[i] ^ q[i]);
+    p[i] ^= t;
+    q[i] ^= t;
+  }
+}
+
+fn pack25519(o: &mut [u8; POINT_LENGTH], n: &Gf) {
+  let mut t = *n;
+  car25519(&mut t);
+  car25519(&mut t);
+  car25519(&mut t);
+  let mut m: Gf = [0; 16];
+  for _ in 0..2 {
+    m[0] = t[0] - 0xffed;
+    for i in 1..15 {
+      m[i] = t[i] - 0xffff - ((m[i - 1] >> 16) & 1);
+      m[i - 1] &= 0xffff;
+    }
+    m[15] = t[15] - 0x7fff - ((m[14] >> 16) & 1);
+    let b = (m[15] >> 16) & 1;
+    m[14] &= 0xffff;
+    sel25519(&mut t, &mut m, 1 - b);
+  }
+  for i in 0..16 {
+    o[2 * i] = (t[i] & 0xff) as u8;
+    o[2 * i + 1] = (t[i] >> 8) as u8;
+  }
+}
+
+fn unpack25519(n: &[u8]) -> Gf {
+  let mut o: Gf = [0; 16];
+  for i in 0..16 {
+    o[i] = (n[2 * i] as i64) + ((n[2 * i + 1] as i64) << 8);
+  }
+  o[15] &= 0x7fff;
+  o
+}
+
+fn add(a: &Gf, b: &Gf) -> Gf {
+  let mut o: Gf = [0; 16];
+  for i in 0..16 {
+    o[i] = a[i] + b[i];
+  }
+  o
+}
+
+fn sub(a: &Gf, b: &Gf) -> Gf {
+  let mut o: Gf = [0; 16];
+  for i in 0..16 {
+    o[i] = a[i] - b[i];
+  }
+  o
+}
+
+fn mul(a: &Gf, b: &Gf) -> Gf {
+  let mut t = [0i64; 31];
+  for i in 0..16 {
+    for j in 0..16 {
+      t[i + j] += a[i] * b[j];
+    }
+  }
+  for i in 0..15 {
+    t[i] += 38 * t[i + 16];
+  }
+  let mut o: Gf = [0; 16];
+  o.copy_from_slice(&t[0..16]);
+  car25519(&mut o);
+  car25519(&mut o);
+  o
+}
+
+fn square(a: &Gf) -> Gf {
+  mul(a, a)
+}
+
+fn inv25519(i: &Gf) -> Gf {
+  let mut c = *i;
+  for a in (0..254).rev() {
+    c = square(&c);
+    if a != 2 && a != 4 {
+      c = mul(&c, i);
+    }
+  }
+  c
+}
+
+fn conf_err(message: String) -> nacl::Error {
+  nacl::Error { condition: nacl::ErrorCondition::Configuration, message }
+}
+
+fn ladder(scalar: &[u8], point: &[u8]) -> [u8; POINT_LENGTH] {
+  let mut z = [0u8; SCALAR_LENGTH];
+  z.copy_from_slice(scalar);
+  z[31] = (z[31] & 127) | 64;
+  z[0] &= 248;
+  let x = unpack25519(point);
+  let mut a: Gf = [0; 16];
+  let mut b = x;
+  let mut c: Gf = [0; 16];
+  let mut d: Gf = [0; 16];
+  a[0] = 1;
+  d[0] = 1;
+  for i in (0..255).rev() {
+    let r = ((z[i >> 3] >> (i & 7)) & 1) as i64;
+    sel25519(&mut a, &mut b, r);
+    sel25519(&mut c, &mut d, r);
+    let e = add(&a, &c);
+    a = sub(&a, &c);
+    c = add(&b, &d);
+    b = sub(&b, &d);
+    d = square(&e);
+    let f = square(&a);
+    a = mul(&c, &a);
+    c = mul(&b, &e);
+    let e = add(&a, &c);
+    a = sub(&a, &c);
+    b = square(&a);
+    c = sub(&d, &f);
+    a = mul(&c, &GF_121665);
+    a = add(&a, &d);
+    c = mul(&c, &a);
+    a = mul(&d, &f);
+    d = mul(&b, &x);
+    b = square(&e);
+    sel25519(&mut a, &mut b, r);
+    sel25519(&mut c, &mut d, r);
+  }
+  let c = inv25519(&c);
+  let a = mul(&a, &c);
+  let mut q = [0u8; POINT_LENGTH];
+  pack25519(&mut q, &a);
+  crate::wipe(&mut z);
+  q
+}
+
+/// Multiplies point by scalar, clamping scalar as RFC 7748 requires, i.e.
+/// clearing its three lowest bits and its highest bit, and setting its
+/// second highest bit. Highest bit of point's u-coordinate is ignored.
+/// All-zero result, which comes from points of small order, is an error,
+/// like in libsodium's crypto_scalarmult.
+pub(crate) fn scalarmult(scalar: &[u8], point: &[u8]) -> Result<Vec<u8>, nacl::Error> {
+  if scalar.len() != SCALAR_LENGTH {
+    return Err(conf_err(format!(
+      "Scalar should have {} bytes, but it is {} bytes long.", SCALAR_LENGTH, scalar.len()
+    )));
+  }
+  if point.len() != POINT_LENGTH {
+    return Err(conf_err(format!(
+      "Point should have {} bytes, but it is {} bytes long.", POINT_LENGTH, point.len()
+    )));
+  }
+  let q = ladder(scalar, point);
+  if nacl::compare(&q, &[0u8; POINT_LENGTH]) {
+    return Err(conf_err("Result of scalar multiplication is zero, as point has small order".to_string()));
+  }
+  Ok(q.to_vec())
+}
+
+/// Multiplies standard base point 9 by scalar, clamping it like `scalarmult`.
+pub(crate) fn scalarmult_base(scalar: &[u8]) -> Result<Vec<u8>, nacl::Error> {
+  scalarmult(scalar, &BASE_POINT)
+}
+
+/// Converts Ed25519 public key into X25519 public key of the same secret, as
+/// u = (1 + y) / (1 - y) of point's y-coordinate, like libsodium's
+/// crypto_sign_ed25519_pk_to_curve25519, though without checking that key
+/// is a point on the curve. Key with y = 1, which gives zero, is an error.
+pub(crate) fn ed25519_pk_to_x25519(pk: &[u8]) -> Result<Vec<u8>, nacl::Error> {
+  if pk.len() != POINT_LENGTH {
+    return Err(conf_err(format!(
+      "Ed25519 public key should have {} bytes, but it is {} bytes long.", POINT_LENGTH, pk.len()
+    )));
+  }
+  let mut one: Gf = [0; 16];
+  one[0] = 1;
+  let y = unpack25519(pk);
+  let u = mul(&add(&one, &y), &inv25519(&sub(&one, &y)));
+  let mut q = [0u8; POINT_LENGTH];
+  pack25519(&mut q, &u);
+  if nacl::compare(&q, &[0u8; POINT_LENGTH]) {
+    return Err(conf_err("Ed25519 public key can't be converted, as it gives zero".to_string()));
+  }
+  Ok(q.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::from_hex;
+
+  #[test]
+  fn rfc7748_scalarmult_vectors() {
+    // section 5.2 of RFC 7748
+    let vectors = [
+      (
+        "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+        "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c",
+        "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552"
+      ),
+      (
+        "4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d",
+        "e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493",
+        "95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957"
+      )
+    ];
+    for (scalar, point, result) in vectors {
+      assert_eq!(scalarmult(&from_hex(scalar), &from_hex(point)).unwrap(), from_hex(result));
+    }
+  }
+
+  #[test]
+  fn rfc7748_iterated_scalarmult() {
+    // section 5.2 of RFC 7748, results after 1 and 1000 iterations
+    let mut k = BASE_POINT.to_vec();
+    let mut u = BASE_POINT.to_vec();
+    for i in 1..=1000 {
+      let r = scalarmult(&k, &u).unwrap();
+      u = k;
+      k = r;
+      if i == 1 {
+        assert_eq!(k, from_hex("422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079"));
+      }
+    }
+    assert_eq!(k, from_hex("684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51"));
+  }
+
+  #[test]
+  fn rfc7748_diffie_hellman() {
+    // section 6.1 of RFC 7748
+    let alice_sk = from_hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+    let alice_pk = from_hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+    let bob_sk = from_hex("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+    let bob_pk = from_hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+    let shared = from_hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+    assert_eq!(scalarmult_base(&alice_sk).unwrap(), alice_pk);
+    assert_eq!(scalarmult_base(&bob_sk).unwrap(), bob_pk);
+    assert_eq!(scalarmult(&alice_sk, &bob_pk).unwrap(), shared);
+    assert_eq!(scalarmult(&bob_sk, &alice_pk).unwrap(), shared);
+  }
+
+  #[test]
+  fn base_mult_matches_nacl_public_box() {
+    for i in 0..20u8 {
+      let sk: Vec<u8> = (0..32u8).map(|j| j.wrapping_mul(31).wrapping_add(i.wrapping_mul(97))).collect();
+      assert_eq!(scalarmult_base(&sk).unwrap(), nacl::public_box::generate_pubkey(&sk).unwrap());
+    }
+  }
+
+  #[test]
+  fn small_order_point_is_rejected() {
+    let sk = from_hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+    assert!(scalarmult(&sk, &[0u8; POINT_LENGTH]).is_err());
+    let mut one = [0u8; POINT_LENGTH];
+    one[0] = 1;
+    assert!(scalarmult(&sk, &one).is_err());
+  }
+
+  #[test]
+  fn ed25519_pk_converts_to_x25519_pk_of_same_secret() {
+    for v in crate::sign_vectors::rfc8032_vectors() {
+      let mut h = [0u8; 64];
+      nacl::sha512::hash_sha512(&mut h, &v.seed);
+      assert_eq!(ed25519_pk_to_x25519(&v.pkey).unwrap(), scalarmult_base(&h[0..32]).unwrap());
+    }
+  }
+
+}
@@ -0,0 +1,437 @@
+// Copyright(c) 2025 - 2026 3NSoft Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Parts of secret box, i.e. of xsalsa20 with poly1305, which nacl uses, but
+//! doesn't expose. These allow checking authenticator of a secret box without
+//! decrypting it.
+
+use nacl::secret_box::{ KEY_LENGTH, NONCE_LENGTH, POLY_LENGTH };
+use crate::wipe;
+
+const SIGMA: &[u8; 16] = b"expand 32-byte k";
+
+fn le32(b: &[u8]) -> u32 {
+  u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+  x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+  x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+  x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+  x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+}
+
+fn salsa20_rounds(x: &mut [u32; 16]) {
+  for _ in 0..10 {
+    quarter_round(x, 0, 4, 8, 12);
+    quarter_round(x, 5, 9, 13, 1);
+    quarter_round(x, 10, 14, 2, 6);
+    quarter_round(x, 15, 3, 7, 11);
+    quarter_round(x, 0, 1, 2, 3);
+    quarter_round(x, 5, 6, 7, 4);
+    quarter_round(x, 10, 11, 8, 9);
+    quarter_round(x, 15, 12, 13, 14);
+  }
+}
+
+/// Salsa20 input with given key and 16 bytes, that go into words 6 to 9.
+fn salsa20_input(k: &[u8], input: &[u8]) -> [u32; 16] {
+  let mut x = [0u32; 16];
+  for i in 0..4 {
+    x[[0, 5, 10, 15][i]] = le32(&SIGMA[(4 * i)..]);
+    x[1 + i] = le32(&k[(4 * i)..]);
+    x[11 + i] = le32(&k[(16 + 4 * i)..]);
+    x[6 + i] = le32(&input[(4 * i)..]);
+  }
+  x
+}
+
+fn hsalsa20(k: &[u8], n: &[u8]) -> [u8; KEY_LENGTH] {
+  let mut x = salsa20_input(k, n);
+  salsa20_rounds(&mut x);
+  let mut out = [0u8; KEY_LENGTH];
+  for (i, w) in [0, 5, 10, 15, 6, 7, 8, 9].into_iter().enumerate() {
+    out[(4 * i)..(4 * i + 4)].copy_from_slice(&x[w].to_le_bytes());
+  }
+  wipe_words(&mut x);
+  out
+}
+
+fn wipe_words(x: &mut [u32; 16]) {
+  for w in x.iter_mut() {
+    unsafe { std::ptr::write_volatile(w, 0); }
+  }
+}
+
+/// Block of salsa20 keystream with given key, 8-byte nonce, and counter.
+fn salsa20_block(k: &[u8], n: &[u8], counter: u64) -> [u8; 64] {
+  let mut input = [0u8; 16];
+  input[0..8].copy_from_slice(n);
+  input[8..16].copy_from_slice(&counter.to_le_bytes());
+  let mut x = salsa20_input(k, &input);
+  let initial = x;
+  salsa20_rounds(&mut x);
+  let mut block = [0u8; 64];
+  for i in 0..16 {
+    block[(4 * i)..(4 * i + 4)].copy_from_slice(&x[i].wrapping_add(initial[i]).to_le_bytes());
+  }
+  wipe_words(&mut x);
+  block
+}
+
+/// Returns 64-byte block of xsalsa20 keystream with given block counter.
+pub(crate) fn xsalsa20_block(n: &[u8], k: &[u8], counter: u64) -> [u8; 64] {
+  let mut subkey = hsalsa20(k, &n[0..16]);
+  let block = salsa20_block(&subkey, &n[16..24], counter);
+  wipe(&mut subkey);
+  block
+}
+
+/// XORs data with xsalsa20 keystream, starting at byte `stream_pos` of it.
+pub(crate) fn xor_keystream(n: &[u8], k: &[u8], stream_pos: u64, data: &mut [u8]) {
+  let mut subkey = hsalsa20(k, &n[0..16]);
+  let mut counter = stream_pos / 64;
+  let mut skip = (stream_pos % 64) as usize;
+  let mut done = 0;
+  while done < data.len() {
+    let mut block = salsa20_block(&subkey, &n[16..24], counter);
+    let len = (64 - skip).min(data.len() - done);
+    for i in 0..len {
+      data[done + i] ^= block[skip + i];
+    }
+    wipe(&mut block);
+    done += len;
+    skip = 0;
+    counter += 1;
+  }
+  wipe(&mut subkey);
+}
+
+/// Poly1305 authenticator of message m with one-time key, done with 26-bit
+/// limbs, like in poly1305-donna.
+fn poly1305(m: &[u8], key: &[u8]) -> [u8; POLY_LENGTH] {
+  const MASK: u32 = 0x3ffffff;
+  let r0 = le32(&key[0..]) & 0x3ffffff;
+  let r1 = (le32(&key[3..]) >> 2) & 0x3ffff03;
+  let r2 = (le32(&key[6..]) >> 4) & 0x3ffc0ff;
+  let r3 = (le32(&key[9..]) >> 6) & 0x3f03fff;
+  let r4 = (le32(&key[12..]) >> 8) & 0x00fffff;
+  let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+  let (mut h0, mut h1, mut h2, mut h3, mut h4) = (0u32, 0u32, 0u32, 0u32, 0u32);
+
+  for chunk in m.chunks(16) {
+    let mut block = [0u8; 17];
+    block[0..chunk.len()].copy_from_slice(chunk);
+    let hibit = if chunk.len() == 16 {
+      1 << 24
+    } else {
+      block[chunk.len()] = 1;
+      0
+    };
+    h0 += le32(&block[0..]) & MASK;
+    h1 += (le32(&block[3..]) >> 2) & MASK;
+    h2 += (le32(&block[6..]) >> 4) & MASK;
+    h3 += (le32(&block[9..]) >> 6) & MASK;
+    h4 += (le32(&block[12..]) >> 8) | hibit;
+
+    let mul = |a: u32, b: u32| (a as u64) * (b as u64);
+    let d0 = mul(h0, r0) + mul(h1, s4) + mul(h2, s3) + mul(h3, s2) + mul(h4, s1);
+    let mut d1 = mul(h0, r1) + mul(h1, r0) + mul(h2, s4) + mul(h3, s3) + mul(h4, s2);
+    let mut d2 = mul(h0, r2) + mul(h1, r1) + mul(h2, r0) + mul(h3, s4) + mul(h4, s3);
+    let mut d3 = mul(h0, r3) + mul(h1, r2) + mul(h2, r1) + mul(h3, r0) + mul(h4, s4);
+    let mut d4 = mul(h0, r4) + mul(h1, r3) + mul(h2, r2) + mul(h3, r1) + mul(h4, r0);
+
+    d1 += d0 >> 26;
+    h0 = (d0 as u32) & MASK;
+    d2 += d1 >> 26;
+    h1 = (d1 as u32) & MASK;
+    d3 += d2 >> 26;
+    h2 = (d2 as u32) & MASK;
+    d4 += d3 >> 26;
+    h3 = (d3 as u32) & MASK;
+    let c = (d4 >> 26) as u32;
+    h4 = (d4 as u32) & MASK;
+    h0 += c * 5;
+    h1 += h0 >> 26;
+    h0 &= MASK;
+  }
+
+  let mut c = h1 >> 26; h1 &= MASK;
+  h2 += c; c = h2 >> 26; h2 &= MASK;
+  h3 += c; c = h3 >> 26; h3 &= MASK;
+  h4 += c; c = h4 >> 26; h4 &= MASK;
+  h0 += c * 5; c = h0 >> 26; h0 &= MASK;
+  h1 += c;
+
+  let mut g0 = h0.wrapping_add(5); c = g0 >> 26; g0 &= MASK;
+  let mut g1 = h1.wrapping_add(c); c = g1 >> 26; g1 &= MASK;
+  let mut g2 = h2.wrapping_add(c); c = g2 >> 26; g2 &= MASK;
+  let mut g3 = h3.wrapping_add(c); c = g3 >> 26; g3 &= MASK;
+  let mut g4 = h4.wrapping_add(c).wrapping_sub(1 << 26);
+
+  let mut select = (g4 >> 31).wrapping_sub(1);
+  g0 &= select; g1 &= select; g2 &= select; g3 &= select; g4 &= select;
+  select = !select;
+  h0 = (h0 & select) | g0;
+  h1 = (h1 & select) | g1;
+  h2 = (h2 & select) | g2;
+  h3 = (h3 & select) | g3;
+  h4 = (h4 & select) | g4;
+
+  let h0 = h0 | (h1 << 26);
+  let h1 = (h1 >> 6) | (h2 << 20);
+  let h2 = (h2 >> 12) | (h3 << 14);
+  let h3 = (h3 >> 18) | (h4 << 8);
+
+  let mut f = (h0 as u64) + (le32(&key[16..]) as u64);
+  let mut tag = [0u8; POLY_LENGTH];
+  tag[0..4].copy_from_slice(&(f as u32).to_le_bytes());
+  f = (h1 as u64) + (le32(&key[20..]) as u64) + (f >> 32);
+  tag[4..8].copy_from_slice(&(f as u32).to_le_bytes());
+  f = (h2 as u64) + (le32(&key[24..]) as u64) + (f >> 32);
+  tag[8..12].copy_from_slice(&(f as u32).to_le_bytes());
+  f = (h3 as u64) + (le32(&key[28..]) as u64) + (f >> 32);
+  tag[12..16].copy_from_slice(&(f as u32).to_le_bytes());
+  tag
+}
+
+fn conf_err(message: String) -> nacl::Error {
+  nacl::Error { condition: nacl::ErrorCondition::Configuration, message }
+}
+
+fn check_nonce_and_key(n: &[u8], k: &[u8]) -> Result<(), nacl::Error> {
+  if n.len() != NONCE_LENGTH {
+    return Err(conf_err(format!(
+      "Nonce array n should have {} bytes in it, but it is {} bytes long.", NONCE_LENGTH, n.len()
+    )));
+  }
+  if k.len() != KEY_LENGTH {
+    return Err(conf_err(format!(
+      "Key array k should have {} bytes in it, but it is {} bytes long.", KEY_LENGTH, k.len()
+    )));
+  }
+  Ok(())
+}
+
+/// Checks poly1305 authenticator of secret box cipher c, in nacl's layout of
+/// authenticator followed by encrypted message, without decrypting it.
+pub(crate) fn verify_tag(c: &[u8], n: &[u8], k: &[u8]) -> Result<bool, nacl::Error> {
+  if c.len() < POLY_LENGTH {
+    return Err(conf_err(format!(
+      "Array c should have at least {} bytes in it, but it is only {} bytes long.", POLY_LENGTH, c.len()
+    )));
+  }
+  check_nonce_and_key(n, k)?;
+  let mut first_block = xsalsa20_block(n, k, 0);
+  let tag = poly1305(&c[POLY_LENGTH..], &first_block[0..32]);
+  wipe(&mut first_block);
+  Ok(nacl::compare_v16(&tag, &c[0..POLY_LENGTH]))
+}
+
+/// Secret box of an empty message, which is just an authenticator, as nacl
+/// refuses to pack empty messages.
+pub(crate) fn pack_empty(n: &[u8], k: &[u8]) -> Result<Vec<u8>, nacl::Error> {
+  check_nonce_and_key(n, k)?;
+  let mut first_block = xsalsa20_block(n, k, 0);
+  let tag = poly1305(&[], &first_block[0..32]);
+  wipe(&mut first_block);
+  Ok(tag.to_vec())
+}
+
+/// Opens secret box of an empty message, made by `pack_empty`.
+pub(crate) fn open_empty(c: &[u8], n: &[u8], k: &[u8]) -> Result<Vec<u8>, nacl::Error> {
+  if verify_tag(c, n, k)? {
+    Ok(Vec::new())
+  } else {
+    Err(nacl::Error {
+      condition: nacl::ErrorCondition::CipherVerification,
+      message: "Cipher bytes fail verification.".to_string()
+    })
+  }
+}
+
+/// Decrypts bytes `start..end` of message from secret box cipher c, in nacl's
+/// layout, without checking authenticator. In secret box, the first 32 bytes
+/// of keystream make poly1305 key, and message is encrypted with the rest.
+pub(crate) fn decrypt_part(c: &[u8], n: &[u8], k: &[u8], start: usize, end: usize) -> Vec<u8> {
+  let mut m = c[(POLY_LENGTH + start)..(POLY_LENGTH + end)].to_vec();
+  xor_keystream(n, k, 32 + (start as u64), &mut m);
+  m
+}
+
+/// Decrypts `len` bytes of message from given offset, like `decrypt_part`,
+/// checking nonce, key and that range is within message, but not
+/// authenticator.
+pub(crate) fn open_range(
+  c: &[u8], n: &[u8], k: &[u8], offset: usize, len: usize
+) -> Result<Vec<u8>, nacl::Error> {
+  if c.len() < POLY_LENGTH {
+    return Err(conf_err(format!(
+      "Array c should have at least {} bytes in it, but it is only {} bytes long.", POLY_LENGTH, c.len()
+    )));
+  }
+  check_nonce_and_key(n, k)?;
+  let m_len = c.len() - POLY_LENGTH;
+  match offset.checked_add(len) {
+    Some(end) if end <= m_len => Ok(decrypt_part(c, n, k, offset, end)),
+    _ => Err(conf_err(format!(
+      "Range of {} bytes at offset {} is outside of message of {} bytes", len, offset, m_len
+    )))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::from_hex;
+
+  /// Key, nonce, message and cipher of secretbox test in NaCl's tests, which
+  /// libsodium also has. Cipher is in nacl's layout, i.e. poly1305 tag,
+  /// followed by encrypted message.
+  const NACL_KEY: &str = "1b27556473e985d462cd51197a9a46c76009549eac6474f206c4ee0844f68389";
+  const NACL_NONCE: &str = "69696ee955b62b73cd62bda875fc73d68219e0036b7a0b37";
+  const NACL_MESSAGE: &str = concat!(
+    "be075fc53c81f2d5cf141316ebeb0c7b5228c52a4c62cbd44b66849b64244ffc",
+    "e5ecbaaf33bd751a1ac728d45e6c61296cdc3c01233561f41db66cce314adb31",
+    "0e3be8250c46f06dceea3a7fa1348057e2f6556ad6b1318a024a838f21af1fde",
+    "048977eb48f59ffd4924ca1c60902e52f0a089bc76897040e082f93776384864",
+    "5e0705"
+  );
+  const NACL_CIPHER: &str = concat!(
+    "f3ffc7703f9400e52a7dfb4b3d3305d9",
+    "8e993b9f48681273c29650ba32fc76ce48332ea7164d96a4476fb8c531a1186a",
+    "c0dfc17c98dce87b4da7f011ec48c97271d2c20f9b928fe2270d6fb863d51738",
+    "b48eeee314a7cc8ab932164548e526ae90224368517acfeabd6bb3732bc0e9da",
+    "99832b61ca01b6de56244a9e88d5f9b37973f622a43d14a6599b1f654cb45a74",
+    "e355a5"
+  );
+
+  /// Deterministic bytes for tests, from xorshift64.
+  fn test_bytes(seed: u64, len: usize) -> Vec<u8> {
+    let mut x = seed | 1;
+    (0..len).map(|_| {
+      x ^= x << 13;
+      x ^= x >> 7;
+      x ^= x << 17;
+      x as u8
+    }).collect()
+  }
+
+  #[test]
+  fn hsalsa20_gives_nacl_first_key() {
+    // core3 test of NaCl: DH shared key and zero input give secretbox key
+    let shared = from_hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+    assert_eq!(hsalsa20(&shared, &[0u8; 16]).to_vec(), from_hex(NACL_KEY));
+  }
+
+  #[test]
+  fn poly1305_rfc8439_vectors() {
+    // section 2.5.2 and vectors 1, 5 to 10 of appendix A.3 of RFC 8439
+    let zeros = "00".repeat(16);
+    let vectors = [
+      (
+        "85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b".to_string(),
+        crate::to_hex(b"Cryptographic Forum Research Group"),
+        "a8061dc1305136c6c22b8baf0c0127a9".to_string()
+      ),
+      ("00".repeat(32), "00".repeat(64), zeros.clone()),
+      (format!("02{}{}", "00".repeat(15), zeros), "ff".repeat(16), format!("03{}", "00".repeat(15))),
+      (
+        format!("02{}{}", "00".repeat(15), "ff".repeat(16)),
+        format!("02{}", "00".repeat(15)),
+        format!("03{}", "00".repeat(15))
+      ),
+      (
+        format!("01{}{}", "00".repeat(15), zeros),
+        format!("{}f0{}11{}", "ff".repeat(16), "ff".repeat(15), "00".repeat(15)),
+        format!("05{}", "00".repeat(15))
+      ),
+      (
+        format!("01{}{}", "00".repeat(15), zeros),
+        format!("{}fb{}{}", "ff".repeat(16), "fe".repeat(15), "01".repeat(16)),
+        zeros.clone()
+      ),
+      (format!("02{}{}", "00".repeat(15), zeros), format!("fd{}", "ff".repeat(15)), format!("fa{}", "ff".repeat(15))),
+      (
+        format!("01000000000000000400000000000000{}", zeros),
+        concat!(
+          "e33594d7505e43b90000000000000000", "3394d7505e4379cd0100000000000000",
+          "00000000000000000000000000000000", "01000000000000000000000000000000"
+        ).to_string(),
+        "14000000000000005500000000000000".to_string()
+      )
+    ];
+    for (key, m, tag) in vectors.iter() {
+      assert_eq!(poly1305(&from_hex(m), &from_hex(key)).to_vec(), from_hex(tag), "key {}", key);
+    }
+  }
+
+  #[test]
+  fn secretbox_nacl_vector() {
+    let (k, n, m) = (from_hex(NACL_KEY), from_hex(NACL_NONCE), from_hex(NACL_MESSAGE));
+    let c = from_hex(NACL_CIPHER);
+    assert_eq!(nacl::secret_box::pack(&m, &n, &k).unwrap(), c);
+    assert!(verify_tag(&c, &n, &k).unwrap());
+    assert_eq!(decrypt_part(&c, &n, &k, 0, m.len()), m);
+    let mut forged = c.clone();
+    forged[POLY_LENGTH + 10] ^= 1;
+    assert!(!verify_tag(&forged, &n, &k).unwrap());
+  }
+
+  #[test]
+  fn parts_match_nacl_secret_box() {
+    for (i, len) in [1usize, 15, 16, 17, 31, 32, 33, 63, 64, 65, 100, 1000, 4097].into_iter().enumerate() {
+      let seed = i as u64 + 1;
+      let k = test_bytes(seed, KEY_LENGTH);
+      let n = test_bytes(seed + 100, NONCE_LENGTH);
+      let m = test_bytes(seed + 200, len);
+      let c = nacl::secret_box::pack(&m, &n, &k).unwrap();
+      assert!(verify_tag(&c, &n, &k).unwrap(), "tag of {} bytes message", len);
+      let opened = nacl::secret_box::open(&c, &n, &k).unwrap();
+      for (start, end) in [(0, len), (0, len / 2), (len / 3, len), (len / 2, len / 2 + 1)] {
+        if end > len || start > end { continue; }
+        assert_eq!(decrypt_part(&c, &n, &k, start, end), &opened[start..end]);
+        assert_eq!(open_range(&c, &n, &k, start, end - start).unwrap(), &opened[start..end]);
+      }
+      assert!(open_range(&c, &n, &k, len, 1).is_err());
+    }
+  }
+
+  #[test]
+  fn range_of_cipher_shorter_than_tag_is_rejected() {
+    let (k, n) = (test_bytes(1, KEY_LENGTH), test_bytes(2, NONCE_LENGTH));
+    let err = open_range(&[0; 5], &n, &k, 0, 0).err().unwrap();
+    assert!(matches!(err.condition, nacl::ErrorCondition::Configuration));
+    assert!(open_range(&[0; POLY_LENGTH], &n, &k, 0, 0).unwrap().is_empty());
+  }
+
+  #[test]
+  fn empty_box_is_tag_of_keystream_key() {
+    let (k, n) = (from_hex(NACL_KEY), from_hex(NACL_NONCE));
+    let c = pack_empty(&n, &k).unwrap();
+    assert_eq!(c.len(), POLY_LENGTH);
+    // nacl's box of one-byte message has poly1305 key from the same keystream
+    let one = nacl::secret_box::pack(&[7], &n, &k).unwrap();
+    let key = xsalsa20_block(&n, &k, 0);
+    assert_eq!(poly1305(&one[POLY_LENGTH..], &key[0..32]).to_vec(), &one[0..POLY_LENGTH]);
+    assert_eq!(poly1305(&[], &key[0..32]).to_vec(), c);
+    assert_eq!(open_empty(&c, &n, &k).unwrap(), Vec::<u8>::new());
+    let mut forged = c.clone();
+    forged[0] ^= 1;
+    assert!(open_empty(&forged, &n, &k).is_err());
+  }
+
+}